@@ -0,0 +1,478 @@
+//! Concentrated liquidity pools with tick crossing (Whirlpool and Raydium
+//! CLMM math)
+//!
+//! Prices are Q64.64 square roots of the price of token A in token B. Swaps
+//! walk the initialized ticks in swap direction, adjusting the active
+//! liquidity by each tick's net liquidity as they cross it. Both programs
+//! share the swap step math but round their tick-to-price tables
+//! differently, see [`TickMath`].
+
+use crate::math::{mul_div_floor, to_u64, U256};
+use crate::{price_impact_bps, Fee, Quote, QuoteError, Result};
+
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+/// Sqrt price at [`MIN_TICK`]
+pub const MIN_SQRT_PRICE: u128 = 4_295_048_016;
+/// Sqrt price at [`MAX_TICK`]
+pub const MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_992_447_579_055;
+/// Sqrt price at [`MAX_TICK`] in Raydium's table
+pub const RAYDIUM_MAX_SQRT_PRICE: u128 = 79_226_673_521_066_979_257_578_248_091;
+
+const Q64: u128 = 1 << 64;
+const Q96: u128 = 1 << 96;
+
+/// `sqrt(1.0001^(2^i))` in Q96, for positive ticks
+const POSITIVE_RATIOS: [u128; 19] = [
+    79_232_123_823_359_799_118_286_999_567,
+    79_236_085_330_515_764_027_303_304_731,
+    79_244_008_939_048_815_603_706_035_061,
+    79_259_858_533_276_714_757_314_932_305,
+    79_291_567_232_598_584_799_939_703_904,
+    79_355_022_692_464_371_645_785_046_466,
+    79_482_085_999_252_804_386_437_311_141,
+    79_736_823_300_114_093_921_829_183_326,
+    80_248_749_790_819_932_309_965_073_892,
+    81_282_483_887_344_747_381_513_967_011,
+    83_390_072_131_320_151_908_154_831_281,
+    87_770_609_709_833_776_024_991_924_138,
+    97_234_110_755_111_693_312_479_820_773,
+    119_332_217_159_966_728_226_237_229_890,
+    179_736_315_981_702_064_433_883_588_727,
+    407_748_233_172_238_350_107_850_275_304,
+    2_098_478_828_474_011_932_436_660_412_517,
+    55_581_415_166_113_811_149_459_800_483_533,
+    38_992_368_544_603_139_932_233_054_999_993_551,
+];
+
+/// `sqrt(1.0001^-(2^i))` in Q64, for negative ticks
+const NEGATIVE_RATIOS: [u128; 19] = [
+    18_445_821_805_675_392_311,
+    18_444_899_583_751_176_498,
+    18_443_055_278_223_354_162,
+    18_439_367_220_385_604_838,
+    18_431_993_317_065_449_817,
+    18_417_254_355_718_160_513,
+    18_387_811_781_193_591_352,
+    18_329_067_761_203_520_168,
+    18_212_142_134_806_087_854,
+    17_980_523_815_641_551_639,
+    17_526_086_738_831_147_013,
+    16_651_378_430_235_024_244,
+    15_030_750_278_693_429_944,
+    12_247_334_978_882_834_399,
+    8_131_365_268_884_726_200,
+    3_584_323_654_723_342_297,
+    696_457_651_847_595_233,
+    26_294_789_957_452_057,
+    37_481_735_321_082,
+];
+
+/// `sqrt(1.0001^-(2^i))` in Q64 as Raydium rounds it, for all ticks
+const RAYDIUM_RATIOS: [u128; 19] = [
+    0xfffc_b933_bd6f_b800,
+    0xfff9_7272_373d_4000,
+    0xfff2_e50f_5f65_7000,
+    0xffe5_caca_7e10_f000,
+    0xffcb_9843_d60f_7000,
+    0xff97_3b41_fa98_e800,
+    0xff2e_a164_66c9_b000,
+    0xfe5d_ee04_6a9a_3800,
+    0xfcbe_86c7_900b_b000,
+    0xf987_a725_3ac6_5800,
+    0xf339_2b08_22bb_6000,
+    0xe715_9475_a2ca_f000,
+    0xd097_f3bd_fd2f_2000,
+    0xa9f7_4646_2d9f_8000,
+    0x70d8_69a1_56f3_1c00,
+    0x31be_135f_97ed_3200,
+    0x09aa_508b_5b85_a500,
+    0x005d_6af8_dedc_582c,
+    0x0000_2216_e584_f5fa,
+];
+
+/// Tick-to-price table of the program owning a pool. Tick crossings happen
+/// at the pool's own table's prices for quotes to match execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickMath {
+    Whirlpool,
+    Raydium,
+}
+
+impl TickMath {
+    /// Q64.64 sqrt price at a tick
+    pub fn sqrt_price_at_tick(self, tick: i32) -> Result<u128> {
+        match self {
+            TickMath::Whirlpool => sqrt_price_at_tick(tick),
+            TickMath::Raydium => raydium_sqrt_price_at_tick(tick),
+        }
+    }
+
+    pub fn min_sqrt_price(self) -> u128 {
+        MIN_SQRT_PRICE
+    }
+
+    pub fn max_sqrt_price(self) -> u128 {
+        match self {
+            TickMath::Whirlpool => MAX_SQRT_PRICE,
+            TickMath::Raydium => RAYDIUM_MAX_SQRT_PRICE,
+        }
+    }
+}
+
+/// Initialized tick the swap may cross, with the liquidity added when
+/// crossing it left to right
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickCrossing {
+    pub tick_index: i32,
+    pub liquidity_net: i128,
+}
+
+/// Live state of a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClmmPool {
+    pub sqrt_price: u128,
+    pub liquidity: u128,
+    pub fee: Fee,
+    pub tick_math: TickMath,
+}
+
+/// Quote of a swap walking the pool's ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClmmQuote {
+    /// `amount_in` is below the requested amount when the price limit or
+    /// the end of the given ticks' liquidity was reached first
+    pub quote: Quote,
+    pub sqrt_price_after: u128,
+    pub liquidity_after: u128,
+    pub ticks_crossed: u32,
+}
+
+/// Q64.64 sqrt price at a tick, `sqrt(1.0001^tick)`, as Whirlpool computes it
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(QuoteError::InvalidTick);
+    }
+    let abs_tick = tick.unsigned_abs();
+
+    if tick >= 0 {
+        let mut ratio = U256::from_u128(if abs_tick & 1 != 0 {
+            POSITIVE_RATIOS[0]
+        } else {
+            Q96
+        });
+        for (bit, factor) in POSITIVE_RATIOS.iter().enumerate().skip(1) {
+            if abs_tick & (1 << bit) != 0 {
+                ratio = ratio
+                    .checked_mul_u128(*factor)
+                    .ok_or(QuoteError::Overflow)?
+                    >> 96;
+            }
+        }
+        (ratio >> 32).to_u128().ok_or(QuoteError::Overflow)
+    } else {
+        let mut ratio = if abs_tick & 1 != 0 {
+            NEGATIVE_RATIOS[0]
+        } else {
+            Q64
+        };
+        for (bit, factor) in NEGATIVE_RATIOS.iter().enumerate().skip(1) {
+            if abs_tick & (1 << bit) != 0 {
+                ratio = (U256::mul(ratio, *factor) >> 64).low_u128();
+            }
+        }
+        Ok(ratio)
+    }
+}
+
+/// Q64.64 sqrt price at a tick as Raydium CLMM computes it: the negative
+/// tick's price, inverted for positive ticks
+pub fn raydium_sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(QuoteError::InvalidTick);
+    }
+    let abs_tick = tick.unsigned_abs();
+
+    let mut ratio = if abs_tick & 1 != 0 {
+        RAYDIUM_RATIOS[0]
+    } else {
+        Q64
+    };
+    for (bit, factor) in RAYDIUM_RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (U256::mul(ratio, *factor) >> 64).low_u128();
+        }
+    }
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+    Ok(ratio)
+}
+
+/// Token A moved between two sqrt prices: `L * (pb - pa) / (pa * pb)`
+pub fn amount_a_delta(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128> {
+    let (lower, upper) = ordered(sqrt_price_0, sqrt_price_1);
+    if lower == 0 {
+        return Err(QuoteError::InvalidSqrtPrice);
+    }
+    let numerator = U256::mul(liquidity, upper - lower)
+        .checked_shl(64)
+        .ok_or(QuoteError::Overflow)?;
+    numerator
+        .checked_div_round(U256::mul(lower, upper), round_up)
+        .and_then(U256::to_u128)
+        .ok_or(QuoteError::Overflow)
+}
+
+/// Token B moved between two sqrt prices: `L * (pb - pa)`
+pub fn amount_b_delta(
+    sqrt_price_0: u128,
+    sqrt_price_1: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128> {
+    let (lower, upper) = ordered(sqrt_price_0, sqrt_price_1);
+    let product = U256::mul(liquidity, upper - lower);
+    let remainder = product.low_u128() & (Q64 - 1);
+    let amount = (product >> 64).to_u128().ok_or(QuoteError::Overflow)?;
+    if round_up && remainder != 0 {
+        amount.checked_add(1).ok_or(QuoteError::Overflow)
+    } else {
+        Ok(amount)
+    }
+}
+
+/// Sqrt price after adding `amount` of the input token, rounded so the pool
+/// never gives out more than the input pays for. Range checks are left to
+/// the caller, whose bounds depend on the [`TickMath`].
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+    a_to_b: bool,
+) -> Result<u128> {
+    if amount == 0 {
+        return Ok(sqrt_price);
+    }
+    if liquidity == 0 {
+        return Err(QuoteError::ZeroLiquidity);
+    }
+
+    let next = if a_to_b {
+        // ceil(L * p / (L + amount * p)), scaled by 2^64
+        let scaled_liquidity = U256::from_u128(liquidity)
+            .checked_shl(64)
+            .ok_or(QuoteError::Overflow)?;
+        let numerator = scaled_liquidity
+            .checked_mul_u128(sqrt_price)
+            .ok_or(QuoteError::Overflow)?;
+        let denominator = scaled_liquidity
+            .checked_add(U256::mul(amount as u128, sqrt_price))
+            .ok_or(QuoteError::Overflow)?;
+        numerator
+            .checked_div_round(denominator, true)
+            .and_then(U256::to_u128)
+            .ok_or(QuoteError::Overflow)?
+    } else {
+        // p + amount / L, rounded down
+        sqrt_price
+            .checked_add(((amount as u128) << 64) / liquidity)
+            .ok_or(QuoteError::Overflow)?
+    };
+    Ok(next)
+}
+
+/// Output for `amount_in`, crossing `ticks` in order. `ticks` are the
+/// initialized ticks in swap direction: descending for A to B, ascending
+/// for B to A. The swap stops at `sqrt_price_limit`, or the price bound in
+/// swap direction when `None`.
+pub fn quote_exact_in(
+    pool: &ClmmPool,
+    ticks: impl IntoIterator<Item = TickCrossing>,
+    amount_in: u64,
+    a_to_b: bool,
+    sqrt_price_limit: Option<u128>,
+) -> Result<ClmmQuote> {
+    if amount_in == 0 {
+        return Err(QuoteError::ZeroAmount);
+    }
+    let (min_sqrt_price, max_sqrt_price) = (
+        pool.tick_math.min_sqrt_price(),
+        pool.tick_math.max_sqrt_price(),
+    );
+    if !(min_sqrt_price..=max_sqrt_price).contains(&pool.sqrt_price) {
+        return Err(QuoteError::InvalidSqrtPrice);
+    }
+    let limit = sqrt_price_limit.unwrap_or(if a_to_b {
+        min_sqrt_price
+    } else {
+        max_sqrt_price
+    });
+    let limit_valid = if a_to_b {
+        (min_sqrt_price..pool.sqrt_price).contains(&limit)
+    } else {
+        limit > pool.sqrt_price && limit <= max_sqrt_price
+    };
+    if !limit_valid {
+        return Err(QuoteError::InvalidSqrtPrice);
+    }
+
+    let mut remaining = amount_in;
+    let mut amount_out: u64 = 0;
+    let mut fee_amount: u64 = 0;
+    let mut sqrt_price = pool.sqrt_price;
+    let mut liquidity = pool.liquidity;
+    let mut ticks_crossed: u32 = 0;
+    let mut next_ticks = ticks.into_iter();
+    let mut next_tick = next_ticks.next();
+
+    while remaining > 0 && sqrt_price != limit {
+        let tick_price = match next_tick {
+            Some(tick) => {
+                let tick_price = pool.tick_math.sqrt_price_at_tick(tick.tick_index)?;
+                let in_direction = if a_to_b {
+                    tick_price <= sqrt_price
+                } else {
+                    tick_price > sqrt_price
+                };
+                if !in_direction {
+                    return Err(QuoteError::InvalidTick);
+                }
+                Some(tick_price)
+            }
+            None => None,
+        };
+        let target = match tick_price {
+            Some(tick_price) if a_to_b => tick_price.max(limit),
+            Some(tick_price) => tick_price.min(limit),
+            None => limit,
+        };
+
+        let step = swap_step(remaining, pool.fee, liquidity, sqrt_price, target, a_to_b)?;
+        remaining -= step.amount_in + step.fee_amount;
+        amount_out = amount_out
+            .checked_add(step.amount_out)
+            .ok_or(QuoteError::Overflow)?;
+        fee_amount += step.fee_amount;
+        sqrt_price = step.next_sqrt_price;
+
+        if let (Some(tick), Some(tick_price)) = (next_tick, tick_price) {
+            if sqrt_price == tick_price {
+                liquidity = cross_tick(liquidity, tick.liquidity_net, a_to_b)?;
+                ticks_crossed += 1;
+                next_tick = next_ticks.next();
+            }
+        }
+    }
+
+    let amount_used = amount_in - remaining;
+    let ideal_out = spot_output(pool.sqrt_price, (amount_used - fee_amount) as u128, a_to_b);
+    Ok(ClmmQuote {
+        quote: Quote {
+            amount_in: amount_used,
+            amount_out,
+            fee_amount,
+            price_impact_bps: price_impact_bps(ideal_out, amount_out as u128),
+        },
+        sqrt_price_after: sqrt_price,
+        liquidity_after: liquidity,
+        ticks_crossed,
+    })
+}
+
+/// One swap step within a single liquidity range
+struct SwapStep {
+    amount_in: u64,
+    amount_out: u64,
+    fee_amount: u64,
+    next_sqrt_price: u128,
+}
+
+fn swap_step(
+    amount_remaining: u64,
+    fee: Fee,
+    liquidity: u128,
+    sqrt_price: u128,
+    target_sqrt_price: u128,
+    a_to_b: bool,
+) -> Result<SwapStep> {
+    let input_delta = |from: u128, to: u128| {
+        if a_to_b {
+            amount_a_delta(from, to, liquidity, true)
+        } else {
+            amount_b_delta(from, to, liquidity, true)
+        }
+    };
+
+    let amount_after_fee = amount_remaining - fee.apply(amount_remaining);
+    // Too large to fit means the target can't be reached with this input
+    let to_target = input_delta(sqrt_price, target_sqrt_price).unwrap_or(u128::MAX);
+
+    let (amount_in, fee_amount, next_sqrt_price) = if to_target <= amount_after_fee as u128 {
+        let amount_in = to_target as u64;
+        (amount_in, fee.on_net(amount_in)?, target_sqrt_price)
+    } else {
+        let next_sqrt_price =
+            next_sqrt_price_from_input(sqrt_price, liquidity, amount_after_fee, a_to_b)?;
+        let amount_in = to_u64(input_delta(sqrt_price, next_sqrt_price)?)?;
+        // The whole remainder is spent; what the price move didn't use is fee
+        let fee_amount = amount_remaining
+            .checked_sub(amount_in)
+            .ok_or(QuoteError::Overflow)?;
+        (amount_in, fee_amount, next_sqrt_price)
+    };
+
+    let amount_out = if a_to_b {
+        amount_b_delta(next_sqrt_price, sqrt_price, liquidity, false)?
+    } else {
+        amount_a_delta(sqrt_price, next_sqrt_price, liquidity, false)?
+    };
+
+    Ok(SwapStep {
+        amount_in,
+        amount_out: to_u64(amount_out)?,
+        fee_amount,
+        next_sqrt_price,
+    })
+}
+
+/// Active liquidity after crossing a tick in swap direction
+fn cross_tick(liquidity: u128, liquidity_net: i128, a_to_b: bool) -> Result<u128> {
+    // Moving left removes what crossing right would have added
+    let delta = if a_to_b {
+        liquidity_net.checked_neg().ok_or(QuoteError::Overflow)?
+    } else {
+        liquidity_net
+    };
+    if delta >= 0 {
+        liquidity.checked_add(delta as u128)
+    } else {
+        liquidity.checked_sub(delta.unsigned_abs())
+    }
+    .ok_or(QuoteError::Overflow)
+}
+
+/// `amount` of the input token valued at the spot price `(p / 2^64)^2`
+fn spot_output(sqrt_price: u128, amount: u128, a_to_b: bool) -> u128 {
+    let value = if a_to_b {
+        mul_div_floor(amount, sqrt_price, Q64)
+            .and_then(|value| mul_div_floor(value, sqrt_price, Q64))
+    } else {
+        mul_div_floor(amount, Q64, sqrt_price)
+            .and_then(|value| mul_div_floor(value, Q64, sqrt_price))
+    };
+    value.unwrap_or(0)
+}
+
+fn ordered(a: u128, b: u128) -> (u128, u128) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
@@ -0,0 +1,64 @@
+//! Constant-product (`x * y = k`) pools such as Raydium AMM v4
+
+use crate::math::{mul_div_ceil, mul_div_floor, to_u64};
+use crate::{price_impact_bps, Fee, Quote, QuoteError, Result};
+
+/// Output for exactly `amount_in`, fee taken from the input
+pub fn quote_exact_in(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee: Fee,
+) -> Result<Quote> {
+    if amount_in == 0 {
+        return Err(QuoteError::ZeroAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(QuoteError::ZeroLiquidity);
+    }
+
+    let fee_amount = fee.apply(amount_in);
+    let net_in = (amount_in - fee_amount) as u128;
+    let amount_out = mul_div_floor(net_in, reserve_out as u128, reserve_in as u128 + net_in)?;
+    let ideal_out = mul_div_floor(net_in, reserve_out as u128, reserve_in as u128)?;
+
+    Ok(Quote {
+        amount_in,
+        amount_out: to_u64(amount_out)?,
+        fee_amount,
+        price_impact_bps: price_impact_bps(ideal_out, amount_out),
+    })
+}
+
+/// Smallest input returning at least `amount_out`
+pub fn quote_exact_out(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee: Fee,
+) -> Result<Quote> {
+    if amount_out == 0 {
+        return Err(QuoteError::ZeroAmount);
+    }
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(QuoteError::ZeroLiquidity);
+    }
+    if amount_out >= reserve_out {
+        return Err(QuoteError::InsufficientLiquidity);
+    }
+
+    let net_in = to_u64(mul_div_ceil(
+        reserve_in as u128,
+        amount_out as u128,
+        (reserve_out - amount_out) as u128,
+    )?)?;
+    let amount_in = fee.gross_up(net_in)?;
+    let ideal_out = mul_div_floor(net_in as u128, reserve_out as u128, reserve_in as u128)?;
+
+    Ok(Quote {
+        amount_in,
+        amount_out,
+        fee_amount: fee.apply(amount_in),
+        price_impact_bps: price_impact_bps(ideal_out, amount_out as u128),
+    })
+}
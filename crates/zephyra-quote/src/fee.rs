@@ -0,0 +1,78 @@
+//! Swap fees as exact fractions of the input
+
+use crate::math::{mul_div_ceil, to_u64};
+use crate::{QuoteError, Result, BPS_DENOMINATOR};
+
+/// Denominator of Whirlpool fee rates, hundredths of a basis point
+pub const HUNDREDTHS_BPS_DENOMINATOR: u64 = 1_000_000;
+
+/// Fee taken from the input amount, `numerator / denominator`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fee {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl Fee {
+    pub const ZERO: Fee = Fee {
+        numerator: 0,
+        denominator: 1,
+    };
+
+    /// Fee of `numerator / denominator`, which must be below one
+    pub const fn new(numerator: u64, denominator: u64) -> Result<Self> {
+        if denominator == 0 || numerator >= denominator {
+            return Err(QuoteError::InvalidFee);
+        }
+        Ok(Fee {
+            numerator,
+            denominator,
+        })
+    }
+
+    /// Fee in basis points
+    pub const fn from_bps(bps: u16) -> Result<Self> {
+        Fee::new(bps as u64, BPS_DENOMINATOR)
+    }
+
+    /// Fee in hundredths of a basis point, as Whirlpool stores it
+    pub const fn from_hundredths_bps(rate: u32) -> Result<Self> {
+        Fee::new(rate as u64, HUNDREDTHS_BPS_DENOMINATOR)
+    }
+
+    pub const fn numerator(&self) -> u64 {
+        self.numerator
+    }
+
+    pub const fn denominator(&self) -> u64 {
+        self.denominator
+    }
+
+    /// Fee charged on `amount`, rounded up
+    pub fn apply(&self, amount: u64) -> u64 {
+        let fee = mul_div_ceil(
+            amount as u128,
+            self.numerator as u128,
+            self.denominator as u128,
+        );
+        fee.map_or(amount, |fee| fee as u64) // at most `amount`
+    }
+
+    /// Fee owed on top of a net input, rounded up
+    pub fn on_net(&self, net: u64) -> Result<u64> {
+        to_u64(mul_div_ceil(
+            net as u128,
+            self.numerator as u128,
+            (self.denominator - self.numerator) as u128,
+        )?)
+    }
+
+    /// Smallest gross input leaving at least `net` after [`Fee::apply`]
+    pub fn gross_up(&self, net: u64) -> Result<u64> {
+        to_u64(mul_div_ceil(
+            net as u128,
+            self.denominator as u128,
+            (self.denominator - self.numerator) as u128,
+        )?)
+    }
+}
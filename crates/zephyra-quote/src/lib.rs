@@ -0,0 +1,86 @@
+//! Deterministic AMM quote math
+//!
+//! Integer-only quoting for the curves Zephyra routes through: constant
+//! product, two-coin stableswap and concentrated liquidity with tick
+//! crossing. The same code runs inside the on-chain executor and in off-chain
+//! clients, so a quote computed off-chain matches execution bit for bit.
+//!
+//! Rounding always favours the pool: fees and required inputs are rounded
+//! up, outputs are rounded down. [`pools`] decodes live pool accounts into
+//! the inputs of these curves, and [`oracles`] decodes the price accounts
+//! swaps are checked against.
+
+#![no_std]
+
+pub mod clmm;
+pub mod constant_product;
+pub mod fee;
+mod math;
+pub mod oracles;
+pub mod pools;
+pub mod stableswap;
+
+pub use fee::Fee;
+
+/// Basis points in 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Why a quote can't be computed or checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteError {
+    /// Swap amount is zero
+    ZeroAmount,
+    /// Pool has no reserves or liquidity
+    ZeroLiquidity,
+    /// Pool can't provide the requested output
+    InsufficientLiquidity,
+    /// Fee is not a fraction below one
+    InvalidFee,
+    /// Stableswap amplification is zero or above `stableswap::MAX_AMP`
+    InvalidAmplification,
+    /// Tick is out of range or not in swap direction
+    InvalidTick,
+    /// Sqrt price is out of range or the limit is behind the current price
+    InvalidSqrtPrice,
+    /// Stableswap invariant didn't converge
+    NoConvergence,
+    /// Account data doesn't match the expected layout
+    InvalidAccountData,
+    /// Mint is not one of the pool's tokens, or a vault holds another mint
+    MintMismatch,
+    /// Pool status doesn't allow swaps
+    PoolDisabled,
+    /// Intermediate value doesn't fit
+    Overflow,
+    /// Oracle price is not positive
+    InvalidPrice,
+    /// Oracle price update is not fully verified
+    UnverifiedPrice,
+    /// Oracle price is older than allowed
+    StalePrice,
+    /// Oracle confidence interval is wider than allowed
+    PriceUncertain,
+}
+
+pub type Result<T> = core::result::Result<T, QuoteError>;
+
+/// Amounts of a quoted swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quote {
+    /// Input taken by the pool, fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Part of `amount_in` kept as fee
+    pub fee_amount: u64,
+    /// Shortfall of `amount_out` against the spot price, after fees
+    pub price_impact_bps: u16,
+}
+
+/// Shortfall of `amount_out` against `ideal_out`, rounded up
+pub(crate) fn price_impact_bps(ideal_out: u128, amount_out: u128) -> u16 {
+    if ideal_out == 0 || amount_out >= ideal_out {
+        return 0;
+    }
+    math::mul_div_ceil(ideal_out - amount_out, BPS_DENOMINATOR as u128, ideal_out)
+        .map_or(BPS_DENOMINATOR as u16, |impact| impact as u16) // bounded by 10_000
+}
@@ -0,0 +1,216 @@
+//! 256-bit intermediates for products of two `u128`s
+
+use core::cmp::Ordering;
+use core::ops::{Shl, Shr};
+
+use crate::{QuoteError, Result};
+
+/// Unsigned 256-bit integer, just wide enough for `u128 * u128`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct U256 {
+    hi: u128,
+    lo: u128,
+}
+
+impl U256 {
+    pub const ZERO: U256 = U256 { hi: 0, lo: 0 };
+
+    pub const fn from_u128(value: u128) -> Self {
+        U256 { hi: 0, lo: value }
+    }
+
+    /// Full product of two `u128`s
+    pub fn mul(a: u128, b: u128) -> Self {
+        const MASK: u128 = u64::MAX as u128;
+        let (a_hi, a_lo) = (a >> 64, a & MASK);
+        let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+        let lo_lo = a_lo * b_lo;
+        let hi_lo = a_hi * b_lo;
+        let lo_hi = a_lo * b_hi;
+        let hi_hi = a_hi * b_hi;
+
+        // Middle column, carrying into the high word
+        let mid = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+        U256 {
+            hi: hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (mid >> 64),
+            lo: (mid << 64) | (lo_lo & MASK),
+        }
+    }
+
+    /// Product with a `u128`, `None` on overflow
+    pub fn checked_mul_u128(self, b: u128) -> Option<Self> {
+        let lo = U256::mul(self.lo, b);
+        let hi = U256::mul(self.hi, b);
+        if hi.hi != 0 {
+            return None;
+        }
+        Some(U256 {
+            hi: lo.hi.checked_add(hi.lo)?,
+            lo: lo.lo,
+        })
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (lo, carry) = self.lo.overflowing_add(other.lo);
+        Some(U256 {
+            hi: self.hi.checked_add(other.hi)?.checked_add(carry as u128)?,
+            lo,
+        })
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (lo, borrow) = self.lo.overflowing_sub(other.lo);
+        Some(U256 {
+            hi: self.hi.checked_sub(other.hi)?.checked_sub(borrow as u128)?,
+            lo,
+        })
+    }
+
+    /// Left shift, `None` if bits are shifted out
+    pub fn checked_shl(self, shift: u32) -> Option<Self> {
+        if shift == 0 {
+            return Some(self);
+        }
+        if shift >= 256 || self.leading_zeros() < shift {
+            return None;
+        }
+        Some(self << shift)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.hi == 0 && self.lo == 0
+    }
+
+    pub fn low_u128(self) -> u128 {
+        self.lo
+    }
+
+    pub fn to_u128(self) -> Option<u128> {
+        (self.hi == 0).then_some(self.lo)
+    }
+
+    pub fn leading_zeros(self) -> u32 {
+        if self.hi == 0 {
+            128 + self.lo.leading_zeros()
+        } else {
+            self.hi.leading_zeros()
+        }
+    }
+
+    /// Quotient and remainder, long division one bit at a time, `None` when
+    /// dividing by zero
+    pub fn checked_div_rem(self, divisor: Self) -> Option<(Self, Self)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        if self.hi == 0 && divisor.hi == 0 {
+            return Some((
+                U256::from_u128(self.lo / divisor.lo),
+                U256::from_u128(self.lo % divisor.lo),
+            ));
+        }
+        if self < divisor {
+            return Some((U256::ZERO, self));
+        }
+
+        let shift = divisor.leading_zeros() - self.leading_zeros();
+        let mut divisor = divisor << shift;
+        let mut quotient = U256::ZERO;
+        let mut remainder = self;
+        for _ in 0..=shift {
+            quotient = quotient << 1;
+            if remainder >= divisor {
+                remainder = remainder.checked_sub(divisor)?;
+                quotient.lo |= 1;
+            }
+            divisor = divisor >> 1;
+        }
+        Some((quotient, remainder))
+    }
+
+    /// Quotient rounded down or up, `None` when dividing by zero
+    pub fn checked_div_round(self, divisor: Self, round_up: bool) -> Option<Self> {
+        let (quotient, remainder) = self.checked_div_rem(divisor)?;
+        if round_up && !remainder.is_zero() {
+            quotient.checked_add(U256::from_u128(1))
+        } else {
+            Some(quotient)
+        }
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.hi, self.lo).cmp(&(other.hi, other.lo))
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Shl<u32> for U256 {
+    type Output = U256;
+
+    fn shl(self, shift: u32) -> Self {
+        match shift {
+            0 => self,
+            1..=127 => U256 {
+                hi: (self.hi << shift) | (self.lo >> (128 - shift)),
+                lo: self.lo << shift,
+            },
+            128..=255 => U256 {
+                hi: self.lo << (shift - 128),
+                lo: 0,
+            },
+            _ => U256::ZERO,
+        }
+    }
+}
+
+impl Shr<u32> for U256 {
+    type Output = U256;
+
+    fn shr(self, shift: u32) -> Self {
+        match shift {
+            0 => self,
+            1..=127 => U256 {
+                hi: self.hi >> shift,
+                lo: (self.lo >> shift) | (self.hi << (128 - shift)),
+            },
+            128..=255 => U256 {
+                hi: 0,
+                lo: self.hi >> (shift - 128),
+            },
+            _ => U256::ZERO,
+        }
+    }
+}
+
+/// `a * b / c` rounded down
+pub(crate) fn mul_div_floor(a: u128, b: u128, c: u128) -> Result<u128> {
+    mul_div(a, b, c, false)
+}
+
+/// `a * b / c` rounded up
+pub(crate) fn mul_div_ceil(a: u128, b: u128, c: u128) -> Result<u128> {
+    mul_div(a, b, c, true)
+}
+
+fn mul_div(a: u128, b: u128, c: u128, round_up: bool) -> Result<u128> {
+    if c == 0 {
+        return Err(QuoteError::Overflow);
+    }
+    U256::mul(a, b)
+        .checked_div_round(U256::from_u128(c), round_up)
+        .and_then(U256::to_u128)
+        .ok_or(QuoteError::Overflow)
+}
+
+/// Narrow to a token amount
+pub(crate) fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| QuoteError::Overflow)
+}
@@ -0,0 +1,82 @@
+//! Zero-copy views over oracle price accounts, and checks of swap prices
+//! against them
+//!
+//! Like [`crate::pools`], decoding only checks the discriminator and length.
+//! Callers check that the account is owned by the oracle program.
+
+pub mod pyth;
+pub mod switchboard;
+
+use crate::math::{mul_div_ceil, mul_div_floor, to_u64, U256};
+use crate::{QuoteError, Result, BPS_DENOMINATOR};
+
+/// Price published by an oracle: `price * 10^exponent` units of the feed's
+/// quote asset per unit of its base asset, within `confidence` either way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i128,
+    pub confidence: u128,
+    pub exponent: i32,
+    /// Unix timestamp of the price
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Check the price is positive, at most `max_age_secs` old at `now`, and
+    /// that its confidence interval is within `max_confidence_bps` of it
+    pub fn validate(&self, now: i64, max_age_secs: u32, max_confidence_bps: u16) -> Result<()> {
+        let price = self.positive_price()?;
+        if now.saturating_sub(self.publish_time) > max_age_secs as i64 {
+            return Err(QuoteError::StalePrice);
+        }
+        if U256::mul(self.confidence, BPS_DENOMINATOR as u128)
+            > U256::mul(price, max_confidence_bps as u128)
+        {
+            return Err(QuoteError::PriceUncertain);
+        }
+        Ok(())
+    }
+
+    /// Deviation of a trade's effective price from this price, rounded up.
+    /// `base_amount` of the feed's base asset traded for `quote_amount` of its
+    /// quote asset, in either direction, with the decimals of their mints.
+    pub fn deviation_bps(
+        &self,
+        base_amount: u64,
+        base_decimals: u8,
+        quote_amount: u64,
+        quote_decimals: u8,
+    ) -> Result<u64> {
+        let price = self.positive_price()?;
+
+        // Quote amount the oracle values `base_amount` at, in raw units
+        let shift = quote_decimals as i32 - base_decimals as i32 + self.exponent;
+        let expected = if shift >= 0 {
+            mul_div_floor(base_amount as u128, price, 1)?
+                .checked_mul(pow10(shift.unsigned_abs())?)
+                .ok_or(QuoteError::Overflow)?
+        } else {
+            mul_div_floor(base_amount as u128, price, pow10(shift.unsigned_abs())?)?
+        };
+        if expected == 0 {
+            return Err(QuoteError::ZeroAmount);
+        }
+
+        to_u64(mul_div_ceil(
+            expected.abs_diff(quote_amount as u128),
+            BPS_DENOMINATOR as u128,
+            expected,
+        )?)
+    }
+
+    fn positive_price(&self) -> Result<u128> {
+        u128::try_from(self.price)
+            .ok()
+            .filter(|price| *price > 0)
+            .ok_or(QuoteError::InvalidPrice)
+    }
+}
+
+fn pow10(exponent: u32) -> Result<u128> {
+    10u128.checked_pow(exponent).ok_or(QuoteError::Overflow)
+}
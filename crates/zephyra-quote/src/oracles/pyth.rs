@@ -0,0 +1,112 @@
+//! Pyth `PriceUpdateV2`, a price feed update posted by the Pyth receiver
+
+use super::OraclePrice;
+use crate::pools::{anchor_account, read_address, read_i32, read_i64, read_u64, Address};
+use crate::{QuoteError, Result};
+
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+/// Size of `PriceUpdateV2`, allocated for the longer `Partial` level
+pub const PRICE_UPDATE_V2_LEN: usize = 134;
+
+const WRITE_AUTHORITY: usize = 8;
+const VERIFICATION_LEVEL: usize = 40;
+
+// `PriceFeedMessage`, from its start right after the verification level
+const FEED_ID: usize = 0;
+const PRICE: usize = 32;
+const CONF: usize = 40;
+const EXPONENT: usize = 48;
+const PUBLISH_TIME: usize = 52;
+const PREV_PUBLISH_TIME: usize = 60;
+const EMA_PRICE: usize = 68;
+const EMA_CONF: usize = 76;
+const POSTED_SLOT: usize = 84;
+
+/// How many Wormhole guardians verified the update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+/// Pyth price update
+#[derive(Debug, Clone, Copy)]
+pub struct PriceUpdateV2<'a> {
+    data: &'a [u8],
+    /// Offset of the `PriceFeedMessage`, which depends on the verification level
+    message: usize,
+}
+
+impl<'a> PriceUpdateV2<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        let data = anchor_account(data, &PRICE_UPDATE_V2_DISCRIMINATOR, PRICE_UPDATE_V2_LEN)?;
+        let message = match data[VERIFICATION_LEVEL] {
+            0 => VERIFICATION_LEVEL + 2,
+            1 => VERIFICATION_LEVEL + 1,
+            _ => return Err(QuoteError::InvalidAccountData),
+        };
+        Ok(PriceUpdateV2 { data, message })
+    }
+
+    pub fn write_authority(&self) -> &'a Address {
+        read_address(self.data, WRITE_AUTHORITY)
+    }
+
+    pub fn verification_level(&self) -> VerificationLevel {
+        match self.data[VERIFICATION_LEVEL] {
+            0 => VerificationLevel::Partial {
+                num_signatures: self.data[VERIFICATION_LEVEL + 1],
+            },
+            _ => VerificationLevel::Full,
+        }
+    }
+
+    pub fn feed_id(&self) -> &'a [u8; 32] {
+        read_address(self.data, self.message + FEED_ID)
+    }
+
+    pub fn price(&self) -> i64 {
+        read_i64(self.data, self.message + PRICE)
+    }
+
+    pub fn conf(&self) -> u64 {
+        read_u64(self.data, self.message + CONF)
+    }
+
+    pub fn exponent(&self) -> i32 {
+        read_i32(self.data, self.message + EXPONENT)
+    }
+
+    pub fn publish_time(&self) -> i64 {
+        read_i64(self.data, self.message + PUBLISH_TIME)
+    }
+
+    pub fn prev_publish_time(&self) -> i64 {
+        read_i64(self.data, self.message + PREV_PUBLISH_TIME)
+    }
+
+    pub fn ema_price(&self) -> i64 {
+        read_i64(self.data, self.message + EMA_PRICE)
+    }
+
+    pub fn ema_conf(&self) -> u64 {
+        read_u64(self.data, self.message + EMA_CONF)
+    }
+
+    pub fn posted_slot(&self) -> u64 {
+        read_u64(self.data, self.message + POSTED_SLOT)
+    }
+
+    /// Spot price, only from updates verified by the full guardian set
+    pub fn oracle_price(&self) -> Result<OraclePrice> {
+        if self.verification_level() != VerificationLevel::Full {
+            return Err(QuoteError::UnverifiedPrice);
+        }
+        Ok(OraclePrice {
+            price: self.price() as i128,
+            confidence: self.conf() as u128,
+            exponent: self.exponent(),
+            publish_time: self.publish_time(),
+        })
+    }
+}
@@ -0,0 +1,95 @@
+//! Switchboard On-Demand `PullFeedAccountData`, a feed aggregated from
+//! oracle submissions
+
+use super::OraclePrice;
+use crate::pools::{
+    anchor_account, read_address, read_i128, read_i64, read_u32, read_u64, Address,
+};
+use crate::{QuoteError, Result};
+
+pub const PULL_FEED_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+/// Length up to the last field read, `max_staleness`
+pub const PULL_FEED_MIN_LEN: usize = 2_396;
+/// Feed values are fixed point with 18 decimals
+pub const PRECISION: i32 = 18;
+
+const AUTHORITY: usize = 2_056;
+const QUEUE: usize = 2_088;
+const FEED_HASH: usize = 2_120;
+const MIN_RESPONSES: usize = 2_176;
+const LAST_UPDATE_TIMESTAMP: usize = 2_216;
+const RESULT_VALUE: usize = 2_264;
+const RESULT_STD_DEV: usize = 2_280;
+const RESULT_NUM_SAMPLES: usize = 2_360;
+const RESULT_SLOT: usize = 2_368;
+const MAX_STALENESS: usize = 2_392;
+
+/// Switchboard pull feed
+#[derive(Debug, Clone, Copy)]
+pub struct PullFeed<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> PullFeed<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        let data = anchor_account(data, &PULL_FEED_DISCRIMINATOR, PULL_FEED_MIN_LEN)?;
+        Ok(PullFeed { data })
+    }
+
+    pub fn authority(&self) -> &'a Address {
+        read_address(self.data, AUTHORITY)
+    }
+
+    pub fn queue(&self) -> &'a Address {
+        read_address(self.data, QUEUE)
+    }
+
+    pub fn feed_hash(&self) -> &'a [u8; 32] {
+        read_address(self.data, FEED_HASH)
+    }
+
+    pub fn min_responses(&self) -> u32 {
+        read_u32(self.data, MIN_RESPONSES)
+    }
+
+    pub fn last_update_timestamp(&self) -> i64 {
+        read_i64(self.data, LAST_UPDATE_TIMESTAMP)
+    }
+
+    /// Median of the latest submissions, with `PRECISION` decimals
+    pub fn value(&self) -> i128 {
+        read_i128(self.data, RESULT_VALUE)
+    }
+
+    /// Standard deviation of the latest submissions, with `PRECISION` decimals
+    pub fn std_dev(&self) -> i128 {
+        read_i128(self.data, RESULT_STD_DEV)
+    }
+
+    pub fn num_samples(&self) -> u8 {
+        self.data[RESULT_NUM_SAMPLES]
+    }
+
+    /// Slot the current result was computed at
+    pub fn result_slot(&self) -> u64 {
+        read_u64(self.data, RESULT_SLOT)
+    }
+
+    /// Most slots a submission counts towards the result
+    pub fn max_staleness(&self) -> u32 {
+        read_u32(self.data, MAX_STALENESS)
+    }
+
+    /// Current result, with its standard deviation as the confidence interval
+    pub fn oracle_price(&self) -> Result<OraclePrice> {
+        if self.num_samples() == 0 {
+            return Err(QuoteError::InvalidPrice);
+        }
+        Ok(OraclePrice {
+            price: self.value(),
+            confidence: u128::try_from(self.std_dev()).map_err(|_| QuoteError::InvalidPrice)?,
+            exponent: -PRECISION,
+            publish_time: self.last_update_timestamp(),
+        })
+    }
+}
@@ -0,0 +1,91 @@
+//! Zero-copy views over the on-chain accounts of supported pools
+//!
+//! Each view borrows the raw account data and reads fields at their fixed
+//! offsets in the program's layout. Decoding only checks the discriminator
+//! and length; callers check account owners and that vaults and tick arrays
+//! are the ones the pool references.
+
+pub mod raydium_amm;
+pub mod raydium_clmm;
+pub mod whirlpool;
+
+use crate::{QuoteError, Result};
+
+/// Raw public key
+pub type Address = [u8; 32];
+
+/// Length of an SPL token account, Token-2022 accounts may be longer
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// SPL token account, such as a pool vault
+#[derive(Debug, Clone, Copy)]
+pub struct TokenAccount<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> TokenAccount<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        if data.len() < TOKEN_ACCOUNT_LEN {
+            return Err(QuoteError::InvalidAccountData);
+        }
+        Ok(TokenAccount { data })
+    }
+
+    pub fn mint(&self) -> &'a Address {
+        read_address(self.data, 0)
+    }
+
+    pub fn owner(&self) -> &'a Address {
+        read_address(self.data, 32)
+    }
+
+    pub fn amount(&self) -> u64 {
+        read_u64(self.data, 64)
+    }
+}
+
+/// Account data of an Anchor account, checked against its discriminator
+pub(crate) fn anchor_account<'a>(
+    data: &'a [u8],
+    discriminator: &[u8; 8],
+    len: usize,
+) -> Result<&'a [u8]> {
+    if data.len() < len || data[..8] != discriminator[..] {
+        return Err(QuoteError::InvalidAccountData);
+    }
+    Ok(data)
+}
+
+// Offsets are within the length checked at decode
+
+pub(crate) fn read_address(data: &[u8], offset: usize) -> &Address {
+    data[offset..offset + 32].try_into().unwrap()
+}
+
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+pub(crate) fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+pub(crate) fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+pub(crate) fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+pub(crate) fn read_u128(data: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+pub(crate) fn read_i128(data: &[u8], offset: usize) -> i128 {
+    i128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
@@ -0,0 +1,153 @@
+//! Raydium AMM v4 `AmmInfo`, a constant-product pool over two vaults
+
+use super::{read_address, read_u64, Address, TokenAccount};
+use crate::{constant_product, Fee, Quote, QuoteError, Result};
+
+/// Size of `AmmInfo`, which has no discriminator
+pub const AMM_INFO_LEN: usize = 752;
+
+const STATUS: usize = 0;
+const COIN_DECIMALS: usize = 32;
+const PC_DECIMALS: usize = 40;
+const TRADE_FEE_NUMERATOR: usize = 144;
+const TRADE_FEE_DENOMINATOR: usize = 152;
+const SWAP_FEE_NUMERATOR: usize = 176;
+const SWAP_FEE_DENOMINATOR: usize = 184;
+const NEED_TAKE_PNL_COIN: usize = 192;
+const NEED_TAKE_PNL_PC: usize = 200;
+const COIN_VAULT: usize = 336;
+const PC_VAULT: usize = 368;
+const COIN_VAULT_MINT: usize = 400;
+const PC_VAULT_MINT: usize = 432;
+const LP_MINT: usize = 464;
+const OPEN_ORDERS: usize = 496;
+const MARKET: usize = 528;
+
+/// `AmmStatus` values that allow swaps: `Initialized` and `SwapOnly`
+const SWAP_STATUSES: [u64; 2] = [1, 6];
+
+/// Raydium AMM v4 pool state
+#[derive(Debug, Clone, Copy)]
+pub struct AmmInfo<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AmmInfo<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        if data.len() != AMM_INFO_LEN {
+            return Err(QuoteError::InvalidAccountData);
+        }
+        Ok(AmmInfo { data })
+    }
+
+    pub fn status(&self) -> u64 {
+        read_u64(self.data, STATUS)
+    }
+
+    pub fn coin_decimals(&self) -> u64 {
+        read_u64(self.data, COIN_DECIMALS)
+    }
+
+    pub fn pc_decimals(&self) -> u64 {
+        read_u64(self.data, PC_DECIMALS)
+    }
+
+    /// Trade fee from the pool's `Fees`, which swaps don't charge. See
+    /// [`AmmInfo::swap_fee`].
+    pub fn trade_fee(&self) -> Result<Fee> {
+        Fee::new(
+            read_u64(self.data, TRADE_FEE_NUMERATOR),
+            read_u64(self.data, TRADE_FEE_DENOMINATOR),
+        )
+    }
+
+    /// Fee charged on the input by `swap_base_in`, rounded up by the program
+    /// like [`Fee::apply`]
+    pub fn swap_fee(&self) -> Result<Fee> {
+        Fee::new(
+            read_u64(self.data, SWAP_FEE_NUMERATOR),
+            read_u64(self.data, SWAP_FEE_DENOMINATOR),
+        )
+    }
+
+    /// Coin owed to the protocol, still held by the coin vault
+    pub fn need_take_pnl_coin(&self) -> u64 {
+        read_u64(self.data, NEED_TAKE_PNL_COIN)
+    }
+
+    /// Pc owed to the protocol, still held by the pc vault
+    pub fn need_take_pnl_pc(&self) -> u64 {
+        read_u64(self.data, NEED_TAKE_PNL_PC)
+    }
+
+    pub fn coin_vault(&self) -> &'a Address {
+        read_address(self.data, COIN_VAULT)
+    }
+
+    pub fn pc_vault(&self) -> &'a Address {
+        read_address(self.data, PC_VAULT)
+    }
+
+    pub fn coin_mint(&self) -> &'a Address {
+        read_address(self.data, COIN_VAULT_MINT)
+    }
+
+    pub fn pc_mint(&self) -> &'a Address {
+        read_address(self.data, PC_VAULT_MINT)
+    }
+
+    pub fn lp_mint(&self) -> &'a Address {
+        read_address(self.data, LP_MINT)
+    }
+
+    pub fn open_orders(&self) -> &'a Address {
+        read_address(self.data, OPEN_ORDERS)
+    }
+
+    pub fn market(&self) -> &'a Address {
+        read_address(self.data, MARKET)
+    }
+
+    /// Coin and pc reserves the curve trades against: the vault balances
+    /// less the protocol's pending PnL
+    pub fn reserves(
+        &self,
+        coin_vault: &TokenAccount,
+        pc_vault: &TokenAccount,
+    ) -> Result<(u64, u64)> {
+        if coin_vault.mint() != self.coin_mint() || pc_vault.mint() != self.pc_mint() {
+            return Err(QuoteError::MintMismatch);
+        }
+        let coin = coin_vault
+            .amount()
+            .checked_sub(self.need_take_pnl_coin())
+            .ok_or(QuoteError::InvalidAccountData)?;
+        let pc = pc_vault
+            .amount()
+            .checked_sub(self.need_take_pnl_pc())
+            .ok_or(QuoteError::InvalidAccountData)?;
+        Ok((coin, pc))
+    }
+
+    /// `swap_base_in` of exactly `amount_in` of `input_mint`
+    pub fn quote_exact_in(
+        &self,
+        coin_vault: &TokenAccount,
+        pc_vault: &TokenAccount,
+        input_mint: &Address,
+        amount_in: u64,
+    ) -> Result<Quote> {
+        if !SWAP_STATUSES.contains(&self.status()) {
+            return Err(QuoteError::PoolDisabled);
+        }
+        let (coin, pc) = self.reserves(coin_vault, pc_vault)?;
+        let (reserve_in, reserve_out) = if input_mint == self.coin_mint() {
+            (coin, pc)
+        } else if input_mint == self.pc_mint() {
+            (pc, coin)
+        } else {
+            return Err(QuoteError::MintMismatch);
+        };
+        constant_product::quote_exact_in(amount_in, reserve_in, reserve_out, self.swap_fee()?)
+    }
+}
@@ -0,0 +1,230 @@
+//! Raydium concentrated liquidity pools, their fee config and tick arrays
+
+use super::{
+    anchor_account, read_address, read_i128, read_i32, read_u128, read_u16, read_u32, Address,
+};
+use crate::clmm::{self, ClmmPool, ClmmQuote, TickCrossing, TickMath, MAX_TICK, MIN_TICK};
+use crate::{Fee, QuoteError, Result};
+
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+pub const POOL_STATE_LEN: usize = 1_544;
+pub const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+pub const AMM_CONFIG_LEN: usize = 117;
+pub const TICK_ARRAY_STATE_DISCRIMINATOR: [u8; 8] = [192, 155, 85, 205, 49, 249, 129, 42];
+pub const TICK_ARRAY_STATE_LEN: usize = 10_240;
+/// Ticks per tick array
+pub const TICK_ARRAY_SIZE: i32 = 60;
+
+const AMM_CONFIG: usize = 9;
+const TOKEN_MINT_0: usize = 73;
+const TOKEN_MINT_1: usize = 105;
+const TOKEN_VAULT_0: usize = 137;
+const TOKEN_VAULT_1: usize = 169;
+const TICK_SPACING: usize = 235;
+const LIQUIDITY: usize = 237;
+const SQRT_PRICE_X64: usize = 253;
+const TICK_CURRENT: usize = 269;
+const STATUS: usize = 389;
+/// Status bit disabling swaps
+const STATUS_SWAP_DISABLED: u8 = 1 << 4;
+
+const TRADE_FEE_RATE: usize = 47;
+
+const POOL_ID: usize = 8;
+const START_TICK_INDEX: usize = 40;
+const TICKS: usize = 44;
+const TICK_STATE_LEN: usize = 168;
+
+/// Raydium CLMM pool state
+#[derive(Debug, Clone, Copy)]
+pub struct PoolState<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> PoolState<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        let data = anchor_account(data, &POOL_STATE_DISCRIMINATOR, POOL_STATE_LEN)?;
+        Ok(PoolState { data })
+    }
+
+    /// Config account holding the pool's fee rate
+    pub fn amm_config(&self) -> &'a Address {
+        read_address(self.data, AMM_CONFIG)
+    }
+
+    pub fn token_mint_0(&self) -> &'a Address {
+        read_address(self.data, TOKEN_MINT_0)
+    }
+
+    pub fn token_mint_1(&self) -> &'a Address {
+        read_address(self.data, TOKEN_MINT_1)
+    }
+
+    pub fn token_vault_0(&self) -> &'a Address {
+        read_address(self.data, TOKEN_VAULT_0)
+    }
+
+    pub fn token_vault_1(&self) -> &'a Address {
+        read_address(self.data, TOKEN_VAULT_1)
+    }
+
+    pub fn tick_spacing(&self) -> u16 {
+        read_u16(self.data, TICK_SPACING)
+    }
+
+    pub fn liquidity(&self) -> u128 {
+        read_u128(self.data, LIQUIDITY)
+    }
+
+    pub fn sqrt_price_x64(&self) -> u128 {
+        read_u128(self.data, SQRT_PRICE_X64)
+    }
+
+    pub fn tick_current(&self) -> i32 {
+        read_i32(self.data, TICK_CURRENT)
+    }
+
+    pub fn status(&self) -> u8 {
+        self.data[STATUS]
+    }
+
+    pub fn pool(&self, config: &AmmConfig) -> Result<ClmmPool> {
+        Ok(ClmmPool {
+            sqrt_price: self.sqrt_price_x64(),
+            liquidity: self.liquidity(),
+            fee: config.trade_fee()?,
+            tick_math: TickMath::Raydium,
+        })
+    }
+
+    /// Exact input swap across consecutive `tick_arrays`, given in swap order. `config`
+    /// must be the pool's [`PoolState::amm_config`]. The quote stops at the
+    /// edge of the last array.
+    pub fn quote_exact_in(
+        &self,
+        config: &AmmConfig,
+        tick_arrays: &[TickArrayState],
+        amount_in: u64,
+        zero_for_one: bool,
+    ) -> Result<ClmmQuote> {
+        if self.status() & STATUS_SWAP_DISABLED != 0 {
+            return Err(QuoteError::PoolDisabled);
+        }
+        let span = TICK_ARRAY_SIZE * self.tick_spacing() as i32;
+        if span == 0
+            || tick_arrays
+                .iter()
+                .any(|array| array.start_tick_index() % span != 0)
+        {
+            return Err(QuoteError::InvalidAccountData);
+        }
+        let step = if zero_for_one { -span } else { span };
+        if tick_arrays
+            .windows(2)
+            .any(|pair| pair[1].start_tick_index() != pair[0].start_tick_index() + step)
+        {
+            return Err(QuoteError::InvalidTick);
+        }
+        let last = tick_arrays.last().ok_or(QuoteError::InvalidTick)?;
+        let limit_tick = if zero_for_one {
+            last.start_tick_index().max(MIN_TICK)
+        } else {
+            (last.start_tick_index() + span).min(MAX_TICK)
+        };
+
+        let tick_current = self.tick_current();
+        let ticks = tick_arrays
+            .iter()
+            .flat_map(move |array| {
+                (0..TICK_ARRAY_SIZE as usize).map(move |offset| {
+                    let offset = if zero_for_one {
+                        TICK_ARRAY_SIZE as usize - 1 - offset
+                    } else {
+                        offset
+                    };
+                    array.tick(offset)
+                })
+            })
+            .filter(move |tick| {
+                tick.liquidity_gross != 0
+                    && if zero_for_one {
+                        tick.tick <= tick_current
+                    } else {
+                        tick.tick > tick_current
+                    }
+            })
+            .map(|tick| TickCrossing {
+                tick_index: tick.tick,
+                liquidity_net: tick.liquidity_net,
+            });
+
+        clmm::quote_exact_in(
+            &self.pool(config)?,
+            ticks,
+            amount_in,
+            zero_for_one,
+            Some(clmm::raydium_sqrt_price_at_tick(limit_tick)?),
+        )
+    }
+}
+
+/// Fee tier shared by Raydium CLMM pools
+#[derive(Debug, Clone, Copy)]
+pub struct AmmConfig<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AmmConfig<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        let data = anchor_account(data, &AMM_CONFIG_DISCRIMINATOR, AMM_CONFIG_LEN)?;
+        Ok(AmmConfig { data })
+    }
+
+    /// Fee rate in hundredths of a basis point
+    pub fn trade_fee_rate(&self) -> u32 {
+        read_u32(self.data, TRADE_FEE_RATE)
+    }
+
+    pub fn trade_fee(&self) -> Result<Fee> {
+        Fee::from_hundredths_bps(self.trade_fee_rate())
+    }
+}
+
+/// One tick of a tick array, initialized when it has gross liquidity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickState {
+    pub tick: i32,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+/// Array of [`TICK_ARRAY_SIZE`] ticks of one pool
+#[derive(Debug, Clone, Copy)]
+pub struct TickArrayState<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> TickArrayState<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        let data = anchor_account(data, &TICK_ARRAY_STATE_DISCRIMINATOR, TICK_ARRAY_STATE_LEN)?;
+        Ok(TickArrayState { data })
+    }
+
+    pub fn pool_id(&self) -> &'a Address {
+        read_address(self.data, POOL_ID)
+    }
+
+    pub fn start_tick_index(&self) -> i32 {
+        read_i32(self.data, START_TICK_INDEX)
+    }
+
+    /// Tick at `offset` from the start of the array, below [`TICK_ARRAY_SIZE`]
+    pub fn tick(&self, offset: usize) -> TickState {
+        let tick = TICKS + offset * TICK_STATE_LEN;
+        TickState {
+            tick: read_i32(self.data, tick),
+            liquidity_net: read_i128(self.data, tick + 4),
+            liquidity_gross: read_u128(self.data, tick + 20),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Orca Whirlpool pools and their fixed tick arrays
+
+use super::{anchor_account, read_address, read_i128, read_i32, read_u128, read_u16, Address};
+use crate::clmm::{self, ClmmPool, ClmmQuote, TickCrossing, TickMath, MAX_TICK, MIN_TICK};
+use crate::{Fee, QuoteError, Result};
+
+pub const WHIRLPOOL_DISCRIMINATOR: [u8; 8] = [63, 149, 209, 12, 225, 128, 99, 9];
+pub const WHIRLPOOL_LEN: usize = 653;
+pub const TICK_ARRAY_DISCRIMINATOR: [u8; 8] = [69, 97, 189, 190, 110, 7, 66, 187];
+pub const TICK_ARRAY_LEN: usize = 9_988;
+/// Ticks per tick array
+pub const TICK_ARRAY_SIZE: i32 = 88;
+
+const TICK_SPACING: usize = 41;
+const FEE_RATE: usize = 45;
+const LIQUIDITY: usize = 49;
+const SQRT_PRICE: usize = 65;
+const TICK_CURRENT_INDEX: usize = 81;
+const TOKEN_MINT_A: usize = 101;
+const TOKEN_VAULT_A: usize = 133;
+const TOKEN_MINT_B: usize = 181;
+const TOKEN_VAULT_B: usize = 213;
+
+const START_TICK_INDEX: usize = 8;
+const TICKS: usize = 12;
+const TICK_LEN: usize = 113;
+const TICK_ARRAY_WHIRLPOOL: usize = TICKS + TICK_ARRAY_SIZE as usize * TICK_LEN;
+
+/// Whirlpool pool state
+#[derive(Debug, Clone, Copy)]
+pub struct Whirlpool<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Whirlpool<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        let data = anchor_account(data, &WHIRLPOOL_DISCRIMINATOR, WHIRLPOOL_LEN)?;
+        Ok(Whirlpool { data })
+    }
+
+    pub fn tick_spacing(&self) -> u16 {
+        read_u16(self.data, TICK_SPACING)
+    }
+
+    /// Fee rate in hundredths of a basis point
+    pub fn fee_rate(&self) -> u16 {
+        read_u16(self.data, FEE_RATE)
+    }
+
+    pub fn fee(&self) -> Result<Fee> {
+        Fee::from_hundredths_bps(self.fee_rate() as u32)
+    }
+
+    pub fn liquidity(&self) -> u128 {
+        read_u128(self.data, LIQUIDITY)
+    }
+
+    pub fn sqrt_price(&self) -> u128 {
+        read_u128(self.data, SQRT_PRICE)
+    }
+
+    pub fn tick_current_index(&self) -> i32 {
+        read_i32(self.data, TICK_CURRENT_INDEX)
+    }
+
+    pub fn token_mint_a(&self) -> &'a Address {
+        read_address(self.data, TOKEN_MINT_A)
+    }
+
+    pub fn token_vault_a(&self) -> &'a Address {
+        read_address(self.data, TOKEN_VAULT_A)
+    }
+
+    pub fn token_mint_b(&self) -> &'a Address {
+        read_address(self.data, TOKEN_MINT_B)
+    }
+
+    pub fn token_vault_b(&self) -> &'a Address {
+        read_address(self.data, TOKEN_VAULT_B)
+    }
+
+    pub fn pool(&self) -> Result<ClmmPool> {
+        Ok(ClmmPool {
+            sqrt_price: self.sqrt_price(),
+            liquidity: self.liquidity(),
+            fee: self.fee()?,
+            tick_math: TickMath::Whirlpool,
+        })
+    }
+
+    /// Exact input swap across consecutive `tick_arrays`, given in swap order as the
+    /// `swap` instruction takes them. The quote stops at the edge of the last
+    /// array, where the program would need another one.
+    pub fn quote_exact_in(
+        &self,
+        tick_arrays: &[TickArray],
+        amount_in: u64,
+        a_to_b: bool,
+    ) -> Result<ClmmQuote> {
+        let tick_spacing = self.tick_spacing() as i32;
+        let span = TICK_ARRAY_SIZE * tick_spacing;
+        if tick_spacing == 0
+            || tick_arrays
+                .iter()
+                .any(|array| array.start_tick_index() % span != 0)
+        {
+            return Err(QuoteError::InvalidAccountData);
+        }
+        let step = if a_to_b { -span } else { span };
+        if tick_arrays
+            .windows(2)
+            .any(|pair| pair[1].start_tick_index() != pair[0].start_tick_index() + step)
+        {
+            return Err(QuoteError::InvalidTick);
+        }
+        let last = tick_arrays.last().ok_or(QuoteError::InvalidTick)?;
+        let limit_tick = if a_to_b {
+            last.start_tick_index().max(MIN_TICK)
+        } else {
+            (last.start_tick_index() + span).min(MAX_TICK)
+        };
+
+        let tick_current = self.tick_current_index();
+        let ticks = tick_arrays
+            .iter()
+            .flat_map(move |array| {
+                (0..TICK_ARRAY_SIZE).map(move |offset| {
+                    let offset = if a_to_b {
+                        TICK_ARRAY_SIZE - 1 - offset
+                    } else {
+                        offset
+                    };
+                    (
+                        array.start_tick_index() + offset * tick_spacing,
+                        array.tick(offset as usize),
+                    )
+                })
+            })
+            .filter(move |(tick_index, tick)| {
+                tick.initialized
+                    && if a_to_b {
+                        *tick_index <= tick_current
+                    } else {
+                        *tick_index > tick_current
+                    }
+            })
+            .map(|(tick_index, tick)| TickCrossing {
+                tick_index,
+                liquidity_net: tick.liquidity_net,
+            });
+
+        clmm::quote_exact_in(
+            &self.pool()?,
+            ticks,
+            amount_in,
+            a_to_b,
+            Some(clmm::sqrt_price_at_tick(limit_tick)?),
+        )
+    }
+}
+
+/// One tick of a tick array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    pub initialized: bool,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+/// Fixed array of [`TICK_ARRAY_SIZE`] ticks of one whirlpool
+#[derive(Debug, Clone, Copy)]
+pub struct TickArray<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> TickArray<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self> {
+        let data = anchor_account(data, &TICK_ARRAY_DISCRIMINATOR, TICK_ARRAY_LEN)?;
+        Ok(TickArray { data })
+    }
+
+    pub fn start_tick_index(&self) -> i32 {
+        read_i32(self.data, START_TICK_INDEX)
+    }
+
+    pub fn whirlpool(&self) -> &'a Address {
+        read_address(self.data, TICK_ARRAY_WHIRLPOOL)
+    }
+
+    /// Tick at `offset` from the start of the array, below [`TICK_ARRAY_SIZE`]
+    pub fn tick(&self, offset: usize) -> Tick {
+        let tick = TICKS + offset * TICK_LEN;
+        Tick {
+            initialized: self.data[tick] != 0,
+            liquidity_net: read_i128(self.data, tick + 1),
+            liquidity_gross: read_u128(self.data, tick + 17),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Two-coin stableswap pools (Curve invariant, Saber conventions)
+//!
+//! `Ann * (x + y) + D = Ann * D + D^3 / (4 * x * y)` with `Ann = amp * 2`.
+
+use crate::math::{mul_div_floor, to_u64, U256};
+use crate::{price_impact_bps, Fee, Quote, QuoteError, Result};
+
+pub const N_COINS: u128 = 2;
+/// Highest amplification coefficient accepted
+pub const MAX_AMP: u64 = 1_000_000;
+/// Newton iterations before giving up
+const MAX_ITERATIONS: usize = 255;
+
+/// Output for exactly `amount_in`, fee taken from the input
+pub fn quote_exact_in(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    amp: u64,
+    fee: Fee,
+) -> Result<Quote> {
+    if amount_in == 0 {
+        return Err(QuoteError::ZeroAmount);
+    }
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+
+    let fee_amount = fee.apply(amount_in);
+    let net_in = (amount_in - fee_amount) as u128;
+    let new_reserve_out = compute_y(amp, reserve_in as u128 + net_in, d)?;
+    // One unit is held back against the invariant's rounding
+    let amount_out = (reserve_out as u128)
+        .saturating_sub(new_reserve_out)
+        .saturating_sub(1);
+    let ideal_out = spot_output(amp, reserve_in as u128, reserve_out as u128, d, net_in);
+
+    Ok(Quote {
+        amount_in,
+        amount_out: to_u64(amount_out)?,
+        fee_amount,
+        price_impact_bps: price_impact_bps(ideal_out, amount_out),
+    })
+}
+
+/// Invariant `D` of the pool's reserves
+pub fn compute_d(amp: u64, reserve_a: u64, reserve_b: u64) -> Result<u128> {
+    let ann = ann(amp)?;
+    if reserve_a == 0 || reserve_b == 0 {
+        return Err(QuoteError::ZeroLiquidity);
+    }
+    let (x, y) = (reserve_a as u128, reserve_b as u128);
+    let sum = x + y;
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^3 / (4 * x * y)
+        let d_p = mul_div_floor(d, d, x * N_COINS)?;
+        let d_p = mul_div_floor(d_p, d, y * N_COINS)?;
+
+        let previous = d;
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|value| value.checked_add(d_p.checked_mul(N_COINS)?))
+            .ok_or(QuoteError::Overflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d)
+            .and_then(|value| value.checked_add(d_p.checked_mul(N_COINS + 1)?))
+            .ok_or(QuoteError::Overflow)?;
+        d = mul_div_floor(numerator, d, denominator)?;
+
+        if d.abs_diff(previous) <= 1 {
+            return Ok(d);
+        }
+    }
+    Err(QuoteError::NoConvergence)
+}
+
+/// Reserve of the other coin keeping the invariant at `d` when one coin
+/// holds `reserve`
+pub fn compute_y(amp: u64, reserve: u128, d: u128) -> Result<u128> {
+    let ann = ann(amp)?;
+    if reserve == 0 {
+        return Err(QuoteError::ZeroLiquidity);
+    }
+
+    // c = D^3 / (4 * x * Ann), b = x + D / Ann
+    let c = mul_div_floor(d, d, reserve * N_COINS)?;
+    let c = mul_div_floor(c, d, ann * N_COINS)?;
+    let b = reserve + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        // y = (y^2 + c) / (2y + b - D)
+        let numerator = U256::mul(y, y)
+            .checked_add(U256::from_u128(c))
+            .ok_or(QuoteError::Overflow)?;
+        let denominator = (y * N_COINS + b)
+            .checked_sub(d)
+            .filter(|denominator| *denominator > 0)
+            .ok_or(QuoteError::Overflow)?;
+        y = numerator
+            .checked_div_round(U256::from_u128(denominator), false)
+            .and_then(U256::to_u128)
+            .ok_or(QuoteError::Overflow)?;
+
+        if y.abs_diff(previous) <= 1 {
+            return Ok(y);
+        }
+    }
+    Err(QuoteError::NoConvergence)
+}
+
+fn ann(amp: u64) -> Result<u128> {
+    if amp == 0 || amp > MAX_AMP {
+        return Err(QuoteError::InvalidAmplification);
+    }
+    Ok(amp as u128 * N_COINS)
+}
+
+/// `amount` of the input coin valued at the marginal price
+/// `(4Ann*x*y + D^3/x) / (4Ann*x*y + D^3/y)`, zero if it doesn't fit
+fn spot_output(amp: u64, x: u128, y: u128, d: u128, amount: u128) -> u128 {
+    let ann = amp as u128 * N_COINS;
+    let price = || -> Option<(U256, U256)> {
+        let xy = U256::mul(x, y).checked_mul_u128(ann * 4)?;
+        let d3 = U256::mul(d, d).checked_mul_u128(d)?;
+        let numerator = xy.checked_add(d3.checked_div_rem(U256::from_u128(x))?.0)?;
+        let denominator = xy.checked_add(d3.checked_div_rem(U256::from_u128(y))?.0)?;
+        Some((numerator, denominator))
+    };
+    let Some((numerator, denominator)) = price() else {
+        return 0;
+    };
+
+    // Keep the leading 128 bits of the price
+    let shift = 128u32.saturating_sub(numerator.leading_zeros().min(denominator.leading_zeros()));
+    let (numerator, denominator) = (
+        (numerator >> shift).low_u128(),
+        (denominator >> shift).low_u128(),
+    );
+    if denominator == 0 {
+        return 0;
+    }
+    mul_div_floor(amount, numerator, denominator).unwrap_or(0)
+}
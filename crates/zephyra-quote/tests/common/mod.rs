@@ -0,0 +1,53 @@
+//! Account dump helpers shared by the decoder tests
+
+// Each test crate only uses some of the helpers
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use zephyra_quote::pools::Address;
+
+/// Account dump in the format of `solana account --output json`
+pub struct Fixture {
+    pub pubkey: Address,
+    pub owner: Address,
+    pub data: Vec<u8>,
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{name}.json"))
+}
+
+pub fn fixture(name: &str) -> Fixture {
+    let dump: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(fixture_path(name)).expect("fixture exists"))
+            .unwrap();
+    let account = &dump["account"];
+    assert_eq!(account["data"][1], "base64");
+
+    Fixture {
+        pubkey: address(dump["pubkey"].as_str().unwrap()),
+        owner: address(account["owner"].as_str().unwrap()),
+        data: STANDARD
+            .decode(account["data"][0].as_str().unwrap())
+            .unwrap(),
+    }
+}
+
+pub fn address(base58: &str) -> Address {
+    bs58::decode(base58).into_vec().unwrap().try_into().unwrap()
+}
+
+/// Mainnet capture of `name`, dumped by `scripts/capture-quote-fixtures.sh`
+pub fn mainnet_fixture(name: &str) -> Fixture {
+    let name = format!("{name}_mainnet");
+    assert!(
+        fixture_path(&name).exists(),
+        "tests/fixtures/{name}.json has not been captured, run scripts/capture-quote-fixtures.sh"
+    );
+    fixture(&name)
+}
@@ -0,0 +1,191 @@
+//! Oracle price decoders and checks against the account dumps in
+//! `tests/fixtures`
+
+mod common;
+
+use common::{address, fixture, mainnet_fixture};
+use zephyra_quote::oracles::pyth::{PriceUpdateV2, VerificationLevel};
+use zephyra_quote::oracles::switchboard::PullFeed;
+use zephyra_quote::QuoteError;
+
+const SOL_DECIMALS: u8 = 9;
+const USDC_DECIMALS: u8 = 6;
+const PUBLISH_TIME: i64 = 1_760_000_000;
+
+#[test]
+fn pyth() {
+    let account = fixture("pyth_sol_usd_price_update");
+    assert_eq!(
+        account.owner,
+        address("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ")
+    );
+
+    let update = PriceUpdateV2::decode(&account.data).unwrap();
+    assert_eq!(update.verification_level(), VerificationLevel::Full);
+    // SOL/USD
+    assert_eq!(update.feed_id()[..4], [0xef, 0x0d, 0x8b, 0x6f]);
+    assert_eq!(update.price(), 15_012_345_678);
+    assert_eq!(update.conf(), 7_500_000);
+    assert_eq!(update.exponent(), -8);
+    assert_eq!(update.publish_time(), PUBLISH_TIME);
+    assert_eq!(update.ema_price(), 15_000_000_000);
+    assert_eq!(update.posted_slot(), 370_000_000);
+
+    let price = update.oracle_price().unwrap();
+    assert_eq!(price.validate(PUBLISH_TIME + 60, 60, 5), Ok(()));
+    assert_eq!(
+        price.validate(PUBLISH_TIME + 61, 60, 5),
+        Err(QuoteError::StalePrice)
+    );
+    // Confidence is 4.996 bps of the price
+    assert_eq!(
+        price.validate(PUBLISH_TIME, 60, 4),
+        Err(QuoteError::PriceUncertain)
+    );
+
+    // 1 SOL is worth 150.123456 USDC
+    let sol_in = 1_000_000_000;
+    assert_eq!(
+        price.deviation_bps(sol_in, SOL_DECIMALS, 150_123_456, USDC_DECIMALS),
+        Ok(0)
+    );
+    assert_eq!(
+        price.deviation_bps(sol_in, SOL_DECIMALS, 148_000_000, USDC_DECIMALS),
+        Ok(142)
+    );
+    assert_eq!(
+        price.deviation_bps(sol_in, SOL_DECIMALS, 152_000_000, USDC_DECIMALS),
+        Ok(126)
+    );
+    assert_eq!(
+        price.deviation_bps(1, SOL_DECIMALS, 1, USDC_DECIMALS),
+        Err(QuoteError::ZeroAmount)
+    );
+
+    // Posted with partial guardian verification, which shifts the message
+    let mut partial = account.data[..40].to_vec();
+    partial.extend_from_slice(&[0, 13]);
+    partial.extend_from_slice(&account.data[41..133]);
+    let update = PriceUpdateV2::decode(&partial).unwrap();
+    assert_eq!(
+        update.verification_level(),
+        VerificationLevel::Partial { num_signatures: 13 }
+    );
+    assert_eq!(update.price(), 15_012_345_678);
+    assert_eq!(update.oracle_price(), Err(QuoteError::UnverifiedPrice));
+
+    let mut unknown_level = account.data.clone();
+    unknown_level[40] = 2;
+    assert!(PriceUpdateV2::decode(&unknown_level).is_err());
+    assert!(PriceUpdateV2::decode(&account.data[..133]).is_err());
+}
+
+#[test]
+fn switchboard() {
+    let account = fixture("switchboard_sol_usd_pull_feed");
+    assert_eq!(
+        account.owner,
+        address("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv")
+    );
+
+    let feed = PullFeed::decode(&account.data).unwrap();
+    assert_eq!(
+        feed.queue(),
+        &address("A43DyUGA7s8eXPxqEjJY6EBu1KKbNgfxF8h17VAHn13w")
+    );
+    assert_eq!(feed.min_responses(), 3);
+    assert_eq!(feed.value(), 150_100_000_000_000_000_000);
+    assert_eq!(feed.std_dev(), 50_000_000_000_000_000);
+    assert_eq!(feed.num_samples(), 5);
+    assert_eq!(feed.result_slot(), 370_000_000);
+    assert_eq!(feed.max_staleness(), 250);
+    assert_eq!(feed.last_update_timestamp(), PUBLISH_TIME);
+
+    let price = feed.oracle_price().unwrap();
+    assert_eq!(price.exponent, -18);
+    assert_eq!(price.validate(PUBLISH_TIME + 30, 30, 4), Ok(()));
+    assert_eq!(
+        price.validate(PUBLISH_TIME + 31, 30, 4),
+        Err(QuoteError::StalePrice)
+    );
+    // Standard deviation is 3.33 bps of the price
+    assert_eq!(
+        price.validate(PUBLISH_TIME, 30, 3),
+        Err(QuoteError::PriceUncertain)
+    );
+
+    // 1 SOL is worth 150.1 USDC, 1% more is 100 bps off
+    assert_eq!(
+        price.deviation_bps(1_000_000_000, SOL_DECIMALS, 151_601_000, USDC_DECIMALS),
+        Ok(100)
+    );
+    // Buying 0.999 SOL with 150.1 USDC, the other way round
+    assert_eq!(
+        price.deviation_bps(999_000_000, SOL_DECIMALS, 150_100_000, USDC_DECIMALS),
+        Ok(11)
+    );
+
+    let mut negative = account.data.clone();
+    negative[2264..2280].copy_from_slice(&(-1i128).to_le_bytes());
+    let price = PullFeed::decode(&negative).unwrap().oracle_price().unwrap();
+    assert_eq!(
+        price.validate(PUBLISH_TIME, 30, 4),
+        Err(QuoteError::InvalidPrice)
+    );
+
+    let mut no_samples = account.data.clone();
+    no_samples[2360] = 0;
+    assert_eq!(
+        PullFeed::decode(&no_samples).unwrap().oracle_price(),
+        Err(QuoteError::InvalidPrice)
+    );
+    assert!(PullFeed::decode(&fixture("pyth_sol_usd_price_update").data).is_err());
+}
+
+// Mainnet captures change with their slot, so only the fields fixed for
+// these feeds are checked, and the price against a wide SOL/USD band
+
+#[test]
+#[ignore = "needs the mainnet capture from scripts/capture-quote-fixtures.sh"]
+fn pyth_mainnet() {
+    let account = mainnet_fixture("pyth_sol_usd_price_update");
+    assert_eq!(
+        account.owner,
+        address("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ")
+    );
+
+    let update = PriceUpdateV2::decode(&account.data).unwrap();
+    assert_eq!(update.verification_level(), VerificationLevel::Full);
+    assert_eq!(
+        update.feed_id(),
+        &[
+            0xef, 0x0d, 0x8b, 0x6f, 0xda, 0x2c, 0xeb, 0xa4, 0x1d, 0xa1, 0x5d, 0x40, 0x95, 0xd1,
+            0xda, 0x39, 0x2a, 0x0d, 0x2f, 0x8e, 0xd0, 0xc6, 0xc7, 0xbc, 0x0f, 0x4c, 0xfa, 0xc8,
+            0xc2, 0x80, 0xb5, 0x6d,
+        ]
+    );
+    assert_eq!(update.exponent(), -8);
+    // Between $1 and $10,000
+    assert!((100_000_000..1_000_000_000_000).contains(&update.price()));
+    assert!(update.oracle_price().is_ok());
+}
+
+#[test]
+#[ignore = "needs the mainnet capture from scripts/capture-quote-fixtures.sh"]
+fn switchboard_mainnet() {
+    let account = mainnet_fixture("switchboard_sol_usd_pull_feed");
+    assert_eq!(
+        account.owner,
+        address("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv")
+    );
+
+    let feed = PullFeed::decode(&account.data).unwrap();
+    assert_eq!(
+        feed.queue(),
+        &address("A43DyUGA7s8eXPxqEjJY6EBu1KKbNgfxF8h17VAHn13w")
+    );
+    let price = feed.oracle_price().unwrap();
+    assert_eq!(price.exponent, -18);
+    // Between $1 and $10,000
+    assert!((10i128.pow(18)..10i128.pow(22)).contains(&feed.value()));
+}
@@ -0,0 +1,307 @@
+//! Pool decoders against the account dumps in `tests/fixtures`
+//!
+//! Expected quotes were computed with an arbitrary-precision reference of the
+//! venues' swap loops, independent of this crate's 256-bit math.
+
+mod common;
+
+use common::{address, fixture, mainnet_fixture, Fixture};
+use zephyra_quote::clmm::TickMath;
+use zephyra_quote::pools::raydium_amm::AmmInfo;
+use zephyra_quote::pools::raydium_clmm::{AmmConfig, PoolState, TickArrayState};
+use zephyra_quote::pools::whirlpool::{TickArray, Whirlpool};
+use zephyra_quote::pools::TokenAccount;
+use zephyra_quote::QuoteError;
+
+const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+#[test]
+fn raydium_amm_v4() {
+    let pool = fixture("raydium_amm_v4_pool");
+    let coin_vault = fixture("raydium_amm_v4_coin_vault");
+    let pc_vault = fixture("raydium_amm_v4_pc_vault");
+    assert_eq!(
+        pool.owner,
+        address("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8")
+    );
+
+    let amm = AmmInfo::decode(&pool.data).unwrap();
+    assert_eq!(amm.status(), 6);
+    assert_eq!((amm.coin_decimals(), amm.pc_decimals()), (9, 6));
+    assert_eq!(amm.coin_mint(), &address(SOL_MINT));
+    assert_eq!(amm.pc_mint(), &address(USDC_MINT));
+    assert_eq!(amm.coin_vault(), &coin_vault.pubkey);
+    assert_eq!(amm.pc_vault(), &pc_vault.pubkey);
+    // Quotes charge the swap fee, not the trade fee
+    let fee = amm.swap_fee().unwrap();
+    assert_eq!((fee.numerator(), fee.denominator()), (25, 10_000));
+    let fee = amm.trade_fee().unwrap();
+    assert_eq!((fee.numerator(), fee.denominator()), (20, 10_000));
+
+    let coin_vault = TokenAccount::decode(&coin_vault.data).unwrap();
+    let pc_vault = TokenAccount::decode(&pc_vault.data).unwrap();
+    assert_eq!(
+        amm.reserves(&coin_vault, &pc_vault).unwrap(),
+        (150_000_000_000_000 - 1_234_567, 22_500_000_000_000 - 89_012)
+    );
+
+    let sol_in = amm
+        .quote_exact_in(&coin_vault, &pc_vault, &address(SOL_MINT), 1_000_000_000)
+        .unwrap();
+    assert_eq!(
+        (sol_in.amount_out, sol_in.fee_amount),
+        (149_624_005, 2_500_000)
+    );
+    let large = amm
+        .quote_exact_in(
+            &coin_vault,
+            &pc_vault,
+            &address(SOL_MINT),
+            5_000_000_000_000,
+        )
+        .unwrap();
+    assert_eq!(
+        (large.amount_out, large.fee_amount),
+        (724_050_329_542, 12_500_000_000)
+    );
+    assert!(large.price_impact_bps > sol_in.price_impact_bps);
+    let usdc_in = amm
+        .quote_exact_in(&coin_vault, &pc_vault, &address(USDC_MINT), 150_000_000)
+        .unwrap();
+    assert_eq!(
+        (usdc_in.amount_out, usdc_in.fee_amount),
+        (997_493_362, 375_000)
+    );
+
+    // Vaults swapped, or a mint the pool doesn't hold
+    assert_eq!(
+        amm.reserves(&pc_vault, &coin_vault),
+        Err(QuoteError::MintMismatch)
+    );
+    assert_eq!(
+        amm.quote_exact_in(&coin_vault, &pc_vault, &[7; 32], 1_000),
+        Err(QuoteError::MintMismatch)
+    );
+    assert!(AmmInfo::decode(&pool.data[..700]).is_err());
+}
+
+#[test]
+fn whirlpool() {
+    let pool = fixture("whirlpool_pool");
+    let whirlpool_program = address("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+    assert_eq!(pool.owner, whirlpool_program);
+
+    let whirlpool = Whirlpool::decode(&pool.data).unwrap();
+    assert_eq!(whirlpool.tick_spacing(), 64);
+    assert_eq!(whirlpool.fee_rate(), 3_000);
+    assert_eq!(whirlpool.liquidity(), 4_800_000_000_000);
+    assert_eq!(whirlpool.sqrt_price(), 7_144_393_258_922_745_856);
+    assert_eq!(whirlpool.tick_current_index(), -18_973);
+    assert_eq!(whirlpool.token_mint_a(), &address(SOL_MINT));
+    assert_eq!(whirlpool.token_mint_b(), &address(USDC_MINT));
+    assert_eq!(
+        whirlpool.token_vault_a(),
+        &fixture("whirlpool_vault_a").pubkey
+    );
+    assert_eq!(
+        whirlpool.token_vault_b(),
+        &fixture("whirlpool_vault_b").pubkey
+    );
+    let vault_a = fixture("whirlpool_vault_a");
+    assert_eq!(
+        TokenAccount::decode(&vault_a.data).unwrap().owner(),
+        &pool.pubkey
+    );
+
+    let dumps: Vec<Fixture> = ["0", "1", "2", "up"]
+        .iter()
+        .map(|label| fixture(&format!("whirlpool_tick_array_{label}")))
+        .collect();
+    let arrays: Vec<TickArray> = dumps
+        .iter()
+        .map(|dump| {
+            assert_eq!(dump.owner, whirlpool_program);
+            TickArray::decode(&dump.data).unwrap()
+        })
+        .collect();
+    assert!(arrays.iter().all(|array| array.whirlpool() == &pool.pubkey));
+    assert_eq!(arrays[0].start_tick_index(), -22_528);
+    // Tick -19200 is the 52nd of the array starting at -22528
+    let tick = arrays[0].tick(52);
+    assert!(tick.initialized);
+    assert_eq!(tick.liquidity_net, 1_200_000_000_000);
+    assert!(!arrays[0].tick(51).initialized);
+
+    let a_to_b = &arrays[..3];
+    let small = whirlpool
+        .quote_exact_in(a_to_b, 1_000_000_000, true)
+        .unwrap();
+    assert_eq!(small.quote.amount_out, 149_537_970);
+    assert_eq!(small.quote.fee_amount, 3_000_000);
+    assert_eq!(small.sqrt_price_after, 7_143_818_573_783_298_020);
+    assert_eq!(small.ticks_crossed, 0);
+
+    let large = whirlpool
+        .quote_exact_in(a_to_b, 2_000_000_000_000, true)
+        .unwrap();
+    assert_eq!(large.quote.amount_out, 242_858_564_239);
+    assert_eq!(large.quote.fee_amount, 6_000_000_002);
+    assert_eq!(large.sqrt_price_after, 5_492_110_134_073_988_491);
+    assert_eq!(large.liquidity_after, 1_600_000_000_000);
+    assert_eq!(large.ticks_crossed, 2);
+
+    // Runs past the last array: filled up to its edge only
+    let drained = whirlpool
+        .quote_exact_in(a_to_b, 4_000_000_000_000_000, true)
+        .unwrap();
+    assert_eq!(drained.quote.amount_in, 4_975_398_998_642);
+    assert_eq!(drained.quote.amount_out, 416_601_182_344);
+    assert_eq!(drained.ticks_crossed, 4);
+
+    let b_to_a = [arrays[0], arrays[3]];
+    let up = whirlpool
+        .quote_exact_in(&b_to_a, 200_000_000_000, false)
+        .unwrap();
+    assert_eq!(up.quote.amount_out, 1_178_390_759_073);
+    assert_eq!(up.quote.fee_amount, 600_000_001);
+    assert_eq!(up.sqrt_price_after, 8_085_461_165_488_311_035);
+    assert_eq!(up.ticks_crossed, 1);
+
+    // Arrays out of swap order
+    assert_eq!(
+        whirlpool.quote_exact_in(&[arrays[1], arrays[0]], 2_000_000_000_000, true),
+        Err(QuoteError::InvalidTick)
+    );
+    assert!(Whirlpool::decode(&dumps[0].data).is_err());
+}
+
+#[test]
+fn raydium_clmm() {
+    let pool = fixture("raydium_clmm_pool");
+    let config = fixture("raydium_clmm_amm_config");
+    let clmm_program = address("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+    assert_eq!(pool.owner, clmm_program);
+
+    let state = PoolState::decode(&pool.data).unwrap();
+    assert_eq!(state.amm_config(), &config.pubkey);
+    assert_eq!(state.token_mint_0(), &address(SOL_MINT));
+    assert_eq!(state.token_mint_1(), &address(USDC_MINT));
+    assert_eq!(
+        state.token_vault_0(),
+        &fixture("raydium_clmm_vault_0").pubkey
+    );
+    assert_eq!(
+        state.token_vault_1(),
+        &fixture("raydium_clmm_vault_1").pubkey
+    );
+    assert_eq!(state.tick_spacing(), 10);
+    assert_eq!(state.liquidity(), 9_500_000_000_000);
+    assert_eq!(state.sqrt_price_x64(), 7_144_464_702_498_117_632);
+    assert_eq!(state.tick_current(), -18_972);
+    assert_eq!(state.status(), 0);
+
+    let config = AmmConfig::decode(&config.data).unwrap();
+    assert_eq!(config.trade_fee_rate(), 500);
+
+    let dumps: Vec<Fixture> = ["0", "1", "2", "up"]
+        .iter()
+        .map(|label| fixture(&format!("raydium_clmm_tick_array_{label}")))
+        .collect();
+    let arrays: Vec<TickArrayState> = dumps
+        .iter()
+        .map(|dump| TickArrayState::decode(&dump.data).unwrap())
+        .collect();
+    assert!(arrays.iter().all(|array| array.pool_id() == &pool.pubkey));
+    assert_eq!(arrays[0].start_tick_index(), -19_200);
+    // Tick -18980 is the 22nd of the array starting at -19200
+    let tick = arrays[0].tick(22);
+    assert_eq!(tick.tick, -18_980);
+    assert_eq!(tick.liquidity_net, 3_000_000_000_000);
+    assert_eq!(tick.liquidity_gross, 3_000_000_000_000);
+
+    let zero_for_one = state
+        .quote_exact_in(&config, &arrays[..3], 300_000_000_000, true)
+        .unwrap();
+    assert_eq!(zero_for_one.quote.amount_out, 44_120_887_859);
+    assert_eq!(zero_for_one.quote.fee_amount, 150_000_001);
+    assert_eq!(zero_for_one.sqrt_price_after, 6_968_897_156_472_468_258);
+    assert_eq!(zero_for_one.liquidity_after, 2_500_000_000_000);
+    assert_eq!(zero_for_one.ticks_crossed, 2);
+
+    let one_for_zero = state
+        .quote_exact_in(&config, &[arrays[0], arrays[3]], 50_000_000_000, false)
+        .unwrap();
+    assert_eq!(one_for_zero.quote.amount_out, 326_712_205_503);
+    assert_eq!(one_for_zero.quote.fee_amount, 25_000_001);
+    assert_eq!(one_for_zero.sqrt_price_after, 7_291_448_310_029_290_555);
+    assert_eq!(one_for_zero.ticks_crossed, 1);
+
+    // Swaps disabled by the pool's status bits
+    let mut disabled = pool.data.clone();
+    disabled[389] = 1 << 4;
+    assert_eq!(
+        PoolState::decode(&disabled)
+            .unwrap()
+            .quote_exact_in(&config, &arrays[..3], 1_000, true),
+        Err(QuoteError::PoolDisabled)
+    );
+}
+
+// Mainnet captures change with their slot, so only the fields fixed for
+// these pools are checked, and the sqrt price against the current tick
+
+#[test]
+#[ignore = "needs the mainnet capture from scripts/capture-quote-fixtures.sh"]
+fn raydium_amm_v4_mainnet() {
+    let pool = mainnet_fixture("raydium_amm_v4_pool");
+    assert_eq!(
+        pool.owner,
+        address("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8")
+    );
+
+    let amm = AmmInfo::decode(&pool.data).unwrap();
+    assert_eq!((amm.coin_decimals(), amm.pc_decimals()), (9, 6));
+    assert_eq!(amm.coin_mint(), &address(SOL_MINT));
+    assert_eq!(amm.pc_mint(), &address(USDC_MINT));
+    let fee = amm.swap_fee().unwrap();
+    assert_eq!((fee.numerator(), fee.denominator()), (25, 10_000));
+}
+
+#[test]
+#[ignore = "needs the mainnet capture from scripts/capture-quote-fixtures.sh"]
+fn whirlpool_mainnet() {
+    let pool = mainnet_fixture("whirlpool_pool");
+    assert_eq!(
+        pool.owner,
+        address("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc")
+    );
+
+    let whirlpool = Whirlpool::decode(&pool.data).unwrap();
+    assert_eq!(whirlpool.token_mint_a(), &address(SOL_MINT));
+    assert_eq!(whirlpool.token_mint_b(), &address(USDC_MINT));
+    assert!(whirlpool.liquidity() > 0);
+    let tick = whirlpool.tick_current_index();
+    let sqrt_price = whirlpool.sqrt_price();
+    assert!(TickMath::Whirlpool.sqrt_price_at_tick(tick).unwrap() <= sqrt_price);
+    assert!(sqrt_price < TickMath::Whirlpool.sqrt_price_at_tick(tick + 1).unwrap());
+}
+
+#[test]
+#[ignore = "needs the mainnet capture from scripts/capture-quote-fixtures.sh"]
+fn raydium_clmm_mainnet() {
+    let pool = mainnet_fixture("raydium_clmm_pool");
+    assert_eq!(
+        pool.owner,
+        address("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK")
+    );
+
+    let state = PoolState::decode(&pool.data).unwrap();
+    assert_eq!(state.token_mint_0(), &address(SOL_MINT));
+    assert_eq!(state.token_mint_1(), &address(USDC_MINT));
+    assert!(state.liquidity() > 0);
+    let tick = state.tick_current();
+    let sqrt_price = state.sqrt_price_x64();
+    assert!(TickMath::Raydium.sqrt_price_at_tick(tick).unwrap() <= sqrt_price);
+    assert!(sqrt_price < TickMath::Raydium.sqrt_price_at_tick(tick + 1).unwrap());
+}
@@ -0,0 +1,314 @@
+//! Property tests of the quote math: invariants never decrease, outputs are
+//! monotonic and bounded, and round trips never pay out more than they take
+
+use proptest::prelude::*;
+use zephyra_quote::clmm::{
+    self, ClmmPool, TickCrossing, TickMath, MAX_SQRT_PRICE, MAX_TICK, MIN_SQRT_PRICE, MIN_TICK,
+};
+use zephyra_quote::{constant_product, stableswap, Fee, QuoteError};
+
+fn fee() -> impl Strategy<Value = Fee> {
+    prop_oneof![
+        Just(Fee::ZERO),
+        (1u16..1_000).prop_map(|bps| Fee::from_bps(bps).unwrap())
+    ]
+}
+
+#[test]
+fn tick_bounds_match_whirlpool() {
+    assert_eq!(clmm::sqrt_price_at_tick(0), Ok(1u128 << 64));
+    assert_eq!(clmm::sqrt_price_at_tick(MIN_TICK), Ok(MIN_SQRT_PRICE));
+    assert_eq!(clmm::sqrt_price_at_tick(MAX_TICK), Ok(MAX_SQRT_PRICE));
+    assert_eq!(
+        clmm::sqrt_price_at_tick(MAX_TICK + 1),
+        Err(QuoteError::InvalidTick)
+    );
+}
+
+#[test]
+fn raydium_tick_bounds() {
+    assert_eq!(clmm::raydium_sqrt_price_at_tick(0), Ok(1u128 << 64));
+    assert_eq!(
+        clmm::raydium_sqrt_price_at_tick(MIN_TICK),
+        Ok(MIN_SQRT_PRICE)
+    );
+    assert_eq!(
+        clmm::raydium_sqrt_price_at_tick(MAX_TICK),
+        Ok(clmm::RAYDIUM_MAX_SQRT_PRICE)
+    );
+}
+
+#[test]
+fn fee_rounds_up() {
+    let fee = Fee::from_bps(30).unwrap();
+    assert_eq!(fee.apply(1), 1);
+    assert_eq!(fee.apply(10_000), 30);
+    assert_eq!(fee.apply(10_001), 31);
+    assert_eq!(Fee::from_bps(10_000), Err(QuoteError::InvalidFee));
+}
+
+#[test]
+fn constant_product_known_quote() {
+    let quote = constant_product::quote_exact_in(
+        1_000_000,
+        1_000_000_000,
+        2_000_000_000,
+        Fee::from_bps(30).unwrap(),
+    )
+    .unwrap();
+    // net 997_000; 997_000 * 2e9 / 1_000_997_000 = 1_992_013.96...
+    assert_eq!(quote.fee_amount, 3_000);
+    assert_eq!(quote.amount_out, 1_992_013);
+    assert_eq!(quote.price_impact_bps, 10);
+}
+
+#[test]
+fn clmm_crosses_ticks() {
+    let pool = ClmmPool {
+        sqrt_price: clmm::sqrt_price_at_tick(0).unwrap(),
+        liquidity: 1_000_000_000,
+        fee: Fee::from_hundredths_bps(3_000).unwrap(),
+        tick_math: TickMath::Whirlpool,
+    };
+    // The active range ends at tick -10, a wider position continues to -1000
+    let ticks = [
+        TickCrossing {
+            tick_index: -10,
+            liquidity_net: 600_000_000,
+        },
+        TickCrossing {
+            tick_index: -1_000,
+            liquidity_net: 400_000_000,
+        },
+    ];
+
+    let quote = clmm::quote_exact_in(&pool, ticks, 2_000_000, true, None).unwrap();
+    assert_eq!(quote.ticks_crossed, 1);
+    assert_eq!(quote.liquidity_after, 400_000_000);
+    assert_eq!(quote.quote.amount_in, 2_000_000);
+    assert!(quote.sqrt_price_after < clmm::sqrt_price_at_tick(-10).unwrap());
+
+    // Past the last tick there is no liquidity left: the swap fills partially
+    let drained = clmm::quote_exact_in(&pool, ticks, u64::MAX / 2, true, None).unwrap();
+    assert_eq!(drained.ticks_crossed, 2);
+    assert_eq!(drained.liquidity_after, 0);
+    assert!(drained.quote.amount_in < u64::MAX / 2);
+}
+
+proptest! {
+    #[test]
+    fn constant_product_never_decreases_k(
+        amount_in in 1u64..1_000_000_000_000,
+        reserve_in in 1_000u64..1_000_000_000_000_000,
+        reserve_out in 1_000u64..1_000_000_000_000_000,
+        fee in fee(),
+    ) {
+        let quote = constant_product::quote_exact_in(amount_in, reserve_in, reserve_out, fee).unwrap();
+        prop_assert!(quote.amount_out < reserve_out);
+        let k_before = reserve_in as u128 * reserve_out as u128;
+        let k_after = (reserve_in as u128 + amount_in as u128) * (reserve_out - quote.amount_out) as u128;
+        prop_assert!(k_after >= k_before);
+    }
+
+    #[test]
+    fn constant_product_is_monotonic(
+        amount_in in 1u64..1_000_000_000_000,
+        extra in 1u64..1_000_000_000,
+        reserve_in in 1_000u64..1_000_000_000_000_000,
+        reserve_out in 1_000u64..1_000_000_000_000_000,
+        fee in fee(),
+    ) {
+        let smaller = constant_product::quote_exact_in(amount_in, reserve_in, reserve_out, fee).unwrap();
+        let larger = constant_product::quote_exact_in(amount_in + extra, reserve_in, reserve_out, fee).unwrap();
+        prop_assert!(larger.amount_out >= smaller.amount_out);
+        prop_assert!(larger.price_impact_bps >= smaller.price_impact_bps);
+    }
+
+    #[test]
+    fn constant_product_exact_out_covers_output(
+        amount_out in 1u64..1_000_000_000,
+        reserve_in in 1_000u64..1_000_000_000_000,
+        reserve_out in 1_000_000_001u64..1_000_000_000_000,
+        fee in fee(),
+    ) {
+        let quote = match constant_product::quote_exact_out(amount_out, reserve_in, reserve_out, fee) {
+            Ok(quote) => quote,
+            Err(error) => {
+                prop_assert_eq!(error, QuoteError::Overflow);
+                return Ok(());
+            }
+        };
+        let exact_in = constant_product::quote_exact_in(quote.amount_in, reserve_in, reserve_out, fee).unwrap();
+        prop_assert!(exact_in.amount_out >= amount_out);
+        // Without a fee one unit less doesn't buy the output
+        if fee == Fee::ZERO && quote.amount_in > 1 {
+            let short = constant_product::quote_exact_in(quote.amount_in - 1, reserve_in, reserve_out, fee).unwrap();
+            prop_assert!(short.amount_out < amount_out);
+        }
+    }
+
+    #[test]
+    fn constant_product_round_trip_loses(
+        amount_in in 1u64..1_000_000_000_000,
+        reserve_in in 1_000u64..1_000_000_000_000_000,
+        reserve_out in 1_000u64..1_000_000_000_000_000,
+        fee in fee(),
+    ) {
+        let there = constant_product::quote_exact_in(amount_in, reserve_in, reserve_out, fee).unwrap();
+        prop_assume!(there.amount_out > 0);
+        let back = constant_product::quote_exact_in(
+            there.amount_out,
+            reserve_out - there.amount_out,
+            reserve_in + amount_in,
+            fee,
+        )
+        .unwrap();
+        prop_assert!(back.amount_out <= amount_in);
+    }
+
+    #[test]
+    fn stableswap_never_decreases_d(
+        amount_in in 1u64..1_000_000_000_000,
+        reserve_in in 1_000_000u64..1_000_000_000_000_000,
+        reserve_out in 1_000_000u64..1_000_000_000_000_000,
+        amp in 1u64..10_000,
+        fee in fee(),
+    ) {
+        let quote = stableswap::quote_exact_in(amount_in, reserve_in, reserve_out, amp, fee).unwrap();
+        prop_assert!(quote.amount_out < reserve_out);
+        let d_before = stableswap::compute_d(amp, reserve_in, reserve_out).unwrap();
+        let d_after = stableswap::compute_d(amp, reserve_in + amount_in, reserve_out - quote.amount_out).unwrap();
+        prop_assert!(d_after >= d_before);
+    }
+
+    #[test]
+    fn stableswap_is_monotonic(
+        amount_in in 1u64..1_000_000_000_000,
+        extra in 1u64..1_000_000_000,
+        reserve_in in 1_000_000u64..1_000_000_000_000_000,
+        reserve_out in 1_000_000u64..1_000_000_000_000_000,
+        amp in 1u64..10_000,
+        fee in fee(),
+    ) {
+        let smaller = stableswap::quote_exact_in(amount_in, reserve_in, reserve_out, amp, fee).unwrap();
+        let larger = stableswap::quote_exact_in(amount_in + extra, reserve_in, reserve_out, amp, fee).unwrap();
+        prop_assert!(larger.amount_out >= smaller.amount_out);
+    }
+
+    #[test]
+    fn stableswap_round_trip_loses(
+        amount_in in 1u64..1_000_000_000_000,
+        reserve_in in 1_000_000u64..1_000_000_000_000_000,
+        reserve_out in 1_000_000u64..1_000_000_000_000_000,
+        amp in 1u64..10_000,
+        fee in fee(),
+    ) {
+        let there = stableswap::quote_exact_in(amount_in, reserve_in, reserve_out, amp, fee).unwrap();
+        prop_assume!(there.amount_out > 0);
+        let back = stableswap::quote_exact_in(
+            there.amount_out,
+            reserve_out - there.amount_out,
+            reserve_in + amount_in,
+            amp,
+            fee,
+        )
+        .unwrap();
+        prop_assert!(back.amount_out <= amount_in);
+    }
+
+    #[test]
+    fn tick_prices_are_monotonic(
+        tick in MIN_TICK..MAX_TICK,
+        tick_math in prop_oneof![Just(TickMath::Whirlpool), Just(TickMath::Raydium)],
+    ) {
+        let price = tick_math.sqrt_price_at_tick(tick).unwrap();
+        let next = tick_math.sqrt_price_at_tick(tick + 1).unwrap();
+        prop_assert!(next > price);
+        prop_assert!((tick_math.min_sqrt_price()..=tick_math.max_sqrt_price()).contains(&price));
+    }
+
+    #[test]
+    fn tick_tables_agree_closely(tick in MIN_TICK..=MAX_TICK) {
+        let whirlpool = clmm::sqrt_price_at_tick(tick).unwrap();
+        let raydium = clmm::raydium_sqrt_price_at_tick(tick).unwrap();
+        // Within one part in 10^9
+        prop_assert!(whirlpool.abs_diff(raydium) <= whirlpool / 1_000_000_000 + 1);
+    }
+
+    #[test]
+    fn clmm_moves_price_in_swap_direction(
+        amount_in in 1u64..1_000_000_000_000,
+        tick in -100_000i32..100_000,
+        liquidity in 1_000_000u128..1_000_000_000_000_000_000,
+        a_to_b in any::<bool>(),
+        fee_rate in 0u32..10_000,
+    ) {
+        let pool = ClmmPool {
+            sqrt_price: clmm::sqrt_price_at_tick(tick).unwrap(),
+            liquidity,
+            fee: Fee::from_hundredths_bps(fee_rate).unwrap(),
+            tick_math: TickMath::Whirlpool,
+        };
+        let quote = clmm::quote_exact_in(&pool, [], amount_in, a_to_b, None).unwrap();
+        if a_to_b {
+            prop_assert!(quote.sqrt_price_after <= pool.sqrt_price);
+        } else {
+            prop_assert!(quote.sqrt_price_after >= pool.sqrt_price);
+        }
+        prop_assert_eq!(quote.quote.amount_in, amount_in);
+        prop_assert!(quote.quote.fee_amount <= amount_in);
+    }
+
+    #[test]
+    fn clmm_round_trip_loses(
+        amount_in in 1u64..1_000_000_000_000,
+        tick in -100_000i32..100_000,
+        liquidity in 1_000_000u128..1_000_000_000_000_000_000,
+        a_to_b in any::<bool>(),
+        fee_rate in 0u32..10_000,
+    ) {
+        let fee = Fee::from_hundredths_bps(fee_rate).unwrap();
+        let pool = ClmmPool {
+            sqrt_price: clmm::sqrt_price_at_tick(tick).unwrap(),
+            liquidity,
+            fee,
+            tick_math: TickMath::Whirlpool,
+        };
+        let there = clmm::quote_exact_in(&pool, [], amount_in, a_to_b, None).unwrap();
+        prop_assume!(there.quote.amount_out > 0 && there.sqrt_price_after != pool.sqrt_price);
+        let moved = ClmmPool {
+            sqrt_price: there.sqrt_price_after,
+            liquidity,
+            fee,
+            tick_math: TickMath::Whirlpool,
+        };
+        let back = clmm::quote_exact_in(&moved, [], there.quote.amount_out, !a_to_b, None).unwrap();
+        prop_assert!(back.quote.amount_out <= amount_in);
+    }
+
+    #[test]
+    fn clmm_tick_crossing_is_monotonic(
+        amount_in in 1u64..1_000_000_000,
+        extra in 1u64..1_000_000_000,
+        spacing in 1i32..200,
+        liquidity in 1_000_000u128..1_000_000_000_000,
+    ) {
+        let pool = ClmmPool {
+            sqrt_price: clmm::sqrt_price_at_tick(0).unwrap(),
+            liquidity,
+            fee: Fee::from_hundredths_bps(3_000).unwrap(),
+            tick_math: TickMath::Whirlpool,
+        };
+        // Liquidity steps up at every tick to the right
+        let ticks: Vec<TickCrossing> = (1..=8)
+            .map(|index| TickCrossing {
+                tick_index: index * spacing,
+                liquidity_net: liquidity as i128 / 2,
+            })
+            .collect();
+        let smaller = clmm::quote_exact_in(&pool, ticks.iter().copied(), amount_in, false, None).unwrap();
+        let larger = clmm::quote_exact_in(&pool, ticks.iter().copied(), amount_in + extra, false, None).unwrap();
+        prop_assert!(larger.quote.amount_out >= smaller.quote.amount_out);
+        prop_assert!(larger.ticks_crossed >= smaller.ticks_crossed);
+    }
+}
@@ -0,0 +1,323 @@
+//! Protection manager instruction builders and PDA helpers
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{system_program, AnchorSerialize, InstructionData, ToAccountMetas};
+use zephyra_protection_manager::magicblock_integration::{
+    RollbackReason, RollupOp, RollupResult, RollupSessionId, SessionBudget,
+};
+use zephyra_protection_manager::{accounts, generate_transaction_id, instruction, ID};
+
+/// Program data account of the protection manager, holding its upgrade authority
+pub fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+pub fn rollup_validator_address(validator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"rollup_validator", validator.as_ref()], &ID).0
+}
+
+/// Session of a single transaction, keyed by the transaction account's address
+pub fn rollup_session_address(transaction_account: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"rollup_session", transaction_account.as_ref()], &ID).0
+}
+
+pub fn batch_rollup_session_address(batch_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"batch_rollup_session", batch_id.as_ref()], &ID).0
+}
+
+pub fn protection_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"protection", owner.as_ref()], &ID).0
+}
+
+pub fn transaction_address(owner: &Pubkey, transaction_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"transaction", owner.as_ref(), transaction_id], &ID).0
+}
+
+pub fn initialize_config(
+    admin: &Pubkey,
+    treasury: Pubkey,
+    submission_deposit: u64,
+    transaction_expiry: i64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitializeConfig {
+            config: config_address(),
+            program: ID,
+            program_data: program_data_address(),
+            admin: *admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeConfig {
+            treasury,
+            submission_deposit,
+            transaction_expiry,
+        }
+        .data(),
+    }
+}
+
+pub fn register_rollup_validator(admin: &Pubkey, validator: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::RegisterRollupValidator {
+            config: config_address(),
+            rollup_validator: rollup_validator_address(&validator),
+            admin: *admin,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RegisterRollupValidator { validator }.data(),
+    }
+}
+
+/// Hold commits open for `challenge_window_slots`, backed by a committer bond
+pub fn update_rollup_config(
+    admin: &Pubkey,
+    challenge_window_slots: u64,
+    committer_bond: u64,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::UpdateConfig {
+            config: config_address(),
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateRollupConfig {
+            challenge_window_slots,
+            committer_bond,
+        }
+        .data(),
+    }
+}
+
+pub fn initialize_protection(payer: &Pubkey, owner: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitializeProtection {
+            protection_account: protection_address(&owner),
+            payer: *payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeProtection {
+            wallet_address: owner,
+        }
+        .data(),
+    }
+}
+
+/// `submit_transaction` for `owner`, landing at `unix_timestamp`, which keys
+/// the transaction ID returned by the program
+pub fn submit_transaction(
+    owner: &Pubkey,
+    input_token: Pubkey,
+    output_token: Pubkey,
+    input_amount: u64,
+    min_output_amount: u64,
+    unix_timestamp: i64,
+) -> Instruction {
+    let transaction_id = generate_transaction_id(
+        owner,
+        &input_token,
+        &output_token,
+        input_amount,
+        unix_timestamp,
+    );
+    Instruction {
+        program_id: ID,
+        accounts: accounts::SubmitTransaction {
+            config: config_address(),
+            protection_account: protection_address(owner),
+            transaction_account: transaction_address(owner, &transaction_id),
+            payer: *owner,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::SubmitTransaction {
+            input_token,
+            output_token,
+            input_amount,
+            min_output_amount,
+        }
+        .data(),
+    }
+}
+
+/// `init_rollup_session` for a transaction, opened by its owner
+pub fn init_rollup_session(
+    owner: &Pubkey,
+    transaction_id: [u8; 32],
+    executor: Option<Pubkey>,
+    budget: Option<SessionBudget>,
+) -> Instruction {
+    let transaction_account = transaction_address(owner, &transaction_id);
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitRollupSession {
+            session_account: rollup_session_address(&transaction_account),
+            transaction_account,
+            payer: *owner,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitRollupSession {
+            transaction_id,
+            executor,
+            budget,
+        }
+        .data(),
+    }
+}
+
+/// `init_batch_rollup_session` for a batch, opened by its authority, with the
+/// member transactions in batch order
+pub fn init_batch_rollup_session(
+    authority: &Pubkey,
+    batch_account: Pubkey,
+    batch_id: [u8; 32],
+    members: &[Pubkey],
+    executor: Option<Pubkey>,
+    budget: Option<SessionBudget>,
+) -> Instruction {
+    let mut accounts = accounts::InitBatchRollupSession {
+        session_account: batch_rollup_session_address(&batch_id),
+        batch_account,
+        config: config_address(),
+        payer: *authority,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(
+        members
+            .iter()
+            .map(|transaction| AccountMeta::new(*transaction, false)),
+    );
+
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: instruction::InitBatchRollupSession {
+            batch_id,
+            executor,
+            budget,
+        }
+        .data(),
+    }
+}
+
+pub fn execute_in_rollup(
+    authority: &Pubkey,
+    session_account: Pubkey,
+    session_id: RollupSessionId,
+    op: &RollupOp,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::ExecuteInRollup {
+            session_account,
+            output_account: None,
+            authority: *authority,
+            system_program: None,
+        }
+        .to_account_metas(None),
+        data: instruction::ExecuteInRollup {
+            session_id,
+            instruction_data: op.try_to_vec().expect("rollup op serializes"),
+        }
+        .data(),
+    }
+}
+
+/// `commit_rollup` for a session, co-signed by the validator bonding it; must
+/// directly follow the validator's Ed25519 signature instruction
+pub fn commit_rollup(
+    authority: &Pubkey,
+    session_account: Pubkey,
+    session_id: RollupSessionId,
+    validator: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::CommitRollup {
+            session_account,
+            authority: *authority,
+            config: config_address(),
+            rollup_validator: rollup_validator_address(validator),
+            validator: *validator,
+            instructions_sysvar: anchor_lang::solana_program::sysvar::instructions::ID,
+            transaction_account: None,
+            payer: None,
+            magic_context: None,
+            magic_program: None,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::CommitRollup { session_id }.data(),
+    }
+}
+
+/// `rollback_rollup` for a session, restoring its linked transaction when given
+pub fn rollback_rollup(
+    authority: &Pubkey,
+    session_account: Pubkey,
+    transaction_account: Option<Pubkey>,
+    session_id: RollupSessionId,
+    reason: RollbackReason,
+    note: Option<String>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::RollbackRollup {
+            session_account,
+            authority: *authority,
+            transaction_account,
+        }
+        .to_account_metas(None),
+        data: instruction::RollbackRollup {
+            session_id,
+            reason,
+            note,
+        }
+        .data(),
+    }
+}
+
+/// `challenge_rollup` disputing op `index` of the session of `owner`'s
+/// transaction, with the op's inclusion proof in the committed op root
+pub fn challenge_rollup(
+    challenger: &Pubkey,
+    owner: &Pubkey,
+    transaction_id: [u8; 32],
+    index: u32,
+    op: RollupOp,
+    claimed_result: RollupResult,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let transaction_account = transaction_address(owner, &transaction_id);
+    Instruction {
+        program_id: ID,
+        accounts: accounts::ChallengeRollup {
+            session_account: rollup_session_address(&transaction_account),
+            challenger: *challenger,
+            transaction_account: Some(transaction_account),
+        }
+        .to_account_metas(None),
+        data: instruction::ChallengeRollup {
+            transaction_id,
+            index,
+            op,
+            claimed_result,
+            proof,
+        }
+        .data(),
+    }
+}
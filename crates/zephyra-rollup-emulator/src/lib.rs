@@ -0,0 +1,31 @@
+//! In-process MagicBlock ephemeral rollup emulator
+//!
+//! Runs the Zephyra protection manager on two local SVMs: a base layer and an
+//! ephemeral rollup next to it. Accounts delegated from the base layer are
+//! cloned into the rollup and locked under the delegation program until they
+//! are undelegated, rollup ops run against the rollup copy, and commits are
+//! signed by the emulated rollup validator so `commit_rollup` accepts them.
+//!
+//! The protection manager must be built for SBF first (`anchor build`); see
+//! [`load_program_elf`].
+
+pub mod instructions;
+pub mod rollup;
+pub mod svm;
+
+pub use rollup::{EmulatorError, EphemeralRollup};
+pub use svm::{LocalSvm, TransactionError, TransactionResult};
+
+use std::path::PathBuf;
+
+/// File name of the protection manager's SBF build
+pub const PROGRAM_ELF: &str = "zephyra_protection_manager.so";
+
+/// Load the protection manager ELF from `SBF_OUT_DIR`, falling back to the
+/// workspace's `target/deploy`. Returns `None` when it hasn't been built.
+pub fn load_program_elf() -> Option<Vec<u8>> {
+    let dir = std::env::var_os("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy"));
+    std::fs::read(dir.join(PROGRAM_ELF)).ok()
+}
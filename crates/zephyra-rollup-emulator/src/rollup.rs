@@ -0,0 +1,211 @@
+//! Emulated ephemeral rollup running next to a base-layer [`LocalSvm`]
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::AnchorDeserialize;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use zephyra_protection_manager::delegation::DELEGATION_PROGRAM_ID;
+use zephyra_protection_manager::magicblock_integration::{commit_hash, CommitProof, RollupSession};
+
+use crate::instructions;
+use crate::svm::{LocalSvm, TransactionError, TransactionResult};
+
+/// Errors raised by the emulator itself rather than the program
+#[derive(Debug)]
+pub enum EmulatorError {
+    /// Account is missing from the layer it was read from
+    AccountNotFound(Pubkey),
+    /// Account is already delegated to the rollup
+    AlreadyDelegated(Pubkey),
+    /// Rollup transaction writes to an account that isn't delegated
+    NotDelegated(Pubkey),
+    /// `commit_rollup` didn't return a commit proof
+    InvalidReturnData,
+    /// Program rejected the transaction
+    Transaction(TransactionError),
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmulatorError::AccountNotFound(pubkey) => write!(f, "account {pubkey} not found"),
+            EmulatorError::AlreadyDelegated(pubkey) => {
+                write!(f, "account {pubkey} is already delegated")
+            }
+            EmulatorError::NotDelegated(pubkey) => {
+                write!(
+                    f,
+                    "writable account {pubkey} is not delegated to the rollup"
+                )
+            }
+            EmulatorError::InvalidReturnData => write!(f, "commit returned no commit proof"),
+            EmulatorError::Transaction(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EmulatorError {}
+
+impl From<TransactionError> for EmulatorError {
+    fn from(error: TransactionError) -> Self {
+        EmulatorError::Transaction(error)
+    }
+}
+
+/// Ephemeral rollup with its own SVM and validator key
+///
+/// Delegation mirrors the MagicBlock delegation program: the base-layer copy
+/// of a delegated account is reassigned to the delegation program, so the
+/// protection manager rejects it there, while the rollup works on a clone.
+/// Undelegating writes the rollup state back under its original owner.
+pub struct EphemeralRollup {
+    svm: LocalSvm,
+    validator: Keypair,
+    delegated: HashMap<Pubkey, Pubkey>, // account -> base-layer owner
+}
+
+impl EphemeralRollup {
+    /// Rollup running the protection manager ELF, signing commits with `validator`
+    pub fn new(program_elf: &[u8], validator: Keypair) -> Self {
+        Self {
+            svm: LocalSvm::new(program_elf),
+            validator,
+            delegated: HashMap::new(),
+        }
+    }
+
+    pub fn validator(&self) -> Pubkey {
+        self.validator.pubkey()
+    }
+
+    pub fn svm(&self) -> &LocalSvm {
+        &self.svm
+    }
+
+    pub fn svm_mut(&mut self) -> &mut LocalSvm {
+        &mut self.svm
+    }
+
+    pub fn is_delegated(&self, account: &Pubkey) -> bool {
+        self.delegated.contains_key(account)
+    }
+
+    /// Clone a read-only account (config, validator registration) from the base layer
+    pub fn clone_account(
+        &mut self,
+        base: &LocalSvm,
+        account: &Pubkey,
+    ) -> Result<(), EmulatorError> {
+        let state = base
+            .get_account(account)
+            .ok_or(EmulatorError::AccountNotFound(*account))?;
+        self.svm.set_account(*account, state.clone());
+        Ok(())
+    }
+
+    /// Delegate a base-layer account to the rollup
+    pub fn delegate(&mut self, base: &mut LocalSvm, account: &Pubkey) -> Result<(), EmulatorError> {
+        if self.is_delegated(account) {
+            return Err(EmulatorError::AlreadyDelegated(*account));
+        }
+        let mut state = base
+            .get_account(account)
+            .cloned()
+            .ok_or(EmulatorError::AccountNotFound(*account))?;
+        self.svm.set_account(*account, state.clone());
+        self.delegated.insert(*account, state.owner);
+
+        state.owner = DELEGATION_PROGRAM_ID;
+        base.set_account(*account, state);
+        Ok(())
+    }
+
+    /// Write a delegated account's rollup state back to the base layer
+    pub fn undelegate(
+        &mut self,
+        base: &mut LocalSvm,
+        account: &Pubkey,
+    ) -> Result<(), EmulatorError> {
+        let owner = self
+            .delegated
+            .remove(account)
+            .ok_or(EmulatorError::NotDelegated(*account))?;
+        match self.svm.remove_account(account) {
+            Some(mut state) => {
+                state.owner = owner;
+                base.set_account(*account, state);
+            }
+            // Closed inside the rollup
+            None => {
+                base.remove_account(account);
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute a transaction inside the rollup. Writable accounts must be
+    /// delegated, except signers paying for the transaction.
+    pub fn process_transaction(
+        &mut self,
+        instructions: &[Instruction],
+    ) -> Result<TransactionResult, EmulatorError> {
+        for meta in instructions
+            .iter()
+            .flat_map(|instruction| &instruction.accounts)
+        {
+            if meta.is_writable && !meta.is_signer && !self.is_delegated(&meta.pubkey) {
+                return Err(EmulatorError::NotDelegated(meta.pubkey));
+            }
+        }
+        Ok(self.svm.process_transaction(instructions)?)
+    }
+
+    /// Ed25519 instruction with the validator's signature over a session's commit hash
+    pub fn sign_commit(&self, session: &RollupSession) -> Instruction {
+        let hash = commit_hash(
+            &session.id,
+            &session.state_hash,
+            &session.op_root,
+            session.instructions_executed,
+        );
+        let signature = self.validator.sign_message(&hash);
+        let signature: &[u8; 64] = signature
+            .as_ref()
+            .try_into()
+            .expect("ed25519 signatures are 64 bytes");
+        solana_ed25519_program::new_ed25519_instruction_with_signature(
+            &hash,
+            signature,
+            &self.validator.pubkey().to_bytes(),
+        )
+    }
+
+    /// Commit a delegated session inside the rollup with a validator signature
+    pub fn commit(
+        &mut self,
+        authority: &Pubkey,
+        session_account: &Pubkey,
+    ) -> Result<CommitProof, EmulatorError> {
+        let session: RollupSession = self
+            .svm
+            .get_anchor_account(session_account)
+            .ok_or(EmulatorError::AccountNotFound(*session_account))?;
+        // Batch sessions settle their member transactions in the commit
+        let mut commit =
+            instructions::commit_rollup(authority, *session_account, session.id, &self.validator());
+        commit.accounts.extend(
+            session
+                .members
+                .iter()
+                .map(|member| AccountMeta::new(member.transaction, false)),
+        );
+        let instructions = [self.sign_commit(&session), commit];
+        let result = self.process_transaction(&instructions)?;
+        CommitProof::try_from_slice(&result.return_data)
+            .map_err(|_| EmulatorError::InvalidReturnData)
+    }
+}
@@ -0,0 +1,256 @@
+//! Minimal local SVM with a persistent account store
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::AccountDeserialize;
+use mollusk_svm::program::create_program_data_account_loader_v3;
+use mollusk_svm::program::loader_keys::LOADER_V3;
+use mollusk_svm::Mollusk;
+use solana_account::Account;
+use solana_instruction::error::InstructionError;
+use solana_instruction::BorrowedInstruction;
+use solana_instructions_sysvar::construct_instructions_data;
+use solana_log_collector::LogCollector;
+
+/// Unix timestamp the local clock starts at
+pub const GENESIS_UNIX_TIMESTAMP: i64 = 1_700_000_000;
+
+/// Local SVM with the protection manager loaded, executing transactions
+/// atomically against an in-memory account store
+pub struct LocalSvm {
+    mollusk: Mollusk,
+    accounts: HashMap<Pubkey, Account>,
+}
+
+/// Outcome of a successful transaction
+#[derive(Debug, Clone)]
+pub struct TransactionResult {
+    pub compute_units_consumed: u64,
+    pub return_data: Vec<u8>,
+    pub logs: Vec<String>,
+}
+
+/// Failed transaction: the instruction that failed and why
+#[derive(Debug, Clone)]
+pub struct TransactionError {
+    pub index: usize,
+    pub error: InstructionError,
+    pub logs: Vec<String>,
+}
+
+impl TransactionError {
+    /// Custom program error code, e.g. an Anchor `ErrorCode`
+    pub fn custom_code(&self) -> Option<u32> {
+        match self.error {
+            InstructionError::Custom(code) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instruction {} failed: {}", self.index, self.error)?;
+        for log in &self.logs {
+            write!(f, "\n  {log}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+impl LocalSvm {
+    /// Local SVM running the given protection manager ELF
+    pub fn new(program_elf: &[u8]) -> Self {
+        let mut mollusk = Mollusk::default();
+        mollusk.add_program_with_elf_and_loader(
+            &zephyra_protection_manager::ID,
+            program_elf,
+            &LOADER_V3,
+        );
+        mollusk.logger = Some(LogCollector::new_ref());
+        mollusk.sysvars.clock.unix_timestamp = GENESIS_UNIX_TIMESTAMP;
+
+        let mut accounts = HashMap::new();
+        let (system_program, system_account) =
+            mollusk_svm::program::keyed_account_for_system_program();
+        accounts.insert(system_program, system_account);
+        // Anchor passes the program id in place of omitted optional accounts
+        accounts.insert(
+            zephyra_protection_manager::ID,
+            mollusk_svm::program::create_program_account_loader_v3(&zephyra_protection_manager::ID),
+        );
+
+        Self { mollusk, accounts }
+    }
+
+    /// Record `authority` as the protection manager's upgrade authority, which
+    /// signs `initialize_config`
+    pub fn set_upgrade_authority(&mut self, authority: Option<Pubkey>) {
+        let mut program_data = create_program_data_account_loader_v3(&[]);
+        // Bincode `UpgradeableLoaderState::ProgramData`: variant, slot, then the authority
+        program_data.data.truncate(12);
+        match authority {
+            Some(authority) => {
+                program_data.data.push(1);
+                program_data.data.extend_from_slice(authority.as_ref());
+            }
+            None => program_data.data.push(0),
+        }
+        self.accounts
+            .insert(crate::instructions::program_data_address(), program_data);
+    }
+
+    pub fn get_account(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    pub fn set_account(&mut self, pubkey: Pubkey, account: Account) {
+        self.accounts.insert(pubkey, account);
+    }
+
+    pub fn remove_account(&mut self, pubkey: &Pubkey) -> Option<Account> {
+        self.accounts.remove(pubkey)
+    }
+
+    /// Deserialize an Anchor account, `None` if missing or of another type
+    pub fn get_anchor_account<T: AccountDeserialize>(&self, pubkey: &Pubkey) -> Option<T> {
+        let account = self.accounts.get(pubkey)?;
+        T::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// Credit lamports to a system account, creating it if needed
+    pub fn airdrop(&mut self, pubkey: &Pubkey, lamports: u64) {
+        let account = self
+            .accounts
+            .entry(*pubkey)
+            .or_insert_with(|| Account::new(0, 0, &anchor_lang::system_program::ID));
+        account.lamports = account.lamports.saturating_add(lamports);
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.mollusk.sysvars.clock.slot
+    }
+
+    pub fn unix_timestamp(&self) -> i64 {
+        self.mollusk.sysvars.clock.unix_timestamp
+    }
+
+    /// Advance the clock to `slot`, moving the unix timestamp forward by `seconds`
+    pub fn warp(&mut self, slot: u64, seconds: i64) {
+        let unix_timestamp = self.unix_timestamp().saturating_add(seconds);
+        self.mollusk.sysvars.warp_to_slot(slot);
+        self.mollusk.sysvars.clock.unix_timestamp = unix_timestamp;
+    }
+
+    /// Execute instructions as one transaction. Account changes are only
+    /// stored if every instruction succeeds.
+    pub fn process_transaction(
+        &mut self,
+        instructions: &[Instruction],
+    ) -> Result<TransactionResult, TransactionError> {
+        let instructions_sysvar = anchor_lang::solana_program::sysvar::instructions::ID;
+        let mut working = self.load_accounts(instructions);
+        let mut sysvar_data = construct_instructions_data(
+            &instructions
+                .iter()
+                .map(borrowed_instruction)
+                .collect::<Vec<_>>(),
+        );
+
+        let mut result = TransactionResult {
+            compute_units_consumed: 0,
+            return_data: Vec::new(),
+            logs: Vec::new(),
+        };
+        for (index, instruction) in instructions.iter().enumerate() {
+            // The instructions sysvar ends with the index of the executing instruction
+            let len = sysvar_data.len();
+            sysvar_data[len - 2..].copy_from_slice(&(index as u16).to_le_bytes());
+            if let Some((_, account)) = working
+                .iter_mut()
+                .find(|(key, _)| *key == instructions_sysvar)
+            {
+                account.data.clone_from(&sysvar_data);
+            }
+
+            let outcome = self.mollusk.process_instruction(instruction, &working);
+            let logs = self.take_logs();
+            if let Err(error) = outcome.raw_result {
+                return Err(TransactionError { index, error, logs });
+            }
+
+            result.compute_units_consumed += outcome.compute_units_consumed;
+            result.return_data = outcome.return_data;
+            result.logs.extend(logs);
+            working = outcome.resulting_accounts;
+        }
+
+        for (pubkey, account) in working {
+            if pubkey == instructions_sysvar {
+                continue;
+            }
+            // Closed accounts are garbage collected at the end of the transaction
+            if account.lamports == 0 {
+                self.accounts.remove(&pubkey);
+            } else {
+                self.accounts.insert(pubkey, account);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Every account referenced by the instructions, from the store or empty
+    fn load_accounts(&self, instructions: &[Instruction]) -> Vec<(Pubkey, Account)> {
+        let mut accounts: Vec<(Pubkey, Account)> = Vec::new();
+        let keys = instructions.iter().flat_map(|instruction| {
+            std::iter::once(instruction.program_id)
+                .chain(instruction.accounts.iter().map(|meta| meta.pubkey))
+        });
+        for key in keys {
+            if accounts.iter().any(|(pubkey, _)| *pubkey == key) {
+                continue;
+            }
+            let account = if key == anchor_lang::solana_program::sysvar::instructions::ID {
+                Account::new(0, 0, &anchor_lang::solana_program::sysvar::ID)
+            } else {
+                self.accounts.get(&key).cloned().unwrap_or_default()
+            };
+            accounts.push((key, account));
+        }
+        accounts
+    }
+
+    fn take_logs(&self) -> Vec<String> {
+        self.mollusk
+            .logger
+            .as_ref()
+            .map(|logger| {
+                let logs = logger.borrow().get_recorded_content().to_vec();
+                *logger.borrow_mut() = LogCollector::default();
+                logs
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn borrowed_instruction(instruction: &Instruction) -> BorrowedInstruction<'_> {
+    BorrowedInstruction {
+        program_id: &instruction.program_id,
+        accounts: instruction
+            .accounts
+            .iter()
+            .map(|meta| solana_instruction::BorrowedAccountMeta {
+                pubkey: &meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect(),
+        data: &instruction.data,
+    }
+}
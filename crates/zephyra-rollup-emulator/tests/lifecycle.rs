@@ -0,0 +1,668 @@
+//! Full rollup session lifecycle against the emulated ephemeral rollup
+//!
+//! Requires the protection manager SBF build, so the tests are ignored by
+//! default: build it, then run `cargo test -p zephyra-rollup-emulator -- --include-ignored`.
+//! A missing build fails them.
+
+use anchor_lang::prelude::{pubkey, Pubkey};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::{AccountSerialize, AnchorSerialize};
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_sha256_hasher::hashv;
+use zephyra_protection_manager::magicblock_integration::{
+    RollbackReason, RollupOp, RollupResult, RollupSession, RollupStatus, RAYDIUM_AMM_PROGRAM_ID,
+};
+use zephyra_protection_manager::{
+    Batch, BatchStatus, ErrorCode, Transaction, TransactionStatus, BATCH_COORDINATOR_PROGRAM_ID,
+};
+use zephyra_rollup_emulator::instructions;
+use zephyra_rollup_emulator::{load_program_elf, EmulatorError, EphemeralRollup, LocalSvm};
+
+const LAMPORTS: u64 = 10_000_000_000;
+const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const AMM_INFO_LEN: usize = 752;
+const AMM_COIN_VAULT: usize = 336;
+const AMM_PC_VAULT: usize = 368;
+
+struct Harness {
+    base: LocalSvm,
+    rollup: EphemeralRollup,
+    admin: Pubkey,
+    user: Pubkey,
+}
+
+fn setup() -> Harness {
+    let elf = load_program_elf()
+        .expect("protection manager SBF build not found, run `anchor build` first");
+
+    let mut base = LocalSvm::new(&elf);
+    let mut rollup = EphemeralRollup::new(&elf, Keypair::new());
+    let admin = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    base.set_upgrade_authority(Some(admin));
+    base.airdrop(&admin, LAMPORTS);
+    base.airdrop(&user, LAMPORTS);
+    rollup.svm_mut().airdrop(&user, LAMPORTS);
+    // The validator bonds its commits
+    let validator = rollup.validator();
+    rollup.svm_mut().airdrop(&validator, LAMPORTS);
+
+    base.process_transaction(&[
+        instructions::initialize_config(&admin, admin, 0, 3_600),
+        instructions::register_rollup_validator(&admin, rollup.validator()),
+        instructions::initialize_protection(&user, user),
+    ])
+    .expect("config, validator and protection registered");
+    rollup
+        .clone_account(&base, &instructions::config_address())
+        .unwrap();
+    rollup
+        .clone_account(
+            &base,
+            &instructions::rollup_validator_address(&rollup.validator()),
+        )
+        .unwrap();
+
+    Harness {
+        base,
+        rollup,
+        admin,
+        user,
+    }
+}
+
+/// Submit a transaction for the user on the base layer, returning its ID
+fn submit_transaction(
+    harness: &mut Harness,
+    input_token: Pubkey,
+    output_token: Pubkey,
+) -> [u8; 32] {
+    let submitted = harness
+        .base
+        .process_transaction(&[instructions::submit_transaction(
+            &harness.user,
+            input_token,
+            output_token,
+            1_000_000,
+            1,
+            harness.base.unix_timestamp(),
+        )])
+        .expect("transaction submitted");
+    submitted.return_data.try_into().unwrap()
+}
+
+/// Submit a transaction for the user on the base layer and open its session
+fn init_session(harness: &mut Harness) -> [u8; 32] {
+    let transaction_id = submit_transaction(harness, Pubkey::new_unique(), Pubkey::new_unique());
+
+    harness
+        .base
+        .process_transaction(&[instructions::init_rollup_session(
+            &harness.user,
+            transaction_id,
+            None,
+            None,
+        )])
+        .expect("session initialized");
+    transaction_id
+}
+
+/// Init a session on the base layer and delegate it to the rollup, along
+/// with its transaction
+fn delegated_session(harness: &mut Harness) -> (Pubkey, Pubkey, RollupSession) {
+    let transaction_id = init_session(harness);
+    let transaction_account = instructions::transaction_address(&harness.user, &transaction_id);
+    let session_account = instructions::rollup_session_address(&transaction_account);
+    for account in [session_account, transaction_account] {
+        harness
+            .rollup
+            .delegate(&mut harness.base, &account)
+            .unwrap();
+    }
+
+    let session = harness
+        .rollup
+        .svm()
+        .get_anchor_account(&session_account)
+        .unwrap();
+    (session_account, transaction_account, session)
+}
+
+/// Overwrite a delegated session inside the rollup, as a dishonest rollup could
+fn forge_session(harness: &mut Harness, session_account: &Pubkey, session: &RollupSession) {
+    let mut account = harness
+        .rollup
+        .svm()
+        .get_account(session_account)
+        .unwrap()
+        .clone();
+    session.try_serialize(&mut &mut account.data[..]).unwrap();
+    harness
+        .rollup
+        .svm_mut()
+        .set_account(*session_account, account);
+}
+
+/// Leaf committing to op `index` and its result in a session's op root
+fn op_leaf(index: u32, op: &RollupOp, result: &RollupResult) -> [u8; 32] {
+    hashv(&[
+        &[0][..],
+        &index.to_le_bytes(),
+        &op.try_to_vec().unwrap(),
+        &result.try_to_vec().unwrap(),
+    ])
+    .to_bytes()
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&[1][..], left, right]).to_bytes()
+}
+
+/// Sibling path of the only leaf of an op accumulator: the empty subtree roots
+fn single_leaf_proof() -> Vec<[u8; 32]> {
+    let mut proof = vec![[0u8; 32]];
+    while proof.len() < 16 {
+        let zero = proof[proof.len() - 1];
+        proof.push(hash_node(&zero, &zero));
+    }
+    proof
+}
+
+/// Store a batch coordinator batch created by `authority` on the base layer
+fn store_batch(
+    harness: &mut Harness,
+    authority: &Pubkey,
+    transactions: &[[u8; 32]],
+) -> (Pubkey, [u8; 32]) {
+    let created_at = harness.base.unix_timestamp();
+    let batch_account = Pubkey::find_program_address(
+        &[b"batch", authority.as_ref(), &created_at.to_le_bytes()],
+        &BATCH_COORDINATOR_PROGRAM_ID,
+    )
+    .0;
+    let batch = Batch {
+        id: [7u8; 32],
+        transactions: transactions.to_vec(),
+        transaction_count: transactions.len() as u32,
+        total_value: 0,
+        status: BatchStatus::Pending,
+        created_at,
+        executed_at: None,
+        completed_at: None,
+        execution_time_ms: None,
+        batch_hash: [0u8; 32],
+    };
+    let mut data = Vec::new();
+    batch.try_serialize(&mut data).unwrap();
+    let mut account = Account::new(LAMPORTS, 0, &BATCH_COORDINATOR_PROGRAM_ID);
+    account.data = data;
+    harness.base.set_account(batch_account, account);
+    (batch_account, batch.id)
+}
+
+/// Store an SPL token account holding `amount` of `mint` on both layers
+fn store_vault(harness: &mut Harness, mint: Pubkey, amount: u64) -> Pubkey {
+    let vault = Pubkey::new_unique();
+    let mut data = Vec::with_capacity(165);
+    data.extend_from_slice(mint.as_ref());
+    data.extend_from_slice(Pubkey::new_unique().as_ref()); // owner
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&[0u8; 36]); // no delegate
+    data.push(1); // initialized
+    data.extend_from_slice(&[0u8; 12]); // not native
+    data.extend_from_slice(&[0u8; 8]); // delegated amount
+    data.extend_from_slice(&[0u8; 36]); // no close authority
+    let mut account = Account::new(LAMPORTS, 0, &TOKEN_PROGRAM_ID);
+    account.data = data;
+    harness.base.set_account(vault, account);
+    harness.rollup.clone_account(&harness.base, &vault).unwrap();
+    vault
+}
+
+/// Store a Raydium AMM v4 pool over two vaults on both layers
+fn store_pool(harness: &mut Harness, vaults: [Pubkey; 2]) -> Pubkey {
+    let pool = Pubkey::new_unique();
+    let mut data = vec![0u8; AMM_INFO_LEN];
+    data[AMM_COIN_VAULT..AMM_COIN_VAULT + 32].copy_from_slice(vaults[0].as_ref());
+    data[AMM_PC_VAULT..AMM_PC_VAULT + 32].copy_from_slice(vaults[1].as_ref());
+    let mut account = Account::new(LAMPORTS, 0, &RAYDIUM_AMM_PROGRAM_ID);
+    account.data = data;
+    harness.base.set_account(pool, account);
+    harness.rollup.clone_account(&harness.base, &pool).unwrap();
+    pool
+}
+
+/// `execute_in_rollup` of a batch member simulation against a pool's vaults
+fn simulate_member(
+    harness: &Harness,
+    session_account: Pubkey,
+    session: &RollupSession,
+    member_index: u8,
+    pool_accounts: [Pubkey; 3],
+    reserves: [u64; 2],
+) -> Instruction {
+    let mut instruction = instructions::execute_in_rollup(
+        &harness.user,
+        session_account,
+        session.id,
+        &RollupOp::SimulateMember {
+            member_index,
+            reserve_in: reserves[0],
+            reserve_out: reserves[1],
+            fee_bps: 30,
+        },
+    );
+    instruction.accounts.extend(
+        pool_accounts
+            .iter()
+            .map(|account| AccountMeta::new_readonly(*account, false)),
+    );
+    instruction
+}
+
+fn simulate_swap() -> RollupOp {
+    RollupOp::SimulateSwap {
+        input_amount: 1_000_000,
+        min_output: 1,
+        reserve_in: 1_000_000_000,
+        reserve_out: 2_000_000_000,
+        fee_bps: 30,
+    }
+}
+
+#[test]
+#[ignore = "needs the protection manager SBF build"]
+fn init_execute_commit() {
+    let mut harness = setup();
+    let (session_account, _, session) = delegated_session(&mut harness);
+
+    harness
+        .rollup
+        .process_transaction(&[instructions::execute_in_rollup(
+            &harness.user,
+            session_account,
+            session.id,
+            &simulate_swap(),
+        )])
+        .expect("op executed in the rollup");
+
+    let proof = harness
+        .rollup
+        .commit(&harness.user, &session_account)
+        .expect("commit accepted");
+    assert_eq!(proof.instructions_executed, 1);
+    assert_eq!(proof.validator, harness.rollup.validator());
+
+    harness
+        .rollup
+        .undelegate(&mut harness.base, &session_account)
+        .unwrap();
+    let committed: RollupSession = harness.base.get_anchor_account(&session_account).unwrap();
+    assert!(committed.status == RollupStatus::Committed);
+    assert_eq!(committed.state_hash, proof.state_hash);
+    assert_eq!(committed.op_root, proof.op_root);
+
+    // Usage is metered from the compute units the op actually consumed
+    assert_eq!(committed.usage.ops, 1);
+    assert!(committed.usage.compute_units > 0);
+    assert!(committed.usage.compute_units < 200_000);
+}
+
+#[test]
+#[ignore = "needs the protection manager SBF build"]
+fn init_execute_rollback() {
+    let mut harness = setup();
+    let (session_account, transaction_account, session) = delegated_session(&mut harness);
+
+    harness
+        .rollup
+        .process_transaction(&[
+            instructions::execute_in_rollup(
+                &harness.user,
+                session_account,
+                session.id,
+                &simulate_swap(),
+            ),
+            instructions::rollback_rollup(
+                &harness.user,
+                session_account,
+                Some(transaction_account),
+                session.id,
+                RollbackReason::RouteUnavailable,
+                Some("pool drained".to_string()),
+            ),
+        ])
+        .expect("rolled back in the rollup");
+
+    for account in [session_account, transaction_account] {
+        harness
+            .rollup
+            .undelegate(&mut harness.base, &account)
+            .unwrap();
+    }
+    let rolled_back: RollupSession = harness.base.get_anchor_account(&session_account).unwrap();
+    assert!(rolled_back.status == RollupStatus::RolledBack);
+    assert_eq!(rolled_back.instructions_executed, 1);
+    let restored: Transaction = harness
+        .base
+        .get_anchor_account(&transaction_account)
+        .unwrap();
+    assert!(restored.status == TransactionStatus::Pending);
+
+    // A rolled back session can't be committed afterwards
+    harness
+        .rollup
+        .delegate(&mut harness.base, &session_account)
+        .unwrap();
+    match harness.rollup.commit(&harness.user, &session_account) {
+        Err(EmulatorError::Transaction(error)) => {
+            assert_eq!(
+                error.custom_code(),
+                Some(ErrorCode::SessionNotActive.into())
+            );
+        }
+        Err(other) => panic!("expected SessionNotActive, got {other}"),
+        Ok(_) => panic!("rolled back session committed"),
+    }
+}
+
+#[test]
+#[ignore = "needs the protection manager SBF build"]
+fn delegated_session_is_locked_on_base_layer() {
+    let mut harness = setup();
+    let (session_account, _, session) = delegated_session(&mut harness);
+
+    let error = harness
+        .base
+        .process_transaction(&[instructions::execute_in_rollup(
+            &harness.user,
+            session_account,
+            session.id,
+            &simulate_swap(),
+        )])
+        .unwrap_err();
+    assert_eq!(
+        error.custom_code(),
+        Some(anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram.into())
+    );
+}
+
+#[test]
+#[ignore = "needs the protection manager SBF build"]
+fn commit_requires_registered_validator() {
+    let mut harness = setup();
+    let transaction_id = init_session(&mut harness);
+    let transaction_account = instructions::transaction_address(&harness.user, &transaction_id);
+    let session_account = instructions::rollup_session_address(&transaction_account);
+
+    // A rollup whose validator was never registered on the base layer
+    let mut rogue = EphemeralRollup::new(&load_program_elf().unwrap(), Keypair::new());
+    rogue.svm_mut().airdrop(&harness.user, LAMPORTS);
+    rogue
+        .clone_account(&harness.base, &instructions::config_address())
+        .unwrap();
+    rogue.delegate(&mut harness.base, &session_account).unwrap();
+
+    match rogue.commit(&harness.user, &session_account) {
+        Err(EmulatorError::Transaction(error)) => assert_eq!(
+            error.custom_code(),
+            Some(anchor_lang::error::ErrorCode::AccountNotInitialized.into())
+        ),
+        Err(other) => panic!("expected AccountNotInitialized, got {other}"),
+        Ok(_) => panic!("commit by an unregistered validator accepted"),
+    }
+}
+
+#[test]
+#[ignore = "needs the protection manager SBF build"]
+fn challenge_disproves_tampered_commit() {
+    let mut harness = setup();
+    let bond = 1_000_000;
+    harness
+        .base
+        .process_transaction(&[instructions::update_rollup_config(
+            &harness.admin,
+            100,
+            bond,
+        )])
+        .expect("commits held open for challenges");
+    harness
+        .rollup
+        .clone_account(&harness.base, &instructions::config_address())
+        .unwrap();
+    let (session_account, transaction_account, session) = delegated_session(&mut harness);
+
+    // The rollup commits a result the op does not produce
+    let op = RollupOp::UpdateRisk {
+        risk_score: 30,
+        mev_detected: false,
+    };
+    let forged = RollupResult::new(true, &99u64.to_le_bytes());
+    let proof = single_leaf_proof();
+    let leaf = op_leaf(0, &op, &forged);
+    let mut tampered = session.clone();
+    tampered.instructions_executed = 1;
+    tampered.op_frontier[0] = leaf;
+    tampered.op_root = proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_node(&node, sibling));
+    forge_session(&mut harness, &session_account, &tampered);
+
+    harness
+        .rollup
+        .commit(&harness.user, &session_account)
+        .expect("tampered session committed");
+    for account in [session_account, transaction_account] {
+        harness
+            .rollup
+            .undelegate(&mut harness.base, &account)
+            .unwrap();
+    }
+    let pending: RollupSession = harness.base.get_anchor_account(&session_account).unwrap();
+    assert!(pending.status == RollupStatus::PendingFinalization);
+    assert_eq!(pending.bond, bond);
+
+    let challenger = Pubkey::new_unique();
+    harness.base.airdrop(&challenger, LAMPORTS);
+    harness
+        .base
+        .process_transaction(&[instructions::challenge_rollup(
+            &challenger,
+            &harness.user,
+            session.transaction_id,
+            0,
+            op,
+            forged,
+            proof,
+        )])
+        .expect("challenge upheld");
+
+    let challenged: RollupSession = harness.base.get_anchor_account(&session_account).unwrap();
+    assert!(challenged.status == RollupStatus::RolledBack);
+    assert!(challenged.rollback_reason == Some(RollbackReason::FraudProven));
+    assert_eq!(challenged.bond, 0);
+    assert_eq!(
+        harness.base.get_account(&challenger).unwrap().lamports,
+        LAMPORTS + bond
+    );
+    let restored: Transaction = harness
+        .base
+        .get_anchor_account(&transaction_account)
+        .unwrap();
+    assert!(restored.status == TransactionStatus::Pending);
+}
+
+#[test]
+#[ignore = "needs the protection manager SBF build"]
+fn batch_session_settles_members_in_commit() {
+    let mut harness = setup();
+    let pools = [
+        (Pubkey::new_unique(), Pubkey::new_unique()),
+        (Pubkey::new_unique(), Pubkey::new_unique()),
+    ];
+    let transaction_ids: Vec<[u8; 32]> = pools
+        .iter()
+        .map(|&(input_token, output_token)| {
+            submit_transaction(&mut harness, input_token, output_token)
+        })
+        .collect();
+    let transaction_accounts: Vec<Pubkey> = transaction_ids
+        .iter()
+        .map(|transaction_id| instructions::transaction_address(&harness.user, transaction_id))
+        .collect();
+    let stranger = Pubkey::new_unique();
+    harness.base.airdrop(&stranger, LAMPORTS);
+
+    // A batch of the user's transactions created by someone else needs their consent
+    let (stranger_batch, stranger_batch_id) =
+        store_batch(&mut harness, &stranger, &transaction_ids);
+    let error = harness
+        .base
+        .process_transaction(&[instructions::init_batch_rollup_session(
+            &stranger,
+            stranger_batch,
+            stranger_batch_id,
+            &transaction_accounts,
+            None,
+            None,
+        )])
+        .unwrap_err();
+    assert_eq!(error.custom_code(), Some(ErrorCode::Unauthorized.into()));
+
+    let user = harness.user;
+    let (batch_account, batch_id) = store_batch(&mut harness, &user, &transaction_ids);
+    let session_account = instructions::batch_rollup_session_address(&batch_id);
+
+    // Only the batch's authority opens its session
+    let error = harness
+        .base
+        .process_transaction(&[instructions::init_batch_rollup_session(
+            &stranger,
+            batch_account,
+            batch_id,
+            &transaction_accounts,
+            None,
+            None,
+        )])
+        .unwrap_err();
+    assert_eq!(error.custom_code(), Some(ErrorCode::Unauthorized.into()));
+
+    harness
+        .base
+        .process_transaction(&[instructions::init_batch_rollup_session(
+            &harness.user,
+            batch_account,
+            batch_id,
+            &transaction_accounts,
+            None,
+            None,
+        )])
+        .expect("batch session initialized");
+    for account in transaction_accounts.iter().chain([&session_account]) {
+        harness.rollup.delegate(&mut harness.base, account).unwrap();
+    }
+    let session: RollupSession = harness
+        .rollup
+        .svm()
+        .get_anchor_account(&session_account)
+        .unwrap();
+    assert_eq!(session.members.len(), 2);
+    assert_eq!(session.members[0].input_amount, 1_000_000);
+
+    let reserves = [1_000_000_000, 2_000_000_000];
+    let pool_accounts: Vec<[Pubkey; 3]> = pools
+        .iter()
+        .map(|&(input_token, output_token)| {
+            let vaults = [
+                store_vault(&mut harness, input_token, reserves[0]),
+                store_vault(&mut harness, output_token, reserves[1]),
+            ];
+            [store_pool(&mut harness, vaults), vaults[0], vaults[1]]
+        })
+        .collect();
+
+    // Reserves must match the vaults
+    let error = harness
+        .rollup
+        .process_transaction(&[simulate_member(
+            &harness,
+            session_account,
+            &session,
+            0,
+            pool_accounts[0],
+            [reserves[0], reserves[1] * 2],
+        )])
+        .unwrap_err();
+    match error {
+        EmulatorError::Transaction(error) => {
+            assert_eq!(error.custom_code(), Some(ErrorCode::ReserveMismatch.into()))
+        }
+        other => panic!("expected ReserveMismatch, got {other}"),
+    }
+
+    // And the vaults must belong to the pool
+    let [_, vault_in, vault_out] = pool_accounts[0];
+    let other_pool = store_pool(&mut harness, [vault_in, Pubkey::new_unique()]);
+    let error = harness
+        .rollup
+        .process_transaction(&[simulate_member(
+            &harness,
+            session_account,
+            &session,
+            0,
+            [other_pool, vault_in, vault_out],
+            reserves,
+        )])
+        .unwrap_err();
+    match error {
+        EmulatorError::Transaction(error) => {
+            assert_eq!(error.custom_code(), Some(ErrorCode::ReserveMismatch.into()))
+        }
+        other => panic!("expected ReserveMismatch, got {other}"),
+    }
+
+    harness
+        .rollup
+        .process_transaction(&[
+            simulate_member(
+                &harness,
+                session_account,
+                &session,
+                0,
+                pool_accounts[0],
+                reserves,
+            ),
+            simulate_member(
+                &harness,
+                session_account,
+                &session,
+                1,
+                pool_accounts[1],
+                reserves,
+            ),
+        ])
+        .expect("members simulated in the rollup");
+    harness
+        .rollup
+        .commit(&harness.user, &session_account)
+        .expect("batch committed");
+
+    for account in transaction_accounts.iter().chain([&session_account]) {
+        harness
+            .rollup
+            .undelegate(&mut harness.base, account)
+            .unwrap();
+    }
+    let committed: RollupSession = harness.base.get_anchor_account(&session_account).unwrap();
+    assert!(committed.status == RollupStatus::Committed);
+    for (member, transaction_account) in committed.members.iter().zip(&transaction_accounts) {
+        let result = member.result.as_ref().unwrap();
+        assert!(result.success);
+        let transaction: Transaction = harness
+            .base
+            .get_anchor_account(transaction_account)
+            .unwrap();
+        assert_eq!(transaction.output_amount, result.output_amount);
+    }
+}
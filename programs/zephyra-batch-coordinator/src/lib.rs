@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use zephyra_protection_manager::Transaction;
 
 declare_id!("Cxajg3f3QNHXLqD1w5yYCMN4V7BsQKXE2LAnEPH73F8S");
 
@@ -327,7 +328,8 @@ pub struct AddToBatch<'info> {
     pub batch_account: Account<'info, Batch>,
     #[account(
         seeds = [b"transaction", transaction_account.owner.as_ref(), &transaction_id],
-        bump
+        bump,
+        seeds::program = zephyra_protection_manager::ID
     )]
     pub transaction_account: Account<'info, Transaction>,
     /// CHECK: Authority that can manage batches
@@ -463,33 +465,4 @@ pub enum ErrorCode {
     Unauthorized,
 }
 
-// Transaction struct (simplified version for cross-program compatibility)
-#[account]
-#[derive(InitSpace)]
-pub struct Transaction {
-    pub id: [u8; 32],
-    pub owner: Pubkey,
-    pub input_token: Pubkey,
-    pub output_token: Pubkey,
-    pub input_amount: u64,
-    pub output_amount: u64,
-    pub risk_score: u8,
-    pub selected_route: u8,
-    pub status: TransactionStatus,
-    pub proof_hash: [u8; 32],
-    pub batch_id: Option<[u8; 32]>,
-    pub created_at: i64,
-    pub completed_at: Option<i64>,
-}
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
-pub enum TransactionStatus {
-    Pending,
-    Simulating,
-    Analyzing,
-    Executing,
-    Completed,
-    Failed,
-}
-
 
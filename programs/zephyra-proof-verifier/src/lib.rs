@@ -2,6 +2,11 @@ use anchor_lang::prelude::*;
 
 declare_id!("CYeCspx1DjYxYQZgMWz1sRPzKg9UxVWhMx5rRM4HihzQ");
 
+/// Most hops a route can chain, as in the route executor
+pub const MAX_HOPS: usize = 4;
+/// Most legs a split route can divide its input across, as in the route executor
+pub const MAX_SPLIT_LEGS: usize = MAX_HOPS;
+
 /// Zephyra Proof Verifier Program
 /// Generate and verify cryptographic proofs of route selection
 #[program]
@@ -13,9 +18,13 @@ pub mod proof_verifier {
         ctx: Context<GenerateProof>,
         transaction_id: [u8; 32],
         routes_considered: Vec<RouteOption>,
-        selected_route: DEX,
+        selected_route: u8,
         reasoning: String,
     ) -> Result<[u8; 32]> {
+        require!(
+            (selected_route as usize) < routes_considered.len(),
+            ErrorCode::InvalidSelectedRoute
+        );
         let proof_account = &mut ctx.accounts.proof_account;
         let clock = Clock::get()?;
 
@@ -23,7 +32,7 @@ pub mod proof_verifier {
         let proof_hash = generate_proof_hash(
             &transaction_id,
             &routes_considered,
-            &selected_route,
+            selected_route,
             &reasoning,
             clock.unix_timestamp,
         );
@@ -89,7 +98,7 @@ pub mod proof_verifier {
             proof_hash,
             transaction_id: proof_account.transaction_id,
             routes_considered: proof_account.routes_considered.clone(),
-            selected_route: proof_account.selected_route.clone(),
+            selected_route: proof_account.selected_route,
             selection_reasoning: proof_account.selection_reasoning.clone(),
             mev_detection_log: proof_account.mev_detection_log.clone(),
             simulation_time: proof_account.simulation_time,
@@ -194,7 +203,7 @@ pub mod proof_verifier {
 fn generate_proof_hash(
     transaction_id: &[u8; 32],
     routes_considered: &[RouteOption],
-    selected_route: &DEX,
+    selected_route: u8,
     reasoning: &str,
     timestamp: i64,
 ) -> [u8; 32] {
@@ -207,7 +216,18 @@ fn generate_proof_hash(
     
     // XOR routes considered
     for route in routes_considered {
-        hash[0] ^= route.dex.to_bytes()[0];
+        for hop in &route.hops {
+            for (i, byte) in hop.venue.as_ref().iter().enumerate() {
+                hash[(i + 1) % 32] ^= byte;
+            }
+            for (i, byte) in hop.pool.as_ref().iter().enumerate() {
+                hash[i] ^= byte;
+            }
+        }
+        for (i, weight) in route.split_weights_bps.iter().enumerate() {
+            hash[(i * 2) % 32] ^= weight.to_le_bytes()[0];
+            hash[(i * 2 + 1) % 32] ^= weight.to_le_bytes()[1];
+        }
         let output_bytes = route.estimated_output.to_le_bytes();
         for (i, byte) in output_bytes.iter().enumerate() {
             hash[(i + 8) % 32] ^= byte;
@@ -216,7 +236,7 @@ fn generate_proof_hash(
     }
     
     // XOR selected route
-    hash[17] ^= selected_route.to_bytes()[0];
+    hash[17] ^= selected_route;
     
     // XOR reasoning bytes (limited to avoid overflow)
     for (i, byte) in reasoning.as_bytes().iter().take(8).enumerate() {
@@ -232,23 +252,8 @@ fn generate_proof_hash(
     hash
 }
 
-/// Convert DEX enum to bytes for hashing
-trait ToBytes {
-    fn to_bytes(&self) -> [u8; 1];
-}
-
-impl ToBytes for DEX {
-    fn to_bytes(&self) -> [u8; 1] {
-        match self {
-            DEX::Jupiter => [0],
-            DEX::Raydium => [1],
-            DEX::Orca => [2],
-        }
-    }
-}
-
 #[derive(Accounts)]
-#[instruction(transaction_id: [u8; 32], routes_considered: Vec<RouteOption>, selected_route: DEX, reasoning: String)]
+#[instruction(transaction_id: [u8; 32], routes_considered: Vec<RouteOption>, selected_route: u8, reasoning: String)]
 pub struct GenerateProof<'info> {
     #[account(
         init,
@@ -323,7 +328,7 @@ pub struct ProofOfRoute {
     pub transaction_id: [u8; 32],
     #[max_len(10)]
     pub routes_considered: Vec<RouteOption>,
-    pub selected_route: DEX,
+    pub selected_route: u8, // index into `routes_considered`
     #[max_len(500)]
     pub selection_reasoning: String,
     #[max_len(20)]
@@ -353,7 +358,12 @@ pub enum MEVAttackType {
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct RouteOption {
-    pub dex: DEX,
+    #[max_len(MAX_HOPS)]
+    pub hops: Vec<RouteHop>,
+    /// Weights splitting the input across `hops` as parallel legs, empty when
+    /// the hops are chained
+    #[max_len(MAX_SPLIT_LEGS)]
+    pub split_weights_bps: Vec<u16>,
     pub estimated_output: u64,
     pub price_impact_bps: u16,
     pub mev_risk_score: u8,
@@ -361,10 +371,11 @@ pub struct RouteOption {
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
-pub enum DEX {
-    Jupiter,
-    Raydium,
-    Orca,
+pub struct RouteHop {
+    pub venue: Pubkey, // venue program, keying its registry entry in the route executor
+    pub pool: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
@@ -373,7 +384,7 @@ pub struct ProofData {
     pub transaction_id: [u8; 32],
     #[max_len(10)]
     pub routes_considered: Vec<RouteOption>,
-    pub selected_route: DEX,
+    pub selected_route: u8, // index into `routes_considered`
     #[max_len(500)]
     pub selection_reasoning: String,
     #[max_len(20)]
@@ -433,6 +444,8 @@ pub enum ErrorCode {
     ProofNotFound,
     #[msg("Invalid timing values")]
     InvalidTiming,
+    #[msg("Selected route is not among the routes considered")]
+    InvalidSelectedRoute,
 }
 
 
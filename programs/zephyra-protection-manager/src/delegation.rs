@@ -0,0 +1,639 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::system_instruction;
+
+use crate::{ErrorCode, RollupSession, RollupStatus, Transaction, TransactionStatus};
+
+/// Ephemeral Rollups Delegation Integration
+/// Implements the MagicBlock delegation program protocol for the
+/// `Transaction` and `RollupSession` PDAs:
+/// - delegate: base layer hands the PDA to the delegation program
+/// - commit / undelegate: ephemeral rollup schedules the state back through the magic program
+/// - process_undelegation: delegation program callback that restores the PDA on the base layer
+///
+/// Handlers are exposed as instructions by the `#[program]` module in `lib.rs`.
+pub mod instructions {
+    use super::*;
+
+    /// Delegate a transaction PDA to the ephemeral rollup
+    pub fn delegate_transaction(
+        ctx: Context<DelegateTransaction>,
+        transaction_id: [u8; 32],
+        commit_frequency_ms: u32,
+        validator: Option<Pubkey>,
+    ) -> Result<()> {
+        {
+            let data = ctx.accounts.transaction_account.try_borrow_data()?;
+            let transaction = Transaction::try_deserialize(&mut &data[..])?;
+            require!(
+                transaction.id == transaction_id,
+                ErrorCode::InvalidTransactionId
+            );
+            require!(
+                !matches!(
+                    transaction.status,
+                    TransactionStatus::Completed | TransactionStatus::Failed
+                ),
+                ErrorCode::InvalidStatusTransition
+            );
+        }
+
+        let owner = ctx.accounts.owner.key();
+        let pda_seeds: &[&[u8]] = &[b"transaction", owner.as_ref(), &transaction_id];
+
+        delegate_account(
+            DelegateAccounts {
+                payer: &ctx.accounts.payer.to_account_info(),
+                pda: &ctx.accounts.transaction_account.to_account_info(),
+                owner_program: &ctx.accounts.owner_program.to_account_info(),
+                buffer: &ctx.accounts.buffer.to_account_info(),
+                delegation_record: &ctx.accounts.delegation_record.to_account_info(),
+                delegation_metadata: &ctx.accounts.delegation_metadata.to_account_info(),
+                delegation_program: &ctx.accounts.delegation_program.to_account_info(),
+                system_program: &ctx.accounts.system_program.to_account_info(),
+            },
+            pda_seeds,
+            ctx.bumps.transaction_account,
+            ctx.bumps.buffer,
+            commit_frequency_ms,
+            validator,
+        )?;
+
+        emit!(AccountDelegated {
+            account: ctx.accounts.transaction_account.key(),
+            kind: DelegatedAccountKind::Transaction,
+            validator,
+            commit_frequency_ms,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Delegate a rollup session PDA to the ephemeral rollup
+    pub fn delegate_rollup_session(
+        ctx: Context<DelegateRollupSession>,
+        transaction_id: [u8; 32],
+        commit_frequency_ms: u32,
+        validator: Option<Pubkey>,
+    ) -> Result<()> {
+        let (seed_prefix, seed_key) = {
+            let data = ctx.accounts.session_account.try_borrow_data()?;
+            let session = RollupSession::try_deserialize(&mut &data[..])?;
+            require!(
+                session.transaction_id == transaction_id,
+                ErrorCode::InvalidTransactionId
+            );
+            require!(
+                session.status == RollupStatus::Active,
+                ErrorCode::SessionNotActive
+            );
+            require!(
+                session.can_abort(&ctx.accounts.payer.key()),
+                ErrorCode::UnauthorizedSessionAccess
+            );
+            (session.seed_prefix(), session.seed_key().to_vec())
+        };
+
+        // Single-transaction and batch sessions live under different seeds
+        let pda_seeds: &[&[u8]] = &[seed_prefix, &seed_key];
+        let (expected_session, session_bump) = Pubkey::find_program_address(pda_seeds, &crate::ID);
+        require_keys_eq!(
+            ctx.accounts.session_account.key(),
+            expected_session,
+            ErrorCode::InvalidSessionId
+        );
+
+        delegate_account(
+            DelegateAccounts {
+                payer: &ctx.accounts.payer.to_account_info(),
+                pda: &ctx.accounts.session_account.to_account_info(),
+                owner_program: &ctx.accounts.owner_program.to_account_info(),
+                buffer: &ctx.accounts.buffer.to_account_info(),
+                delegation_record: &ctx.accounts.delegation_record.to_account_info(),
+                delegation_metadata: &ctx.accounts.delegation_metadata.to_account_info(),
+                delegation_program: &ctx.accounts.delegation_program.to_account_info(),
+                system_program: &ctx.accounts.system_program.to_account_info(),
+            },
+            pda_seeds,
+            session_bump,
+            ctx.bumps.buffer,
+            commit_frequency_ms,
+            validator,
+        )?;
+
+        emit!(AccountDelegated {
+            account: ctx.accounts.session_account.key(),
+            kind: DelegatedAccountKind::RollupSession,
+            validator,
+            commit_frequency_ms,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Commit a delegated transaction back to the base layer and release it
+    pub fn undelegate_transaction(
+        ctx: Context<UndelegateTransaction>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.transaction_account.id == transaction_id,
+            ErrorCode::InvalidTransactionId
+        );
+
+        schedule_commit(
+            &ctx.accounts.payer.to_account_info(),
+            &[ctx.accounts.transaction_account.to_account_info()],
+            &ctx.accounts.magic_context.to_account_info(),
+            &ctx.accounts.magic_program.to_account_info(),
+            true,
+        )
+    }
+
+    /// Commit a delegated rollup session back to the base layer and release it
+    pub fn undelegate_rollup_session<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UndelegateRollupSession<'info>>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.session_account.transaction_id == transaction_id,
+            ErrorCode::InvalidSessionId
+        );
+        require!(
+            ctx.accounts
+                .session_account
+                .can_abort(&ctx.accounts.payer.key()),
+            ErrorCode::UnauthorizedSessionAccess
+        );
+
+        // A batch session's member transactions follow as remaining accounts
+        let mut accounts = vec![ctx.accounts.session_account.to_account_info()];
+        if let Some(transaction_account) = &ctx.accounts.transaction_account {
+            accounts.push(transaction_account.to_account_info());
+        }
+        accounts.extend(ctx.remaining_accounts.iter().cloned());
+
+        schedule_commit(
+            &ctx.accounts.payer.to_account_info(),
+            &accounts,
+            &ctx.accounts.magic_context.to_account_info(),
+            &ctx.accounts.magic_program.to_account_info(),
+            true,
+        )
+    }
+
+    /// Delegation program callback restoring an undelegated PDA on the base layer
+    pub fn process_undelegation(
+        ctx: Context<ProcessUndelegation>,
+        account_seeds: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let buffer = &ctx.accounts.buffer;
+
+        // Only the delegation program can sign for its undelegation buffer
+        require!(buffer.is_signer, ErrorCode::InvalidDelegationAccount);
+        require_keys_eq!(
+            *buffer.owner,
+            DELEGATION_PROGRAM_ID,
+            ErrorCode::InvalidDelegationAccount
+        );
+        let (expected_buffer, _) = Pubkey::find_program_address(
+            &[
+                UNDELEGATE_BUFFER_SEED,
+                ctx.accounts.base_account.key.as_ref(),
+            ],
+            &DELEGATION_PROGRAM_ID,
+        );
+        require_keys_eq!(
+            buffer.key(),
+            expected_buffer,
+            ErrorCode::InvalidDelegationAccount
+        );
+
+        let kind = match account_seeds.first().map(Vec::as_slice) {
+            Some(b"transaction") => DelegatedAccountKind::Transaction,
+            Some(b"rollup_session" | b"batch_rollup_session") => {
+                DelegatedAccountKind::RollupSession
+            }
+            _ => return err!(ErrorCode::InvalidDelegationAccount),
+        };
+
+        let seeds: Vec<&[u8]> = account_seeds.iter().map(Vec::as_slice).collect();
+        let (expected_account, bump) = Pubkey::find_program_address(&seeds, &crate::ID);
+        require_keys_eq!(
+            ctx.accounts.base_account.key(),
+            expected_account,
+            ErrorCode::InvalidDelegationAccount
+        );
+
+        let bump_seed = [bump];
+        let mut signer_seeds = seeds.clone();
+        signer_seeds.push(&bump_seed);
+
+        let base_account = ctx.accounts.base_account.to_account_info();
+        create_pda(
+            &base_account,
+            buffer.data_len(),
+            &[&signer_seeds],
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        base_account
+            .try_borrow_mut_data()?
+            .copy_from_slice(&buffer.try_borrow_data()?);
+
+        emit!(AccountUndelegated {
+            account: base_account.key(),
+            kind,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// Constants
+pub const DELEGATION_PROGRAM_ID: Pubkey = pubkey!("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
+pub const MAGIC_PROGRAM_ID: Pubkey = pubkey!("Magic11111111111111111111111111111111111111");
+pub const MAGIC_CONTEXT_ID: Pubkey = pubkey!("MagicContext1111111111111111111111111111111");
+
+pub const BUFFER_SEED: &[u8] = b"buffer";
+pub const DELEGATION_RECORD_SEED: &[u8] = b"delegation";
+pub const DELEGATION_METADATA_SEED: &[u8] = b"delegation-metadata";
+pub const UNDELEGATE_BUFFER_SEED: &[u8] = b"undelegate-buffer";
+
+const DELEGATE_DISCRIMINATOR: u64 = 0; // delegation program `Delegate` instruction
+const SCHEDULE_COMMIT: u32 = 1; // magic program `ScheduleCommit` (bincode enum tag)
+const SCHEDULE_COMMIT_AND_UNDELEGATE: u32 = 2; // magic program `ScheduleCommitAndUndelegate`
+
+struct DelegateAccounts<'a, 'info> {
+    payer: &'a AccountInfo<'info>,
+    pda: &'a AccountInfo<'info>,
+    owner_program: &'a AccountInfo<'info>,
+    buffer: &'a AccountInfo<'info>,
+    delegation_record: &'a AccountInfo<'info>,
+    delegation_metadata: &'a AccountInfo<'info>,
+    delegation_program: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+}
+
+/// Arguments of the delegation program `Delegate` instruction
+#[derive(AnchorSerialize, AnchorDeserialize)]
+struct DelegateAccountArgs {
+    commit_frequency_ms: u32,
+    seeds: Vec<Vec<u8>>,
+    validator: Option<Pubkey>,
+}
+
+/// Hand a PDA owned by this program to the delegation program
+fn delegate_account(
+    accounts: DelegateAccounts,
+    pda_seeds: &[&[u8]],
+    pda_bump: u8,
+    buffer_bump: u8,
+    commit_frequency_ms: u32,
+    validator: Option<Pubkey>,
+) -> Result<()> {
+    let pda_key = accounts.pda.key();
+    let pda_bump_seed = [pda_bump];
+    let mut pda_signer: Vec<&[u8]> = pda_seeds.to_vec();
+    pda_signer.push(&pda_bump_seed);
+    let buffer_bump_seed = [buffer_bump];
+    let buffer_signer: &[&[u8]] = &[BUFFER_SEED, pda_key.as_ref(), &buffer_bump_seed];
+
+    // Stage the current state in a buffer the delegation program copies from
+    let data_len = accounts.pda.data_len();
+    create_pda(
+        accounts.buffer,
+        data_len,
+        &[buffer_signer],
+        accounts.payer,
+        accounts.system_program,
+    )?;
+    accounts
+        .buffer
+        .try_borrow_mut_data()?
+        .copy_from_slice(&accounts.pda.try_borrow_data()?);
+
+    // Zero the PDA and hand ownership to the delegation program
+    accounts.pda.try_borrow_mut_data()?.fill(0);
+    accounts.pda.assign(accounts.system_program.key);
+    invoke_signed(
+        &system_instruction::assign(&pda_key, &DELEGATION_PROGRAM_ID),
+        &[accounts.pda.clone(), accounts.system_program.clone()],
+        &[&pda_signer],
+    )?;
+
+    let mut data = DELEGATE_DISCRIMINATOR.to_le_bytes().to_vec();
+    DelegateAccountArgs {
+        commit_frequency_ms,
+        seeds: pda_seeds.iter().map(|seed| seed.to_vec()).collect(),
+        validator,
+    }
+    .serialize(&mut data)?;
+
+    invoke_signed(
+        &Instruction {
+            program_id: DELEGATION_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(accounts.payer.key(), true),
+                AccountMeta::new(pda_key, true),
+                AccountMeta::new_readonly(accounts.owner_program.key(), false),
+                AccountMeta::new(accounts.buffer.key(), false),
+                AccountMeta::new(accounts.delegation_record.key(), false),
+                AccountMeta::new(accounts.delegation_metadata.key(), false),
+                AccountMeta::new_readonly(accounts.system_program.key(), false),
+            ],
+            data,
+        },
+        &[
+            accounts.payer.clone(),
+            accounts.pda.clone(),
+            accounts.owner_program.clone(),
+            accounts.buffer.clone(),
+            accounts.delegation_record.clone(),
+            accounts.delegation_metadata.clone(),
+            accounts.system_program.clone(),
+            accounts.delegation_program.clone(),
+        ],
+        &[&pda_signer],
+    )?;
+
+    // Close the buffer back to the payer
+    accounts.buffer.resize(0)?;
+    accounts.buffer.assign(accounts.system_program.key);
+    let buffer_lamports = accounts.buffer.lamports();
+    if buffer_lamports > 0 {
+        invoke_signed(
+            &system_instruction::transfer(
+                &accounts.buffer.key(),
+                &accounts.payer.key(),
+                buffer_lamports,
+            ),
+            &[
+                accounts.buffer.clone(),
+                accounts.payer.clone(),
+                accounts.system_program.clone(),
+            ],
+            &[buffer_signer],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Schedule delegated accounts to be committed to the base layer by the ephemeral validator
+pub(crate) fn schedule_commit<'info>(
+    payer: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    magic_context: &AccountInfo<'info>,
+    magic_program: &AccountInfo<'info>,
+    undelegate: bool,
+) -> Result<()> {
+    let tag = if undelegate {
+        SCHEDULE_COMMIT_AND_UNDELEGATE
+    } else {
+        SCHEDULE_COMMIT
+    };
+
+    let mut account_metas = vec![
+        AccountMeta::new(payer.key(), true),
+        AccountMeta::new(magic_context.key(), false),
+    ];
+    account_metas.extend(accounts.iter().map(|account| AccountMeta {
+        pubkey: account.key(),
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+
+    let mut account_infos = vec![payer.clone(), magic_context.clone()];
+    account_infos.extend(accounts.iter().cloned());
+    account_infos.push(magic_program.clone());
+
+    invoke(
+        &Instruction {
+            program_id: MAGIC_PROGRAM_ID,
+            accounts: account_metas,
+            data: tag.to_le_bytes().to_vec(),
+        },
+        &account_infos,
+    )?;
+
+    Ok(())
+}
+
+/// Create (or re-create) a PDA owned by this program
+fn create_pda<'info>(
+    target: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[&[u8]]],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+
+    if target.lamports() == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                &payer.key(),
+                &target.key(),
+                rent,
+                space as u64,
+                &crate::ID,
+            ),
+            &[payer.clone(), target.clone(), system_program.clone()],
+            signer_seeds,
+        )?;
+    } else {
+        let top_up = rent.saturating_sub(target.lamports());
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(&payer.key(), &target.key(), top_up),
+                &[payer.clone(), target.clone(), system_program.clone()],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(&target.key(), space as u64),
+            &[target.clone(), system_program.clone()],
+            signer_seeds,
+        )?;
+        invoke_signed(
+            &system_instruction::assign(&target.key(), &crate::ID),
+            &[target.clone(), system_program.clone()],
+            signer_seeds,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum DelegatedAccountKind {
+    Transaction,
+    RollupSession,
+}
+
+// Context structures
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct DelegateTransaction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub owner: Signer<'info>,
+    /// CHECK: Transaction PDA handed to the delegation program, deserialized in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"transaction", owner.key().as_ref(), &transaction_id],
+        bump
+    )]
+    pub transaction_account: UncheckedAccount<'info>,
+    /// CHECK: Temporary delegation buffer, created and closed in the handler
+    #[account(
+        mut,
+        seeds = [BUFFER_SEED, transaction_account.key().as_ref()],
+        bump
+    )]
+    pub buffer: UncheckedAccount<'info>,
+    /// CHECK: Initialized by the delegation program
+    #[account(
+        mut,
+        seeds = [DELEGATION_RECORD_SEED, transaction_account.key().as_ref()],
+        seeds::program = DELEGATION_PROGRAM_ID,
+        bump
+    )]
+    pub delegation_record: UncheckedAccount<'info>,
+    /// CHECK: Initialized by the delegation program
+    #[account(
+        mut,
+        seeds = [DELEGATION_METADATA_SEED, transaction_account.key().as_ref()],
+        seeds::program = DELEGATION_PROGRAM_ID,
+        bump
+    )]
+    pub delegation_metadata: UncheckedAccount<'info>,
+    /// CHECK: This program, passed as the delegated account owner
+    #[account(address = crate::ID)]
+    pub owner_program: UncheckedAccount<'info>,
+    /// CHECK: MagicBlock delegation program
+    #[account(address = DELEGATION_PROGRAM_ID)]
+    pub delegation_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct DelegateRollupSession<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Rollup session PDA handed to the delegation program, checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub session_account: UncheckedAccount<'info>,
+    /// CHECK: Temporary delegation buffer, created and closed in the handler
+    #[account(
+        mut,
+        seeds = [BUFFER_SEED, session_account.key().as_ref()],
+        bump
+    )]
+    pub buffer: UncheckedAccount<'info>,
+    /// CHECK: Initialized by the delegation program
+    #[account(
+        mut,
+        seeds = [DELEGATION_RECORD_SEED, session_account.key().as_ref()],
+        seeds::program = DELEGATION_PROGRAM_ID,
+        bump
+    )]
+    pub delegation_record: UncheckedAccount<'info>,
+    /// CHECK: Initialized by the delegation program
+    #[account(
+        mut,
+        seeds = [DELEGATION_METADATA_SEED, session_account.key().as_ref()],
+        seeds::program = DELEGATION_PROGRAM_ID,
+        bump
+    )]
+    pub delegation_metadata: UncheckedAccount<'info>,
+    /// CHECK: This program, passed as the delegated account owner
+    #[account(address = crate::ID)]
+    pub owner_program: UncheckedAccount<'info>,
+    /// CHECK: MagicBlock delegation program
+    #[account(address = DELEGATION_PROGRAM_ID)]
+    pub delegation_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct UndelegateTransaction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"transaction", owner.key().as_ref(), &transaction_id],
+        bump
+    )]
+    pub transaction_account: Account<'info, Transaction>,
+    /// CHECK: MagicBlock commit context
+    #[account(mut, address = MAGIC_CONTEXT_ID)]
+    pub magic_context: UncheckedAccount<'info>,
+    /// CHECK: MagicBlock magic program
+    #[account(address = MAGIC_PROGRAM_ID)]
+    pub magic_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct UndelegateRollupSession<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
+        bump,
+        constraint = session_account.transaction_id == transaction_id @ ErrorCode::InvalidTransactionId
+    )]
+    pub session_account: Account<'info, RollupSession>,
+    #[account(
+        mut,
+        constraint = session_account.linked_transaction == Some(transaction_account.key())
+            @ ErrorCode::InvalidTransactionId
+    )]
+    pub transaction_account: Option<Account<'info, Transaction>>,
+    /// CHECK: MagicBlock commit context
+    #[account(mut, address = MAGIC_CONTEXT_ID)]
+    pub magic_context: UncheckedAccount<'info>,
+    /// CHECK: MagicBlock magic program
+    #[account(address = MAGIC_PROGRAM_ID)]
+    pub magic_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessUndelegation<'info> {
+    /// CHECK: Delegated PDA being restored, validated against the seeds in the handler
+    #[account(mut)]
+    pub base_account: UncheckedAccount<'info>,
+    /// CHECK: Undelegation buffer signed by the delegation program, validated in the handler
+    pub buffer: UncheckedAccount<'info>,
+    /// CHECK: Funds the restored PDA's rent
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Events
+#[event]
+pub struct AccountDelegated {
+    pub account: Pubkey,
+    pub kind: DelegatedAccountKind,
+    pub validator: Option<Pubkey>,
+    pub commit_frequency_ms: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountUndelegated {
+    pub account: Pubkey,
+    pub kind: DelegatedAccountKind,
+    pub timestamp: i64,
+}
@@ -1,13 +1,164 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use solana_sha256_hasher::hashv;
+
+pub mod delegation;
+pub mod magicblock_integration;
+
+pub use delegation::*;
+pub use magicblock_integration::*;
 
 declare_id!("835NApE56thzrECSzQnBiEGgwDpgHbeMxw9xPWHZcsEj");
 
+/// Zephyra Route Executor program, owner of `RouteExecution` accounts
+pub const ROUTE_EXECUTOR_PROGRAM_ID: Pubkey = pubkey!("ED8b3n8TAyFGohFofCFiUFFT2X3xpsUpSEajWmCZbJ8n");
+/// Zephyra Proof Verifier program, owner of `ProofOfRoute` accounts
+pub const PROOF_VERIFIER_PROGRAM_ID: Pubkey = pubkey!("CYeCspx1DjYxYQZgMWz1sRPzKg9UxVWhMx5rRM4HihzQ");
+/// Batch Coordinator program, owner of batches covered by batch rollup sessions
+pub const BATCH_COORDINATOR_PROGRAM_ID: Pubkey = pubkey!("Cxajg3f3QNHXLqD1w5yYCMN4V7BsQKXE2LAnEPH73F8S");
+
+/// Maximum number of input mints tracked individually per protection account
+const MAX_TRACKED_MINTS: usize = 8;
+/// Maximum number of status transitions kept on a transaction's timeline
+const MAX_TIMELINE_ENTRIES: usize = 10;
+/// Maximum number of keepers allowed to advance any transaction
+pub const MAX_KEEPERS: usize = 8;
+
 /// Zephyra Protection Manager Program
 /// Central coordinator for MEV protection and transaction management
 #[program]
 pub mod zephyra_protection_manager {
     use super::*;
 
+    /// Initialize protocol-wide configuration, signed by the program's upgrade authority
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        treasury: Pubkey,
+        submission_deposit: u64,
+        transaction_expiry: i64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        require!(transaction_expiry > 0, ErrorCode::InvalidConfig);
+
+        config.admin = ctx.accounts.admin.key();
+        config.treasury = treasury;
+        config.submission_deposit = submission_deposit;
+        config.transaction_expiry = transaction_expiry;
+        config.bump = ctx.bumps.config;
+        config.challenge_window_slots = 0;
+        config.committer_bond = 0;
+        config.keepers = Vec::new();
+
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            treasury,
+            submission_deposit,
+            transaction_expiry,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Update protocol-wide configuration
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        treasury: Pubkey,
+        submission_deposit: u64,
+        transaction_expiry: i64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        require!(transaction_expiry > 0, ErrorCode::InvalidConfig);
+
+        config.treasury = treasury;
+        config.submission_deposit = submission_deposit;
+        config.transaction_expiry = transaction_expiry;
+
+        emit!(ConfigUpdated {
+            admin: config.admin,
+            treasury,
+            submission_deposit,
+            transaction_expiry,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Configure optimistic finality for rollup commits
+    pub fn update_rollup_config(
+        ctx: Context<UpdateConfig>,
+        challenge_window_slots: u64,
+        committer_bond: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        // A bond without a window to challenge in would never be slashed
+        require!(
+            challenge_window_slots > 0 || committer_bond == 0,
+            ErrorCode::InvalidConfig
+        );
+
+        config.challenge_window_slots = challenge_window_slots;
+        config.committer_bond = committer_bond;
+
+        emit!(RollupConfigUpdated {
+            challenge_window_slots,
+            committer_bond,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the keepers allowed to advance any transaction's status
+    pub fn set_keepers(ctx: Context<UpdateConfig>, keepers: Vec<Pubkey>) -> Result<()> {
+        require!(keepers.len() <= MAX_KEEPERS, ErrorCode::InvalidConfig);
+
+        ctx.accounts.config.keepers = keepers.clone();
+
+        emit!(KeepersUpdated {
+            keepers,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Register a rollup validator key allowed to sign commits
+    pub fn register_rollup_validator(
+        ctx: Context<RegisterRollupValidator>,
+        validator: Pubkey,
+    ) -> Result<()> {
+        let rollup_validator = &mut ctx.accounts.rollup_validator;
+        let clock = Clock::get()?;
+
+        rollup_validator.validator = validator;
+        rollup_validator.registered_at = clock.unix_timestamp;
+        rollup_validator.bump = ctx.bumps.rollup_validator;
+
+        emit!(RollupValidatorUpdated {
+            validator,
+            registered: true,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a registered rollup validator key
+    pub fn deregister_rollup_validator(ctx: Context<DeregisterRollupValidator>) -> Result<()> {
+        emit!(RollupValidatorUpdated {
+            validator: ctx.accounts.rollup_validator.validator,
+            registered: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
     /// Initialize protection account for user
     pub fn initialize_protection(
         ctx: Context<InitializeProtection>,
@@ -20,11 +171,13 @@ pub mod zephyra_protection_manager {
         protection_account.total_transactions = 0;
         protection_account.total_savings = 0;
         protection_account.mev_attacks_blocked = 0;
+        protection_account.mint_stats = Vec::new();
         protection_account.settings = ProtectionSettings {
             max_slippage_bps: 100, // 1% default
             max_mev_risk_score: 50, // Medium risk threshold
             auto_execute: true,
             batch_enabled: true,
+            execution_guard: ExecutionGuard::default(),
         };
         protection_account.bump = ctx.bumps.protection_account;
 
@@ -42,8 +195,23 @@ pub mod zephyra_protection_manager {
         input_token: Pubkey,
         output_token: Pubkey,
         input_amount: u64,
-        _min_output_amount: u64,
+        min_output_amount: u64,
     ) -> Result<[u8; 32]> {
+        // Hold the anti-spam deposit on the transaction account until it settles
+        let deposit = ctx.accounts.config.submission_deposit;
+        if deposit > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: ctx.accounts.transaction_account.to_account_info(),
+                    },
+                ),
+                deposit,
+            )?;
+        }
+
         let transaction_account = &mut ctx.accounts.transaction_account;
         let protection_account = &mut ctx.accounts.protection_account;
         let clock = Clock::get()?;
@@ -62,17 +230,32 @@ pub mod zephyra_protection_manager {
         transaction_account.input_token = input_token;
         transaction_account.output_token = output_token;
         transaction_account.input_amount = input_amount;
+        transaction_account.min_output_amount = min_output_amount;
         transaction_account.output_amount = 0; // Will be set after execution
         transaction_account.risk_score = 0; // Will be set by AI analysis
-        transaction_account.selected_route = 0; // 0=Jupiter, 1=Raydium, 2=Orca
-        transaction_account.status = TransactionStatus::Pending;
+        transaction_account.selected_route = None; // Set once a route is selected
         transaction_account.proof_hash = [0u8; 32]; // Will be set after proof generation
         transaction_account.batch_id = None;
         transaction_account.created_at = clock.unix_timestamp;
         transaction_account.completed_at = None;
+        transaction_account.created_slot = clock.slot;
+        transaction_account.completed_slot = None;
+        transaction_account.timeline = Vec::new();
+        transaction_account.deposit = deposit;
+        transaction_account.depositor = ctx.accounts.payer.key();
+        record_status_change(
+            transaction_account,
+            TransactionStatus::Pending,
+            ctx.accounts.payer.key(),
+            &clock,
+        );
 
         // Update protection account stats
         protection_account.total_transactions += 1;
+        if let Some(stats) = mint_stats_entry(&mut protection_account.mint_stats, input_token) {
+            stats.transaction_count = stats.transaction_count.saturating_add(1);
+            stats.total_input = stats.total_input.saturating_add(input_amount);
+        }
 
         emit!(TransactionSubmitted {
             transaction_id,
@@ -80,6 +263,7 @@ pub mod zephyra_protection_manager {
             input_token,
             output_token,
             input_amount,
+            min_output_amount,
             timestamp: clock.unix_timestamp,
         });
 
@@ -101,6 +285,72 @@ pub mod zephyra_protection_manager {
         Ok(transaction_account.status)
     }
 
+    /// Get a full snapshot of a transaction, including linked accounts
+    pub fn get_transaction_view(
+        ctx: Context<GetTransactionView>,
+        transaction_id: [u8; 32],
+    ) -> Result<TransactionView> {
+        let transaction_account = &ctx.accounts.transaction_account;
+
+        require!(
+            transaction_account.id == transaction_id,
+            ErrorCode::InvalidTransactionId
+        );
+
+        let (proof_account, _) = Pubkey::find_program_address(
+            &[b"proof", transaction_id.as_ref()],
+            &PROOF_VERIFIER_PROGRAM_ID,
+        );
+        let route_execution_account = transaction_account.selected_route.map(|route| {
+            Pubkey::find_program_address(
+                &[
+                    b"route_execution",
+                    transaction_id.as_ref(),
+                    route.route_execution_seed(),
+                ],
+                &ROUTE_EXECUTOR_PROGRAM_ID,
+            )
+            .0
+        });
+
+        Ok(TransactionView {
+            id: transaction_account.id,
+            owner: transaction_account.owner,
+            input_token: transaction_account.input_token,
+            output_token: transaction_account.output_token,
+            input_amount: transaction_account.input_amount,
+            min_output_amount: transaction_account.min_output_amount,
+            output_amount: transaction_account.output_amount,
+            risk_score: transaction_account.risk_score,
+            selected_route: transaction_account.selected_route,
+            status: transaction_account.status,
+            proof_hash: transaction_account.proof_hash,
+            created_at: transaction_account.created_at,
+            completed_at: transaction_account.completed_at,
+            created_slot: transaction_account.created_slot,
+            last_updated_slot: transaction_account.last_updated_slot,
+            completed_slot: transaction_account.completed_slot,
+            timeline: transaction_account.timeline.clone(),
+            batch_id: transaction_account.batch_id,
+            proof_account,
+            route_execution_account,
+        })
+    }
+
+    /// Get a summary of a protection account with per-mint stats
+    pub fn get_account_summary(ctx: Context<GetAccountSummary>) -> Result<AccountSummary> {
+        let protection_account = &ctx.accounts.protection_account;
+
+        Ok(AccountSummary {
+            owner: protection_account.owner,
+            total_transactions: protection_account.total_transactions,
+            total_savings: protection_account.total_savings,
+            mev_attacks_blocked: protection_account.mev_attacks_blocked,
+            settings: protection_account.settings.clone(),
+            mint_stats: protection_account.mint_stats.clone(),
+        })
+    }
+
     /// Update protection settings
     pub fn update_settings(
         ctx: Context<UpdateSettings>,
@@ -125,7 +375,34 @@ pub mod zephyra_protection_manager {
         Ok(())
     }
 
-    /// Update transaction with AI risk analysis results
+    /// Set the checks the route executor runs on the owner's swaps
+    pub fn update_execution_guard(
+        ctx: Context<UpdateSettings>,
+        guard: ExecutionGuard,
+    ) -> Result<()> {
+        if let Some(oracle) = &guard.oracle {
+            require!(
+                oracle.max_age_secs > 0
+                    && oracle.max_confidence_bps <= 10_000
+                    && oracle.max_deviation_bps <= 10_000,
+                ErrorCode::InvalidExecutionGuard
+            );
+        }
+
+        let protection_account = &mut ctx.accounts.protection_account;
+        protection_account.settings.execution_guard = guard;
+
+        emit!(ExecutionGuardUpdated {
+            owner: protection_account.owner,
+            guard,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Update transaction with AI risk analysis results, signed by its owner or
+    /// a keeper
     pub fn update_risk_analysis(
         ctx: Context<UpdateRiskAnalysis>,
         transaction_id: [u8; 32],
@@ -140,9 +417,20 @@ pub mod zephyra_protection_manager {
             ErrorCode::InvalidTransactionId
         );
         require!(risk_score <= 100, ErrorCode::InvalidRiskScore);
+        require!(
+            transaction_account
+                .status
+                .can_advance_to(TransactionStatus::Analyzing),
+            ErrorCode::InvalidStatusTransition
+        );
 
         transaction_account.risk_score = risk_score;
-        transaction_account.status = TransactionStatus::Analyzing;
+        record_status_change(
+            transaction_account,
+            TransactionStatus::Analyzing,
+            ctx.accounts.authority.key(),
+            &Clock::get()?,
+        );
 
         if mev_detected {
             protection_account.mev_attacks_blocked += 1;
@@ -158,7 +446,69 @@ pub mod zephyra_protection_manager {
         Ok(())
     }
 
-    /// Complete transaction execution
+    /// Advance a transaction through an intermediate lifecycle stage, signed by
+    /// its owner or a keeper
+    pub fn update_transaction_status(
+        ctx: Context<UpdateTransactionStatus>,
+        transaction_id: [u8; 32],
+        status: TransactionStatus,
+    ) -> Result<()> {
+        let transaction_account = &mut ctx.accounts.transaction_account;
+
+        require!(
+            transaction_account.id == transaction_id,
+            ErrorCode::InvalidTransactionId
+        );
+        // Completion carries execution results, see `complete_transaction`
+        require!(
+            status != TransactionStatus::Completed
+                && transaction_account.status.can_advance_to(status),
+            ErrorCode::InvalidStatusTransition
+        );
+
+        record_status_change(
+            transaction_account,
+            status,
+            ctx.accounts.authority.key(),
+            &Clock::get()?,
+        );
+
+        Ok(())
+    }
+
+    /// Record the route a transaction executes along, moving it to
+    /// RouteSelected. Signed by its owner or a keeper.
+    pub fn select_route(
+        ctx: Context<UpdateTransactionStatus>,
+        transaction_id: [u8; 32],
+        route: SelectedRoute,
+    ) -> Result<()> {
+        let transaction_account = &mut ctx.accounts.transaction_account;
+
+        require!(
+            transaction_account.id == transaction_id,
+            ErrorCode::InvalidTransactionId
+        );
+        require!(
+            transaction_account
+                .status
+                .can_advance_to(TransactionStatus::RouteSelected),
+            ErrorCode::InvalidStatusTransition
+        );
+
+        transaction_account.selected_route = Some(route);
+        record_status_change(
+            transaction_account,
+            TransactionStatus::RouteSelected,
+            ctx.accounts.authority.key(),
+            &Clock::get()?,
+        );
+
+        Ok(())
+    }
+
+    /// Complete an executing transaction with its execution results, signed by
+    /// a keeper or by the route executor at the end of its swap
     pub fn complete_transaction(
         ctx: Context<CompleteTransaction>,
         transaction_id: [u8; 32],
@@ -173,11 +523,23 @@ pub mod zephyra_protection_manager {
             transaction_account.id == transaction_id,
             ErrorCode::InvalidTransactionId
         );
+        require!(
+            transaction_account
+                .status
+                .can_advance_to(TransactionStatus::Completed),
+            ErrorCode::InvalidStatusTransition
+        );
 
         transaction_account.output_amount = output_amount;
         transaction_account.proof_hash = proof_hash;
-        transaction_account.status = TransactionStatus::Completed;
         transaction_account.completed_at = Some(clock.unix_timestamp);
+        transaction_account.completed_slot = Some(clock.slot);
+        record_status_change(
+            transaction_account,
+            TransactionStatus::Completed,
+            ctx.accounts.authority.key(),
+            &clock,
+        );
 
         // Calculate savings (simplified - in production this would be more complex)
         let savings = if output_amount > transaction_account.input_amount {
@@ -186,7 +548,22 @@ pub mod zephyra_protection_manager {
             0
         };
 
-        protection_account.total_savings += savings;
+        protection_account.total_savings = protection_account
+            .total_savings
+            .checked_add(savings)
+            .ok_or(ErrorCode::MathOverflow)?;
+        settle_deposit(
+            transaction_account,
+            &ctx.accounts.depositor.to_account_info(),
+            true,
+        )?;
+        if let Some(stats) =
+            mint_stats_entry(&mut protection_account.mint_stats, transaction_account.input_token)
+        {
+            stats.completed_count = stats.completed_count.saturating_add(1);
+            stats.total_output = stats.total_output.saturating_add(output_amount);
+            stats.total_savings = stats.total_savings.saturating_add(savings);
+        }
 
         emit!(TransactionCompleted {
             transaction_id,
@@ -198,42 +575,485 @@ pub mod zephyra_protection_manager {
 
         Ok(())
     }
+
+    /// Cancel an unfinished transaction, refunding its deposit and rent
+    pub fn cancel_transaction(
+        ctx: Context<CancelTransaction>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        let transaction_account = &mut ctx.accounts.transaction_account;
+
+        require!(
+            transaction_account.id == transaction_id,
+            ErrorCode::InvalidTransactionId
+        );
+        require!(
+            !matches!(
+                transaction_account.status,
+                TransactionStatus::Executing | TransactionStatus::Completed
+            ),
+            ErrorCode::TransactionNotCancellable
+        );
+
+        // Remaining rent follows the deposit back to the depositor on close
+        settle_deposit(
+            transaction_account,
+            &ctx.accounts.depositor.to_account_info(),
+            true,
+        )?;
+
+        Ok(())
+    }
+
+    /// Expire an abandoned transaction, forfeiting its deposit to the treasury. An
+    /// execution that never settles fails instead, once it times out.
+    pub fn expire_transaction(
+        ctx: Context<ExpireTransaction>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        let transaction_account = &mut ctx.accounts.transaction_account;
+        let clock = Clock::get()?;
+
+        require!(
+            transaction_account.id == transaction_id,
+            ErrorCode::InvalidTransactionId
+        );
+        // Finished transactions have settled
+        require!(
+            !matches!(
+                transaction_account.status,
+                TransactionStatus::Completed | TransactionStatus::Failed
+            ),
+            ErrorCode::TransactionNotExpired
+        );
+
+        if transaction_account.status == TransactionStatus::Executing {
+            // The swap may still settle until the expiry has passed since it started.
+            // Failing it lets the owner cancel to reclaim the deposit.
+            let started_at = transaction_account
+                .timeline
+                .last()
+                .map_or(transaction_account.created_at, |entry| entry.timestamp);
+            let times_out_at = started_at
+                .checked_add(ctx.accounts.config.transaction_expiry)
+                .ok_or(ErrorCode::InvalidConfig)?;
+            require!(
+                clock.unix_timestamp >= times_out_at,
+                ErrorCode::TransactionNotExpired
+            );
+
+            // No one signs an expiry
+            record_status_change(
+                transaction_account,
+                TransactionStatus::Failed,
+                Pubkey::default(),
+                &clock,
+            );
+            return Ok(());
+        }
+
+        let expires_at = transaction_account
+            .created_at
+            .checked_add(ctx.accounts.config.transaction_expiry)
+            .ok_or(ErrorCode::InvalidConfig)?;
+        require!(
+            clock.unix_timestamp >= expires_at,
+            ErrorCode::TransactionNotExpired
+        );
+
+        settle_deposit(
+            transaction_account,
+            &ctx.accounts.treasury.to_account_info(),
+            false,
+        )?;
+
+        transaction_account.close(ctx.accounts.depositor.to_account_info())
+    }
+
+    /// Close a completed transaction, returning its rent to the depositor. Its
+    /// deposit was refunded on completion.
+    pub fn close_transaction(
+        ctx: Context<CloseTransaction>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        let transaction_account = &ctx.accounts.transaction_account;
+
+        require!(
+            transaction_account.id == transaction_id,
+            ErrorCode::InvalidTransactionId
+        );
+        require!(
+            transaction_account.status == TransactionStatus::Completed,
+            ErrorCode::TransactionNotCompleted
+        );
+
+        Ok(())
+    }
+
+    /// Initialize ephemeral rollup session
+    pub fn init_rollup_session(
+        ctx: Context<InitRollupSession>,
+        transaction_id: [u8; 32],
+        executor: Option<Pubkey>,
+        budget: Option<SessionBudget>,
+    ) -> Result<RollupSessionId> {
+        magicblock_integration::handlers::init_rollup_session(
+            ctx,
+            transaction_id,
+            executor,
+            budget,
+        )
+    }
+
+    /// Initialize ephemeral rollup session for a whole batch
+    pub fn init_batch_rollup_session(
+        ctx: Context<InitBatchRollupSession>,
+        batch_id: [u8; 32],
+        executor: Option<Pubkey>,
+        budget: Option<SessionBudget>,
+    ) -> Result<RollupSessionId> {
+        magicblock_integration::handlers::init_batch_rollup_session(
+            ctx, batch_id, executor, budget,
+        )
+    }
+
+    /// Execute instruction in rollup
+    pub fn execute_in_rollup(
+        ctx: Context<ExecuteInRollup>,
+        session_id: RollupSessionId,
+        instruction_data: Vec<u8>,
+    ) -> Result<RollupResult> {
+        magicblock_integration::handlers::execute_in_rollup(ctx, session_id, instruction_data)
+    }
+
+    /// Commit rollup state to mainnet
+    pub fn commit_rollup<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CommitRollup<'info>>,
+        session_id: RollupSessionId,
+    ) -> Result<CommitProof> {
+        magicblock_integration::handlers::commit_rollup(ctx, session_id)
+    }
+
+    /// Rollback rollup state and restore the linked transaction
+    pub fn rollback_rollup(
+        ctx: Context<RollbackRollup>,
+        session_id: RollupSessionId,
+        reason: RollbackReason,
+        note: Option<String>,
+    ) -> Result<()> {
+        magicblock_integration::handlers::rollback_rollup(ctx, session_id, reason, note)
+    }
+
+    /// Expire a stale rollup session or close a finished one
+    pub fn expire_rollup_session(
+        ctx: Context<ExpireRollupSession>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        magicblock_integration::handlers::expire_rollup_session(ctx, transaction_id)
+    }
+
+    /// Finalize a rollup commit once its challenge window has passed
+    pub fn finalize_rollup(
+        ctx: Context<FinalizeRollup>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        magicblock_integration::handlers::finalize_rollup(ctx, transaction_id)
+    }
+
+    /// Dispute a pending rollup commit with a fraud proof for one op
+    pub fn challenge_rollup(
+        ctx: Context<ChallengeRollup>,
+        transaction_id: [u8; 32],
+        index: u32,
+        op: RollupOp,
+        claimed_result: RollupResult,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        magicblock_integration::handlers::challenge_rollup(
+            ctx,
+            transaction_id,
+            index,
+            op,
+            claimed_result,
+            proof,
+        )
+    }
+
+    /// Write one chunk of an executed op's output
+    pub fn write_rollup_output(
+        ctx: Context<WriteRollupOutput>,
+        session_id: RollupSessionId,
+        op_index: u32,
+        chunk_index: u16,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        magicblock_integration::handlers::write_rollup_output(
+            ctx,
+            session_id,
+            op_index,
+            chunk_index,
+            data,
+        )
+    }
+
+    /// Close an op's output and its chunks once their session has closed
+    pub fn close_rollup_output<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseRollupOutput<'info>>,
+    ) -> Result<()> {
+        magicblock_integration::handlers::close_rollup_output(ctx)
+    }
+
+    /// Verify inclusion of an executed op in a rollup session
+    pub fn verify_rollup_op(
+        ctx: Context<VerifyRollupOp>,
+        session_id: RollupSessionId,
+        index: u32,
+        op: RollupOp,
+        result: RollupResult,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        magicblock_integration::handlers::verify_rollup_op(
+            ctx, session_id, index, op, result, proof,
+        )
+    }
+
+    /// Delegate a transaction to an ephemeral rollup
+    pub fn delegate_transaction(
+        ctx: Context<DelegateTransaction>,
+        transaction_id: [u8; 32],
+        commit_frequency_ms: u32,
+        validator: Option<Pubkey>,
+    ) -> Result<()> {
+        delegation::instructions::delegate_transaction(
+            ctx,
+            transaction_id,
+            commit_frequency_ms,
+            validator,
+        )
+    }
+
+    /// Delegate a rollup session to an ephemeral rollup
+    pub fn delegate_rollup_session(
+        ctx: Context<DelegateRollupSession>,
+        transaction_id: [u8; 32],
+        commit_frequency_ms: u32,
+        validator: Option<Pubkey>,
+    ) -> Result<()> {
+        delegation::instructions::delegate_rollup_session(
+            ctx,
+            transaction_id,
+            commit_frequency_ms,
+            validator,
+        )
+    }
+
+    /// Commit and undelegate a transaction from an ephemeral rollup
+    pub fn undelegate_transaction(
+        ctx: Context<UndelegateTransaction>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        delegation::instructions::undelegate_transaction(ctx, transaction_id)
+    }
+
+    /// Commit and undelegate a rollup session from an ephemeral rollup
+    pub fn undelegate_rollup_session<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UndelegateRollupSession<'info>>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        delegation::instructions::undelegate_rollup_session(ctx, transaction_id)
+    }
+
+    /// Delegation program callback restoring an undelegated account
+    pub fn process_undelegation(
+        ctx: Context<ProcessUndelegation>,
+        account_seeds: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        delegation::instructions::process_undelegation(ctx, account_seeds)
+    }
+}
+
+/// Move a transaction's held deposit to `recipient`
+fn settle_deposit<'info>(
+    transaction_account: &mut Account<'info, Transaction>,
+    recipient: &AccountInfo<'info>,
+    refunded: bool,
+) -> Result<()> {
+    let amount = transaction_account.deposit;
+    if amount == 0 {
+        return Ok(());
+    }
+
+    transaction_account.sub_lamports(amount)?;
+    recipient.add_lamports(amount)?;
+    transaction_account.deposit = 0;
+
+    emit!(DepositSettled {
+        transaction_id: transaction_account.id,
+        recipient: recipient.key(),
+        amount,
+        refunded,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Set a transaction's status and record the transition on its timeline
+fn record_status_change(
+    transaction_account: &mut Transaction,
+    status: TransactionStatus,
+    signer: Pubkey,
+    clock: &Clock,
+) {
+    let previous_status = transaction_account.status;
+
+    transaction_account.status = status;
+    transaction_account.last_updated_slot = clock.slot;
+
+    // Keep the most recent transitions once the timeline is full
+    if transaction_account.timeline.len() >= MAX_TIMELINE_ENTRIES {
+        transaction_account.timeline.remove(0);
+    }
+    transaction_account.timeline.push(StatusTransition {
+        status,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+        signer,
+    });
+
+    emit!(TransactionStatusChanged {
+        transaction_id: transaction_account.id,
+        previous_status,
+        new_status: status,
+        signer,
+        slot: clock.slot,
+        timestamp: clock.unix_timestamp,
+    });
+}
+
+/// Find or start the stats entry for a mint, or `None` once all slots are taken
+fn mint_stats_entry(mint_stats: &mut Vec<MintStats>, mint: Pubkey) -> Option<&mut MintStats> {
+    match mint_stats.iter().position(|stats| stats.mint == mint) {
+        Some(index) => mint_stats.get_mut(index),
+        None if mint_stats.len() < MAX_TRACKED_MINTS => {
+            mint_stats.push(MintStats {
+                mint,
+                transaction_count: 0,
+                completed_count: 0,
+                total_input: 0,
+                total_output: 0,
+                total_savings: 0,
+            });
+            mint_stats.last_mut()
+        }
+        None => None,
+    }
 }
 
-/// Generate unique transaction ID
-fn generate_transaction_id(
+/// Route executor escrow authority for a transaction, the signer of its completion CPI
+pub fn escrow_authority(owner: &Pubkey, transaction_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"escrow", owner.as_ref(), transaction_id.as_ref()],
+        &ROUTE_EXECUTOR_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Generate unique transaction ID, a hash of its inputs so that no choice of
+/// inputs reproduces another owner's ID
+pub fn generate_transaction_id(
     owner: &Pubkey,
     input_token: &Pubkey,
     output_token: &Pubkey,
     input_amount: u64,
     timestamp: i64,
 ) -> [u8; 32] {
-    // Generate ID by XOR-ing all inputs into 32 bytes
-    let mut id = [0u8; 32];
-    
-    // XOR owner pubkey
-    for (i, byte) in owner.to_bytes().iter().enumerate() {
-        id[i] ^= byte;
-    }
-    
-    // XOR input_token (with offset to mix better)
-    for (i, byte) in input_token.to_bytes().iter().enumerate() {
-        id[(i + 8) % 32] ^= byte;
-    }
-    
-    // XOR output_token (with different offset)
-    for (i, byte) in output_token.to_bytes().iter().enumerate() {
-        id[(i + 16) % 32] ^= byte;
-    }
-    
-    // XOR amount and timestamp
-    let amount_bytes = input_amount.to_le_bytes();
-    let timestamp_bytes = timestamp.to_le_bytes();
-    for i in 0..8 {
-        id[i + 24] ^= amount_bytes[i] ^ timestamp_bytes[i];
-    }
-    
-    id
+    hashv(&[
+        owner.as_ref(),
+        input_token.as_ref(),
+        output_token.as_ref(),
+        &input_amount.to_le_bytes(),
+        &timestamp.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::ZephyraProtectionManager>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(validator: Pubkey)]
+pub struct RegisterRollupValidator<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RollupValidator::INIT_SPACE,
+        seeds = [b"rollup_validator", validator.as_ref()],
+        bump
+    )]
+    pub rollup_validator: Account<'info, RollupValidator>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterRollupValidator<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"rollup_validator", rollup_validator.validator.as_ref()],
+        bump = rollup_validator.bump
+    )]
+    pub rollup_validator: Account<'info, RollupValidator>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -255,6 +1075,11 @@ pub struct InitializeProtection<'info> {
 #[derive(Accounts)]
 #[instruction(input_token: Pubkey, output_token: Pubkey, input_amount: u64, min_output_amount: u64)]
 pub struct SubmitTransaction<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
     #[account(
         mut,
         seeds = [b"protection", protection_account.owner.as_ref()],
@@ -289,6 +1114,30 @@ pub struct GetTransactionStatus<'info> {
     pub transaction_account: Account<'info, Transaction>,
 }
 
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct GetTransactionView<'info> {
+    #[account(
+        seeds = [b"protection", protection_account.owner.as_ref()],
+        bump = protection_account.bump
+    )]
+    pub protection_account: Account<'info, ProtectionAccount>,
+    #[account(
+        seeds = [b"transaction", protection_account.owner.as_ref(), &transaction_id],
+        bump
+    )]
+    pub transaction_account: Account<'info, Transaction>,
+}
+
+#[derive(Accounts)]
+pub struct GetAccountSummary<'info> {
+    #[account(
+        seeds = [b"protection", protection_account.owner.as_ref()],
+        bump = protection_account.bump
+    )]
+    pub protection_account: Account<'info, ProtectionAccount>,
+}
+
 #[derive(Accounts)]
 #[instruction(max_slippage: u16, max_mev_risk: u8)]
 pub struct UpdateSettings<'info> {
@@ -296,7 +1145,7 @@ pub struct UpdateSettings<'info> {
         mut,
         seeds = [b"protection", protection_account.owner.as_ref()],
         bump = protection_account.bump,
-        constraint = protection_account.owner == payer.key()
+        constraint = protection_account.owner == payer.key() @ ErrorCode::Unauthorized
     )]
     pub protection_account: Account<'info, ProtectionAccount>,
     #[account(mut)]
@@ -306,6 +1155,11 @@ pub struct UpdateSettings<'info> {
 #[derive(Accounts)]
 #[instruction(transaction_id: [u8; 32], risk_score: u8, mev_detected: bool)]
 pub struct UpdateRiskAnalysis<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
     #[account(
         mut,
         seeds = [b"protection", protection_account.owner.as_ref()],
@@ -318,11 +1172,47 @@ pub struct UpdateRiskAnalysis<'info> {
         bump
     )]
     pub transaction_account: Account<'info, Transaction>,
+    #[account(
+        constraint = authority.key() == protection_account.owner
+            || config.is_keeper(&authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct UpdateTransactionStatus<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        seeds = [b"protection", protection_account.owner.as_ref()],
+        bump = protection_account.bump
+    )]
+    pub protection_account: Account<'info, ProtectionAccount>,
+    #[account(
+        mut,
+        seeds = [b"transaction", protection_account.owner.as_ref(), &transaction_id],
+        bump
+    )]
+    pub transaction_account: Account<'info, Transaction>,
+    #[account(
+        constraint = authority.key() == protection_account.owner
+            || config.is_keeper(&authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
 #[instruction(transaction_id: [u8; 32], output_amount: u64, proof_hash: [u8; 32])]
 pub struct CompleteTransaction<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
     #[account(
         mut,
         seeds = [b"protection", protection_account.owner.as_ref()],
@@ -335,6 +1225,112 @@ pub struct CompleteTransaction<'info> {
         bump
     )]
     pub transaction_account: Account<'info, Transaction>,
+    /// CHECK: Receives the refunded deposit, validated against the transaction
+    #[account(mut, address = transaction_account.depositor)]
+    pub depositor: UncheckedAccount<'info>,
+    /// A keeper, or the route executor's escrow for this transaction signing its CPI
+    #[account(
+        constraint = config.is_keeper(&authority.key())
+            || authority.key() == escrow_authority(&protection_account.owner, &transaction_id)
+            @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct CancelTransaction<'info> {
+    #[account(
+        seeds = [b"protection", protection_account.owner.as_ref()],
+        bump = protection_account.bump,
+        constraint = protection_account.owner == owner.key() @ ErrorCode::Unauthorized
+    )]
+    pub protection_account: Account<'info, ProtectionAccount>,
+    #[account(
+        mut,
+        close = depositor,
+        seeds = [b"transaction", protection_account.owner.as_ref(), &transaction_id],
+        bump
+    )]
+    pub transaction_account: Account<'info, Transaction>,
+    /// CHECK: Receives the refunded deposit and rent, validated against the transaction
+    #[account(mut, address = transaction_account.depositor)]
+    pub depositor: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct ExpireTransaction<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    /// Closed to the depositor, unless a timed-out execution only fails
+    #[account(
+        mut,
+        seeds = [b"transaction", transaction_account.owner.as_ref(), &transaction_id],
+        bump
+    )]
+    pub transaction_account: Account<'info, Transaction>,
+    /// CHECK: Receives the forfeited deposit, validated against the config
+    #[account(mut, address = config.treasury)]
+    pub treasury: UncheckedAccount<'info>,
+    /// CHECK: Receives the remaining rent, validated against the transaction
+    #[account(mut, address = transaction_account.depositor)]
+    pub depositor: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct CloseTransaction<'info> {
+    #[account(
+        seeds = [b"protection", protection_account.owner.as_ref()],
+        bump = protection_account.bump,
+        constraint = protection_account.owner == owner.key() @ ErrorCode::Unauthorized
+    )]
+    pub protection_account: Account<'info, ProtectionAccount>,
+    #[account(
+        mut,
+        close = depositor,
+        seeds = [b"transaction", protection_account.owner.as_ref(), &transaction_id],
+        bump
+    )]
+    pub transaction_account: Account<'info, Transaction>,
+    /// CHECK: Receives the rent, validated against the transaction
+    #[account(mut, address = transaction_account.depositor)]
+    pub depositor: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub submission_deposit: u64, // in lamports, refundable
+    pub transaction_expiry: i64, // seconds before an unfinished transaction can be expired
+    pub bump: u8,
+    pub challenge_window_slots: u64, // 0 = rollup commits finalize immediately
+    pub committer_bond: u64, // in lamports, slashed on a successful challenge
+    /// Keeper wallets and Zephyra program signers allowed to advance any transaction
+    #[max_len(MAX_KEEPERS)]
+    pub keepers: Vec<Pubkey>,
+}
+
+impl ProtocolConfig {
+    pub fn is_keeper(&self, key: &Pubkey) -> bool {
+        self.keepers.contains(key)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RollupValidator {
+    pub validator: Pubkey, // Ed25519 key signing rollup commits
+    pub registered_at: i64,
+    pub bump: u8,
 }
 
 #[account]
@@ -346,6 +1342,18 @@ pub struct ProtectionAccount {
     pub mev_attacks_blocked: u32,
     pub settings: ProtectionSettings,
     pub bump: u8,
+    #[max_len(MAX_TRACKED_MINTS)]
+    pub mint_stats: Vec<MintStats>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct MintStats {
+    pub mint: Pubkey,
+    pub transaction_count: u64,
+    pub completed_count: u64,
+    pub total_input: u64,
+    pub total_output: u64,
+    pub total_savings: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
@@ -354,6 +1362,29 @@ pub struct ProtectionSettings {
     pub max_mev_risk_score: u8, // 0-100
     pub auto_execute: bool,
     pub batch_enabled: bool,
+    pub execution_guard: ExecutionGuard, // enforced by the route executor on every swap
+}
+
+/// Optional instruction-introspection and oracle checks for a protected swap
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct ExecutionGuard {
+    /// Require the swap to be a top-level instruction signed by the owner
+    pub enforce_caller: bool,
+    /// Reject transactions where another program touches the pool before this swap
+    pub reject_prior_pool_access: bool,
+    /// Reject swaps priced too far from the oracle price in the `price_feed` account
+    pub oracle: Option<OracleGuard>,
+}
+
+/// Bounds on a swap's effective price against a Pyth or Switchboard feed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct OracleGuard {
+    /// Oldest oracle price accepted, in seconds
+    pub max_age_secs: u32,
+    /// Widest confidence interval accepted, relative to the price
+    pub max_confidence_bps: u16,
+    /// Largest deviation of the effective price from the oracle price, either way
+    pub max_deviation_bps: u16,
 }
 
 #[account]
@@ -364,14 +1395,22 @@ pub struct Transaction {
     pub input_token: Pubkey,
     pub output_token: Pubkey,
     pub input_amount: u64,
+    pub min_output_amount: u64, // least output the route executor accepts for the input
     pub output_amount: u64,
     pub risk_score: u8,
-    pub selected_route: u8, // 0=Jupiter, 1=Raydium, 2=Orca
+    pub selected_route: Option<SelectedRoute>,
     pub status: TransactionStatus,
     pub proof_hash: [u8; 32],
     pub batch_id: Option<[u8; 32]>,
     pub created_at: i64,
     pub completed_at: Option<i64>,
+    pub created_slot: u64,
+    pub last_updated_slot: u64,
+    pub completed_slot: Option<u64>,
+    #[max_len(MAX_TIMELINE_ENTRIES)]
+    pub timeline: Vec<StatusTransition>,
+    pub deposit: u64, // anti-spam deposit held until completion, cancellation or expiry
+    pub depositor: Pubkey,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
@@ -382,6 +1421,157 @@ pub enum TransactionStatus {
     Executing,
     Completed,
     Failed,
+    RouteSelected,
+    Batched,
+}
+
+impl TransactionStatus {
+    /// Legal transitions between lifecycle stages. Pending is only set on
+    /// submission, Completed is only reached from Executing, and finished
+    /// transactions stay as they are.
+    pub fn can_advance_to(self, next: TransactionStatus) -> bool {
+        use TransactionStatus::*;
+
+        matches!(
+            (self, next),
+            (Pending | Analyzing, Simulating)
+                | (Pending | Simulating, Analyzing)
+                | (Pending | Simulating | Analyzing, RouteSelected)
+                | (RouteSelected, Batched)
+                | (RouteSelected | Batched, Executing)
+                | (Executing, Completed)
+                | (
+                    Pending | Simulating | Analyzing | RouteSelected | Batched | Executing,
+                    Failed
+                )
+        )
+    }
+}
+
+/// Route a transaction executes along, keying its `RouteExecution` in the
+/// route executor
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub enum SelectedRoute {
+    /// One swap through the registered venue with this program
+    Venue { program_id: Pubkey },
+    /// Chained or split route across venues, bound to its hops and split
+    /// weights by the route executor's hash of them
+    Route { route_hash: [u8; 32] },
+}
+
+impl SelectedRoute {
+    /// Last seed of the route's `RouteExecution` PDA
+    pub fn route_execution_seed(&self) -> &[u8] {
+        match self {
+            SelectedRoute::Venue { program_id } => program_id.as_ref(),
+            SelectedRoute::Route { .. } => b"route",
+        }
+    }
+}
+
+// Batch structure (mirrored from batch coordinator for cross-program compatibility)
+#[account]
+#[derive(InitSpace)]
+pub struct Batch {
+    pub id: [u8; 32],
+    #[max_len(10)]
+    pub transactions: Vec<[u8; 32]>, // transaction IDs (max 10)
+    pub transaction_count: u32,
+    pub total_value: u64, // in lamports
+    pub status: BatchStatus,
+    pub created_at: i64,
+    pub executed_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub execution_time_ms: Option<u32>,
+    pub batch_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub enum BatchStatus {
+    Pending,
+    Processing,
+    Completed,
+    Failed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct StatusTransition {
+    pub status: TransactionStatus,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub signer: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactionView {
+    pub id: [u8; 32],
+    pub owner: Pubkey,
+    pub input_token: Pubkey,
+    pub output_token: Pubkey,
+    pub input_amount: u64,
+    pub min_output_amount: u64,
+    pub output_amount: u64,
+    pub risk_score: u8,
+    pub selected_route: Option<SelectedRoute>,
+    pub status: TransactionStatus,
+    pub proof_hash: [u8; 32],
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub created_slot: u64,
+    pub last_updated_slot: u64,
+    pub completed_slot: Option<u64>,
+    pub timeline: Vec<StatusTransition>,
+    pub batch_id: Option<[u8; 32]>,
+    pub proof_account: Pubkey, // ProofOfRoute PDA in the proof verifier
+    pub route_execution_account: Option<Pubkey>, // RouteExecution PDA of the selected route
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AccountSummary {
+    pub owner: Pubkey,
+    pub total_transactions: u64,
+    pub total_savings: u64,
+    pub mev_attacks_blocked: u32,
+    pub settings: ProtectionSettings,
+    pub mint_stats: Vec<MintStats>,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub submission_deposit: u64,
+    pub transaction_expiry: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeepersUpdated {
+    pub keepers: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RollupConfigUpdated {
+    pub challenge_window_slots: u64,
+    pub committer_bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RollupValidatorUpdated {
+    pub validator: Pubkey,
+    pub registered: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositSettled {
+    pub transaction_id: [u8; 32],
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub refunded: bool,
+    pub timestamp: i64,
 }
 
 #[event]
@@ -397,6 +1587,7 @@ pub struct TransactionSubmitted {
     pub input_token: Pubkey,
     pub output_token: Pubkey,
     pub input_amount: u64,
+    pub min_output_amount: u64,
     pub timestamp: i64,
 }
 
@@ -409,6 +1600,16 @@ pub struct TransactionCompleted {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct TransactionStatusChanged {
+    pub transaction_id: [u8; 32],
+    pub previous_status: TransactionStatus,
+    pub new_status: TransactionStatus,
+    pub signer: Pubkey,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct MEVDetected {
     pub transaction_id: [u8; 32],
@@ -425,6 +1626,13 @@ pub struct SettingsUpdated {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct ExecutionGuardUpdated {
+    pub owner: Pubkey,
+    pub guard: ExecutionGuard,
+    pub timestamp: i64,
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("Invalid transaction ID")]
@@ -437,4 +1645,68 @@ pub enum ErrorCode {
     TransactionNotFound,
     #[msg("Unauthorized access")]
     Unauthorized,
+    #[msg("Invalid transaction status transition")]
+    InvalidStatusTransition,
+    #[msg("Invalid protocol configuration")]
+    InvalidConfig,
+    #[msg("Transaction cannot be cancelled in its current status")]
+    TransactionNotCancellable,
+    #[msg("Transaction has not expired")]
+    TransactionNotExpired,
+    #[msg("Invalid session ID")]
+    InvalidSessionId,
+    #[msg("Session is not active")]
+    SessionNotActive,
+    #[msg("Session has expired")]
+    SessionExpired,
+    #[msg("Rollup execution failed")]
+    ExecutionFailed,
+    #[msg("Invalid instruction data")]
+    InvalidInstructionData,
+    #[msg("Invalid delegation account")]
+    InvalidDelegationAccount,
+    #[msg("Rollup session op limit reached")]
+    SessionOpLimitReached,
+    #[msg("Invalid op inclusion proof")]
+    InvalidInclusionProof,
+    #[msg("Commit is not signed by a registered rollup validator")]
+    UnknownRollupValidator,
+    #[msg("Invalid commit signature")]
+    InvalidCommitSignature,
+    #[msg("Session has not expired")]
+    SessionNotExpired,
+    #[msg("Transaction is delegated to an ephemeral rollup")]
+    TransactionDelegated,
+    #[msg("Signer is not authorized for this rollup session")]
+    UnauthorizedSessionAccess,
+    #[msg("Session is not pending finalization")]
+    SessionNotPendingFinalization,
+    #[msg("Challenge window is still open")]
+    ChallengeWindowOpen,
+    #[msg("Challenge window has closed")]
+    ChallengeWindowClosed,
+    #[msg("Re-executed op matches the committed result")]
+    ChallengeRejected,
+    #[msg("Invalid batch ID")]
+    InvalidBatchId,
+    #[msg("Every batch member must simulate successfully before commit")]
+    BatchSessionIncomplete,
+    #[msg("Invalid session budget")]
+    InvalidSessionBudget,
+    #[msg("Session resource budget exceeded")]
+    SessionBudgetExceeded,
+    #[msg("Output chunk out of order or does not match the committed output")]
+    InvalidOutputChunk,
+    #[msg("Rollback note too long")]
+    RollbackNoteTooLong,
+    #[msg("Execution guard bounds are out of range")]
+    InvalidExecutionGuard,
+    #[msg("Op reserves do not match the pool's token vaults")]
+    ReserveMismatch,
+    #[msg("Transaction has not completed")]
+    TransactionNotCompleted,
+    #[msg("Output's session is still open")]
+    SessionStillOpen,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
 }
@@ -1,4 +1,19 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::system_program;
+use anchor_spl::token::TokenAccount;
+use solana_program::compute_units::sol_remaining_compute_units;
+use solana_sha256_hasher::hashv;
+use zephyra_quote::pools::{raydium_amm::AmmInfo, whirlpool::Whirlpool};
+use zephyra_quote::{constant_product, Fee};
+
+use crate::delegation::{schedule_commit, MAGIC_CONTEXT_ID, MAGIC_PROGRAM_ID};
+use crate::{
+    record_status_change, Batch, BatchStatus, ErrorCode, ProtocolConfig, RollupValidator,
+    SelectedRoute, Transaction, TransactionStatus, BATCH_COORDINATOR_PROGRAM_ID,
+};
 
 /// MagicBlock Ephemeral Rollups Integration Module
 /// Official integration with MagicBlock API
@@ -8,28 +23,51 @@ use anchor_lang::prelude::*;
 /// - Asia: https://asia.magicblock.app
 /// - EU: https://eu.magicblock.app  
 /// - US: https://us.magicblock.app
-pub mod magicblock_integration {
+///
+/// Handlers are exposed as instructions by the `#[program]` module in `lib.rs`.
+pub mod handlers {
     use super::*;
 
     /// Initialize ephemeral rollup session
     pub fn init_rollup_session(
         ctx: Context<InitRollupSession>,
         transaction_id: [u8; 32],
+        executor: Option<Pubkey>,
+        budget: Option<SessionBudget>,
     ) -> Result<RollupSessionId> {
         let session_account = &mut ctx.accounts.session_account;
         let clock = Clock::get()?;
 
-        // Generate unique session ID
-        let session_id = generate_session_id(&transaction_id, clock.unix_timestamp);
+        let session_id = start_session(
+            session_account,
+            transaction_id,
+            ctx.accounts.payer.key(),
+            executor,
+            budget.unwrap_or_default(),
+            &clock,
+        )?;
+
+        // Mark the linked transaction as provisionally simulating in this session
+        let transaction_account = &mut ctx.accounts.transaction_account;
+        require!(
+            !matches!(
+                transaction_account.status,
+                TransactionStatus::Executing
+                    | TransactionStatus::Completed
+                    | TransactionStatus::Failed
+            ),
+            ErrorCode::InvalidStatusTransition
+        );
 
-        // Initialize session account
-        session_account.id = session_id;
-        session_account.transaction_id = transaction_id;
-        session_account.status = RollupStatus::Active;
-        session_account.created_at = clock.unix_timestamp;
-        session_account.expires_at = clock.unix_timestamp + ROLLUP_SESSION_TIMEOUT;
-        session_account.instructions_executed = 0;
-        session_account.state_hash = [0u8; 32]; // Will be updated as instructions are executed
+        session_account.linked_transaction = Some(transaction_account.key());
+        session_account.transaction_snapshot =
+            Some(TransactionSnapshot::capture(transaction_account));
+        record_status_change(
+            transaction_account,
+            TransactionStatus::Simulating,
+            ctx.accounts.payer.key(),
+            &clock,
+        );
 
         emit!(RollupSessionInitialized {
             session_id,
@@ -40,6 +78,121 @@ pub mod magicblock_integration {
         Ok(session_id)
     }
 
+    /// Initialize an ephemeral rollup session covering a whole batch
+    pub fn init_batch_rollup_session(
+        ctx: Context<InitBatchRollupSession>,
+        batch_id: [u8; 32],
+        executor: Option<Pubkey>,
+        budget: Option<SessionBudget>,
+    ) -> Result<RollupSessionId> {
+        let batch = {
+            let data = ctx.accounts.batch_account.try_borrow_data()?;
+            Batch::try_deserialize(&mut &data[..])?
+        };
+        require!(batch.id == batch_id, ErrorCode::InvalidBatchId);
+        require!(
+            matches!(batch.status, BatchStatus::Pending | BatchStatus::Processing)
+                && !batch.transactions.is_empty(),
+            ErrorCode::InvalidBatchId
+        );
+
+        // Only the batch's authority opens a session for it
+        let (expected_batch, _) = Pubkey::find_program_address(
+            &[
+                b"batch",
+                ctx.accounts.payer.key().as_ref(),
+                &batch.created_at.to_le_bytes(),
+            ],
+            &BATCH_COORDINATOR_PROGRAM_ID,
+        );
+        require_keys_eq!(
+            ctx.accounts.batch_account.key(),
+            expected_batch,
+            ErrorCode::Unauthorized
+        );
+
+        // Member transactions follow as remaining accounts, in batch order,
+        // then the signing owners of members the payer does not own
+        require!(
+            ctx.remaining_accounts.len() >= batch.transactions.len(),
+            ErrorCode::InvalidTransactionId
+        );
+        let (member_infos, owner_infos) =
+            ctx.remaining_accounts.split_at(batch.transactions.len());
+        let payer = ctx.accounts.payer.key();
+        let is_keeper = ctx.accounts.config.is_keeper(&payer);
+
+        let session_account = &mut ctx.accounts.session_account;
+        let clock = Clock::get()?;
+
+        let session_id = start_session(
+            session_account,
+            batch_id,
+            ctx.accounts.payer.key(),
+            executor,
+            budget.unwrap_or_default(),
+            &clock,
+        )?;
+        session_account.batch_id = Some(batch_id);
+
+        // Take each member's amounts from its transaction and mark it simulating
+        for (transaction_id, transaction_info) in batch.transactions.iter().zip(member_infos) {
+            let member = update_transaction(transaction_info, |transaction_account| {
+                require!(
+                    transaction_account.id == *transaction_id,
+                    ErrorCode::InvalidTransactionId
+                );
+                // A keeper, or the member's owner, consents to its simulation
+                let owner = transaction_account.owner;
+                require!(
+                    is_keeper
+                        || owner == payer
+                        || owner_infos
+                            .iter()
+                            .any(|info| info.is_signer && info.key() == owner),
+                    ErrorCode::Unauthorized
+                );
+                require!(
+                    !matches!(
+                        transaction_account.status,
+                        TransactionStatus::Executing
+                            | TransactionStatus::Completed
+                            | TransactionStatus::Failed
+                    ),
+                    ErrorCode::InvalidStatusTransition
+                );
+
+                let member = BatchMember {
+                    transaction_id: *transaction_id,
+                    transaction: transaction_info.key(),
+                    input_token: transaction_account.input_token,
+                    output_token: transaction_account.output_token,
+                    input_amount: transaction_account.input_amount,
+                    min_output_amount: transaction_account.min_output_amount,
+                    snapshot: Some(TransactionSnapshot::capture(transaction_account)),
+                    result: None,
+                };
+                record_status_change(
+                    transaction_account,
+                    TransactionStatus::Simulating,
+                    ctx.accounts.payer.key(),
+                    &clock,
+                );
+                Ok(member)
+            })?;
+            session_account.members.push(member);
+        }
+
+        emit!(BatchRollupSessionInitialized {
+            session_id,
+            batch_id,
+            member_count: session_account.members.len() as u8,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(session_id)
+    }
+
     /// Execute instruction in rollup
     pub fn execute_in_rollup(
         ctx: Context<ExecuteInRollup>,
@@ -61,30 +214,77 @@ pub mod magicblock_integration {
             clock.unix_timestamp < session_account.expires_at,
             ErrorCode::SessionExpired
         );
+        require!(
+            session_account.is_operator(&ctx.accounts.authority.key()),
+            ErrorCode::UnauthorizedSessionAccess
+        );
+        // Refuse an op over either limit before it touches the session state
+        require!(
+            session_account.instructions_executed < MAX_SESSION_OPS,
+            ErrorCode::SessionOpLimitReached
+        );
+        require!(
+            session_account.usage.ops < session_account.budget.max_ops,
+            ErrorCode::SessionBudgetExceeded
+        );
 
-        // Simulate instruction execution in rollup
-        let execution_result = simulate_rollup_execution(&instruction_data)?;
-        
-        // Update session state
-        session_account.instructions_executed += 1;
+        // Decode and apply the operation to the session state
+        let op = RollupOp::try_from_slice(&instruction_data)
+            .map_err(|_| error!(ErrorCode::InvalidInstructionData))?;
+        check_member_reserves(&session_account.members, &op, ctx.remaining_accounts)?;
+        let op_index = session_account.instructions_executed;
+        let session: &mut RollupSession = session_account;
+        let remaining_before = sol_remaining_compute_units();
+        let result = apply_rollup_op(&mut session.state, &mut session.members, op_index, &op)?;
+        let compute_units = remaining_before.saturating_sub(sol_remaining_compute_units());
+
+        // Charge the compute units the op consumed against the session's budget
+        session
+            .usage
+            .charge(&session.budget, compute_units, result.output_len)?;
+
+        let output_hash = result.output_hash;
+        let output_len = result.output_len;
+
+        // Record the output's commitment so readers can fetch it in chunks
+        if let Some(output_account) = &mut ctx.accounts.output_account {
+            output_account.session = session_account.key();
+            output_account.session_id = session_account.id;
+            output_account.payer = ctx.accounts.authority.key();
+            output_account.op_index = op_index;
+            output_account.output_hash = output_hash;
+            output_account.output_len = output_len;
+            output_account.chunk_count = output_len.div_ceil(OUTPUT_CHUNK_LEN as u32) as u16;
+            output_account.chunks_written = 0;
+            output_account.written_hash = [0u8; 32];
+            output_account.bump = ctx.bumps.output_account.unwrap_or_default();
+        }
+
+        // Extend the hash chain and the op accumulator
+        let index = session_account.instructions_executed;
+        let result_bytes = result.try_to_vec()?;
         session_account.state_hash = update_state_hash(
             session_account.state_hash,
             &instruction_data,
-            execution_result.success,
+            &result_bytes,
+        );
+        let leaf = op_leaf(index, &instruction_data, &result_bytes);
+        accumulator_insert(&mut session_account.op_frontier, index, leaf);
+        session_account.instructions_executed += 1;
+        session_account.op_root = accumulator_root(
+            &session_account.op_frontier,
+            session_account.instructions_executed,
         );
-
-        let result = RollupResult {
-            success: execution_result.success,
-            output_data: execution_result.output_data,
-            execution_time_ms: execution_result.execution_time_ms,
-            gas_used: execution_result.gas_used,
-        };
 
         emit!(InstructionExecuted {
             session_id,
-            instruction_data: instruction_data.clone(),
-            success: execution_result.success,
-            execution_time_ms: execution_result.execution_time_ms,
+            op_index,
+            op_hash: hashv(&[&instruction_data]).to_bytes(),
+            op_len: instruction_data.len() as u32,
+            output_hash,
+            output_len,
+            success: result.success,
+            compute_units,
             timestamp: clock.unix_timestamp,
         });
 
@@ -92,8 +292,8 @@ pub mod magicblock_integration {
     }
 
     /// Commit rollup state to mainnet
-    pub fn commit_rollup(
-        ctx: Context<CommitRollup>,
+    pub fn commit_rollup<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CommitRollup<'info>>,
         session_id: RollupSessionId,
     ) -> Result<CommitProof> {
         let session_account = &mut ctx.accounts.session_account;
@@ -107,34 +307,121 @@ pub mod magicblock_integration {
             session_account.status == RollupStatus::Active,
             ErrorCode::SessionNotActive
         );
+        require!(
+            clock.unix_timestamp < session_account.expires_at,
+            ErrorCode::SessionExpired
+        );
+        require!(
+            session_account.is_operator(&ctx.accounts.authority.key()),
+            ErrorCode::UnauthorizedSessionAccess
+        );
+
+        // Batch sessions commit atomically: every member must have simulated successfully
+        require!(
+            session_account
+                .members
+                .iter()
+                .all(|member| member.result.as_ref().is_some_and(|result| result.success)),
+            ErrorCode::BatchSessionIncomplete
+        );
 
-        // Generate commit proof
-        let commit_proof = generate_commit_proof(
+        // Generate commit proof, signed by a registered rollup validator
+        let hash = commit_hash(
+            &session_id,
             &session_account.state_hash,
+            &session_account.op_root,
             session_account.instructions_executed,
-            clock.unix_timestamp,
         );
+        let validator = ctx.accounts.rollup_validator.validator;
+        let signature = verify_commit_signature(
+            &ctx.accounts.instructions_sysvar.to_account_info(),
+            &validator,
+            &hash,
+        )?;
+        let commit_proof = CommitProof {
+            hash,
+            state_hash: session_account.state_hash,
+            op_root: session_account.op_root,
+            instructions_executed: session_account.instructions_executed,
+            timestamp: clock.unix_timestamp,
+            signature,
+            validator,
+        };
 
-        // Mark session as committed
-        session_account.status = RollupStatus::Committed;
+        // Mark session as committed, or hold it open for challenges first. The
+        // validator vouching for the commit bonds it.
+        let config = &ctx.accounts.config;
         session_account.committed_at = Some(clock.unix_timestamp);
+        session_account.committer = Some(validator);
+        if config.challenge_window_slots > 0 {
+            session_account.status = RollupStatus::PendingFinalization;
+            session_account.finalizes_at_slot =
+                Some(clock.slot.saturating_add(config.challenge_window_slots));
+            session_account.bond = config.committer_bond;
+
+            if config.committer_bond > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.validator.to_account_info(),
+                            to: session_account.to_account_info(),
+                        },
+                    ),
+                    config.committer_bond,
+                )?;
+            }
+        } else {
+            session_account.status = RollupStatus::Committed;
+            apply_member_outputs(&session_account.members, ctx.remaining_accounts)?;
+        }
+
+        // Inside the ephemeral rollup, commit the session (and its transaction)
+        // back to the base layer and release the delegation
+        if let (Some(payer), Some(magic_context), Some(magic_program)) = (
+            &ctx.accounts.payer,
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        ) {
+            session_account.exit(&crate::ID)?;
+
+            let mut accounts = vec![session_account.to_account_info()];
+            if let Some(transaction_account) = &ctx.accounts.transaction_account {
+                accounts.push(transaction_account.to_account_info());
+            }
+            accounts.extend(ctx.remaining_accounts.iter().cloned());
+
+            schedule_commit(
+                &payer.to_account_info(),
+                &accounts,
+                &magic_context.to_account_info(),
+                &magic_program.to_account_info(),
+                true,
+            )?;
+        }
 
         emit!(RollupCommitted {
             session_id,
             state_hash: session_account.state_hash,
+            op_root: session_account.op_root,
             instructions_executed: session_account.instructions_executed,
             commit_proof: commit_proof.hash,
+            validator,
+            batch_id: session_account.batch_id,
+            budget: session_account.budget.clone(),
+            usage: session_account.usage.clone(),
             timestamp: clock.unix_timestamp,
         });
 
         Ok(commit_proof)
     }
 
-    /// Rollback rollup state
+    /// Rollback rollup state and restore the linked transaction
     pub fn rollback_rollup(
         ctx: Context<RollbackRollup>,
         session_id: RollupSessionId,
-        reason: String,
+        reason: RollbackReason,
+        note: Option<String>,
     ) -> Result<()> {
         let session_account = &mut ctx.accounts.session_account;
         let clock = Clock::get()?;
@@ -147,19 +434,545 @@ pub mod magicblock_integration {
             session_account.status == RollupStatus::Active,
             ErrorCode::SessionNotActive
         );
+        require!(
+            session_account.can_abort(&ctx.accounts.authority.key()),
+            ErrorCode::UnauthorizedSessionAccess
+        );
+        require!(
+            note.as_ref()
+                .map_or(true, |note| note.len() <= MAX_ROLLBACK_NOTE_LEN),
+            ErrorCode::RollbackNoteTooLong
+        );
 
-        // Mark session as rolled back
-        session_account.status = RollupStatus::RolledBack;
-        session_account.rolled_back_at = Some(clock.unix_timestamp);
+        let transaction_restored = roll_back_session(
+            session_account,
+            ctx.accounts.transaction_account.as_deref(),
+            ctx.remaining_accounts,
+            reason,
+            ctx.accounts.authority.key(),
+            &clock,
+        )?;
 
         emit!(RollupRolledBack {
             session_id,
             reason,
+            note,
+            transaction_restored,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
+
+    /// Finalize a pending commit after its challenge window and return the bond.
+    /// A batch session's members take their simulated outputs.
+    pub fn finalize_rollup(
+        ctx: Context<FinalizeRollup>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        let session_account = &mut ctx.accounts.session_account;
+        let clock = Clock::get()?;
+
+        require!(
+            session_account.status == RollupStatus::PendingFinalization,
+            ErrorCode::SessionNotPendingFinalization
+        );
+        require!(
+            session_account
+                .finalizes_at_slot
+                .is_some_and(|slot| clock.slot >= slot),
+            ErrorCode::ChallengeWindowOpen
+        );
+
+        session_account.status = RollupStatus::Committed;
+        apply_member_outputs(&session_account.members, ctx.remaining_accounts)?;
+        let bond = release_bond(session_account, &ctx.accounts.committer.to_account_info())?;
+
+        emit!(RollupFinalized {
+            session_id: session_account.id,
+            transaction_id,
+            committer: ctx.accounts.committer.key(),
+            bond_returned: bond,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Roll back a pending commit whose op re-executes to a different result,
+    /// slashing the committer's bond to the challenger
+    pub fn challenge_rollup(
+        ctx: Context<ChallengeRollup>,
+        transaction_id: [u8; 32],
+        index: u32,
+        op: RollupOp,
+        claimed_result: RollupResult,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let session_account = &mut ctx.accounts.session_account;
+        let clock = Clock::get()?;
+
+        require!(
+            session_account.status == RollupStatus::PendingFinalization,
+            ErrorCode::SessionNotPendingFinalization
+        );
+        require!(
+            session_account
+                .finalizes_at_slot
+                .is_some_and(|slot| clock.slot < slot),
+            ErrorCode::ChallengeWindowClosed
+        );
+
+        // The disputed op and result must be part of the committed accumulator
+        let claimed_bytes = claimed_result.try_to_vec()?;
+        require!(
+            index < session_account.instructions_executed
+                && proof.len() == ACCUMULATOR_DEPTH,
+            ErrorCode::InvalidInclusionProof
+        );
+        let leaf = op_leaf(index, &op.try_to_vec()?, &claimed_bytes);
+        require!(
+            merkle_root_from_proof(leaf, index, &proof) == session_account.op_root,
+            ErrorCode::InvalidInclusionProof
+        );
+
+        // Ops are pure over their arguments, so re-execution needs no prior state
+        let recomputed = apply_rollup_op(
+            &mut RollupState::default(),
+            &mut session_account.members.clone(),
+            index,
+            &op,
+        )?;
+        require!(
+            recomputed.try_to_vec()? != claimed_bytes,
+            ErrorCode::ChallengeRejected
+        );
+
+        let transaction_restored = roll_back_session(
+            session_account,
+            ctx.accounts.transaction_account.as_deref(),
+            ctx.remaining_accounts,
+            RollbackReason::FraudProven,
+            ctx.accounts.challenger.key(),
+            &clock,
+        )?;
+        let slashed = release_bond(session_account, &ctx.accounts.challenger.to_account_info())?;
+
+        emit!(RollupChallenged {
+            session_id: session_account.id,
+            transaction_id,
+            op_index: index,
+            challenger: ctx.accounts.challenger.key(),
+            bond_slashed: slashed,
+            transaction_restored,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Expire a stale session and close finished sessions to their payer
+    pub fn expire_rollup_session(
+        ctx: Context<ExpireRollupSession>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        let session_account = &mut ctx.accounts.session_account;
+        let clock = Clock::get()?;
+
+        require!(
+            session_account.status != RollupStatus::PendingFinalization,
+            ErrorCode::ChallengeWindowOpen
+        );
+        if session_account.status == RollupStatus::Active {
+            require!(
+                clock.unix_timestamp >= session_account.expires_at,
+                ErrorCode::SessionNotExpired
+            );
+            session_account.status = RollupStatus::Expired;
+        }
+
+        // Restore the linked transaction, unless the session committed, a
+        // rollback already restored it or the transaction has moved on since
+        if let (Some(linked_transaction), Some(snapshot)) = (
+            session_account.linked_transaction,
+            session_account.transaction_snapshot.clone(),
+        ) {
+            if session_account.status != RollupStatus::Committed {
+                let transaction_info = ctx
+                    .accounts
+                    .transaction_account
+                    .as_ref()
+                    .ok_or(ErrorCode::InvalidTransactionId)?;
+                require_keys_eq!(
+                    transaction_info.key(),
+                    linked_transaction,
+                    ErrorCode::InvalidTransactionId
+                );
+
+                // A cancelled or expired transaction has nothing left to undo
+                if !transaction_info.data_is_empty() {
+                    require_keys_eq!(
+                        *transaction_info.owner,
+                        crate::ID,
+                        ErrorCode::TransactionDelegated
+                    );
+
+                    let mut data = transaction_info.try_borrow_mut_data()?;
+                    let mut transaction_account = Transaction::try_deserialize(&mut &data[..])?;
+                    if snapshot.restore(&mut transaction_account, ctx.accounts.cranker.key(), &clock)
+                    {
+                        transaction_account.try_serialize(&mut &mut data[..])?;
+                    }
+                }
+            }
+        }
+
+        // Likewise for a batch session's members, supplied as remaining accounts
+        if session_account.status != RollupStatus::Committed
+            && session_account.members.iter().any(|member| member.snapshot.is_some())
+        {
+            require!(
+                ctx.remaining_accounts.len() == session_account.members.len(),
+                ErrorCode::InvalidTransactionId
+            );
+            let cranker = ctx.accounts.cranker.key();
+            for (member, transaction_info) in
+                session_account.members.iter().zip(ctx.remaining_accounts)
+            {
+                require_keys_eq!(
+                    transaction_info.key(),
+                    member.transaction,
+                    ErrorCode::InvalidTransactionId
+                );
+                let Some(snapshot) = &member.snapshot else {
+                    continue;
+                };
+                if !transaction_info.data_is_empty() {
+                    update_transaction(transaction_info, |transaction_account| {
+                        Ok(snapshot.restore(transaction_account, cranker, &clock))
+                    })?;
+                }
+            }
+        }
+
+        emit!(RollupSessionClosed {
+            session_id: session_account.id,
+            transaction_id,
+            status: session_account.status.clone(),
+            payer: session_account.payer,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Write one chunk of an op's output, in order
+    pub fn write_rollup_output(
+        ctx: Context<WriteRollupOutput>,
+        session_id: RollupSessionId,
+        op_index: u32,
+        chunk_index: u16,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let session_account = &ctx.accounts.session_account;
+        let output_account = &mut ctx.accounts.output_account;
+        let clock = Clock::get()?;
+
+        require!(
+            session_account.id == session_id,
+            ErrorCode::InvalidSessionId
+        );
+        require!(
+            session_account.is_operator(&ctx.accounts.authority.key()),
+            ErrorCode::UnauthorizedSessionAccess
+        );
+
+        // Chunks are written in order, full-sized except for the last one
+        require!(
+            chunk_index == output_account.chunks_written
+                && chunk_index < output_account.chunk_count,
+            ErrorCode::InvalidOutputChunk
+        );
+        let offset = chunk_index as usize * OUTPUT_CHUNK_LEN;
+        let expected_len = (output_account.output_len as usize - offset).min(OUTPUT_CHUNK_LEN);
+        require!(data.len() == expected_len, ErrorCode::InvalidOutputChunk);
+
+        let written_hash = hash_output_chunk(&output_account.written_hash, &data);
+        let chunks_written = chunk_index + 1;
+        if chunks_written == output_account.chunk_count {
+            require!(
+                written_hash == output_account.output_hash,
+                ErrorCode::InvalidOutputChunk
+            );
+        }
+
+        output_account.written_hash = written_hash;
+        output_account.chunks_written = chunks_written;
+
+        let chunk_account = &mut ctx.accounts.chunk_account;
+        chunk_account.output = output_account.key();
+        chunk_account.chunk_index = chunk_index;
+        chunk_account.data = data;
+        chunk_account.bump = ctx.bumps.chunk_account;
+
+        emit!(RollupOutputChunkWritten {
+            session_id,
+            op_index,
+            chunk_index,
+            chunk_hash: hashv(&[&chunk_account.data]).to_bytes(),
+            chunk_len: chunk_account.data.len() as u32,
+            complete: output_account.is_complete(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close an op's output and its chunks, passed as remaining accounts in
+    /// chunk order, to their payer once the session is closed or re-opened
+    pub fn close_rollup_output<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseRollupOutput<'info>>,
+    ) -> Result<()> {
+        let output_account = &ctx.accounts.output_account;
+        let session_info = &ctx.accounts.session_account;
+
+        let session_open = !session_info.data_is_empty()
+            && RollupSession::try_deserialize(&mut &session_info.try_borrow_data()?[..])
+                .is_ok_and(|session| session.id == output_account.session_id);
+        require!(!session_open, ErrorCode::SessionStillOpen);
+
+        require!(
+            ctx.remaining_accounts.len() == output_account.chunks_written as usize,
+            ErrorCode::InvalidOutputChunk
+        );
+        let payer = ctx.accounts.payer.to_account_info();
+        for (chunk_index, chunk_info) in ctx.remaining_accounts.iter().enumerate() {
+            let chunk_account = Account::<RollupOutputChunk>::try_from(chunk_info)?;
+            require!(
+                chunk_account.output == output_account.key()
+                    && chunk_account.chunk_index as usize == chunk_index,
+                ErrorCode::InvalidOutputChunk
+            );
+            chunk_account.close(payer.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Verify that an op and its result were executed in a rollup session
+    pub fn verify_rollup_op(
+        ctx: Context<VerifyRollupOp>,
+        session_id: RollupSessionId,
+        index: u32,
+        op: RollupOp,
+        result: RollupResult,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let session_account = &ctx.accounts.session_account;
+
+        require!(
+            session_account.id == session_id,
+            ErrorCode::InvalidSessionId
+        );
+        require!(
+            index < session_account.instructions_executed,
+            ErrorCode::InvalidInclusionProof
+        );
+        require!(
+            proof.len() == ACCUMULATOR_DEPTH,
+            ErrorCode::InvalidInclusionProof
+        );
+
+        let leaf = op_leaf(index, &op.try_to_vec()?, &result.try_to_vec()?);
+        require!(
+            merkle_root_from_proof(leaf, index, &proof) == session_account.op_root,
+            ErrorCode::InvalidInclusionProof
+        );
+
+        Ok(())
+    }
+}
+
+/// Mark a session rolled back for `reason` and put its linked transaction, or
+/// its batch members' transactions, back the way they were before the session.
+/// These must then be supplied, members as `member_accounts` in member order.
+fn roll_back_session(
+    session_account: &mut RollupSession,
+    transaction_account: Option<&AccountInfo>,
+    member_accounts: &[AccountInfo],
+    reason: RollbackReason,
+    signer: Pubkey,
+    clock: &Clock,
+) -> Result<bool> {
+    let mut transaction_restored = false;
+    if let Some(linked_transaction) = session_account.linked_transaction {
+        let transaction_info = transaction_account.ok_or(ErrorCode::InvalidTransactionId)?;
+        require_keys_eq!(
+            transaction_info.key(),
+            linked_transaction,
+            ErrorCode::InvalidTransactionId
+        );
+
+        // A cancelled or expired transaction has nothing left to undo
+        if let Some(snapshot) = session_account.transaction_snapshot.take() {
+            if !transaction_info.data_is_empty() {
+                transaction_restored = update_transaction(transaction_info, |transaction| {
+                    Ok(snapshot.restore(transaction, signer, clock))
+                })?;
+            }
+        }
+    }
+
+    if !session_account.members.is_empty() {
+        require!(
+            member_accounts.len() == session_account.members.len(),
+            ErrorCode::InvalidTransactionId
+        );
+    }
+    for (member, transaction_info) in session_account.members.iter_mut().zip(member_accounts) {
+        require_keys_eq!(
+            transaction_info.key(),
+            member.transaction,
+            ErrorCode::InvalidTransactionId
+        );
+        if let Some(snapshot) = member.snapshot.take() {
+            if !transaction_info.data_is_empty() {
+                transaction_restored |= update_transaction(transaction_info, |transaction_account| {
+                    Ok(snapshot.restore(transaction_account, signer, clock))
+                })?;
+            }
+        }
+    }
+
+    session_account.status = RollupStatus::RolledBack;
+    session_account.rolled_back_at = Some(clock.unix_timestamp);
+    session_account.rollback_reason = Some(reason);
+    Ok(transaction_restored)
+}
+
+/// Give a committed batch session's members, supplied as remaining accounts in
+/// member order, their simulated outputs. Members cancelled since are skipped.
+fn apply_member_outputs(members: &[BatchMember], member_accounts: &[AccountInfo]) -> Result<()> {
+    require!(
+        member_accounts.len() == members.len(),
+        ErrorCode::InvalidTransactionId
+    );
+    for (member, transaction_info) in members.iter().zip(member_accounts) {
+        require_keys_eq!(
+            transaction_info.key(),
+            member.transaction,
+            ErrorCode::InvalidTransactionId
+        );
+        if transaction_info.data_is_empty() {
+            continue;
+        }
+        let output_amount = member.result.as_ref().map_or(0, |result| result.output_amount);
+        update_transaction(transaction_info, |transaction_account| {
+            transaction_account.output_amount = output_amount;
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+/// Deserialize a transaction passed as an unchecked account, apply `update`
+/// and write it back
+fn update_transaction<T>(
+    transaction_info: &AccountInfo,
+    update: impl FnOnce(&mut Transaction) -> Result<T>,
+) -> Result<T> {
+    require_keys_eq!(
+        *transaction_info.owner,
+        crate::ID,
+        ErrorCode::TransactionDelegated
+    );
+
+    let mut data = transaction_info.try_borrow_mut_data()?;
+    let mut transaction_account = Transaction::try_deserialize(&mut &data[..])?;
+    let result = update(&mut transaction_account)?;
+    transaction_account.try_serialize(&mut &mut data[..])?;
+    Ok(result)
+}
+
+/// Check a member simulation's reserves against the pool's token vaults,
+/// supplied as remaining accounts: a Raydium AMM v4 pool or a Whirlpool, its
+/// input-side vault, then its output-side one
+fn check_member_reserves(
+    members: &[BatchMember],
+    op: &RollupOp,
+    pool_accounts: &[AccountInfo],
+) -> Result<()> {
+    let RollupOp::SimulateMember {
+        member_index,
+        reserve_in,
+        reserve_out,
+        ..
+    } = *op
+    else {
+        return Ok(());
+    };
+    let member = members
+        .get(member_index as usize)
+        .ok_or(ErrorCode::InvalidInstructionData)?;
+    let [pool_info, vault_in, vault_out] = pool_accounts else {
+        return err!(ErrorCode::ReserveMismatch);
+    };
+
+    // A member routed to a single venue is simulated against that venue's pool
+    if let Some(Some(SelectedRoute::Venue { program_id })) =
+        member.snapshot.as_ref().map(|snapshot| snapshot.selected_route)
+    {
+        require_keys_eq!(*pool_info.owner, program_id, ErrorCode::ReserveMismatch);
+    }
+
+    // The vaults must be the ones the pool references
+    let pool_data = pool_info.try_borrow_data()?;
+    let invalid_pool = |_| error!(ErrorCode::ReserveMismatch);
+    let (vault_a, vault_b) = if *pool_info.owner == RAYDIUM_AMM_PROGRAM_ID {
+        let pool = AmmInfo::decode(&pool_data).map_err(invalid_pool)?;
+        (*pool.coin_vault(), *pool.pc_vault())
+    } else if *pool_info.owner == WHIRLPOOL_PROGRAM_ID {
+        let pool = Whirlpool::decode(&pool_data).map_err(invalid_pool)?;
+        (*pool.token_vault_a(), *pool.token_vault_b())
+    } else {
+        return err!(ErrorCode::ReserveMismatch);
+    };
+    let vaults = [vault_in.key().to_bytes(), vault_out.key().to_bytes()];
+    require!(
+        vaults == [vault_a, vault_b] || vaults == [vault_b, vault_a],
+        ErrorCode::ReserveMismatch
+    );
+
+    for (vault_info, mint, reserve) in [
+        (vault_in, member.input_token, reserve_in),
+        (vault_out, member.output_token, reserve_out),
+    ] {
+        require_keys_eq!(
+            *vault_info.owner,
+            anchor_spl::token::ID,
+            ErrorCode::ReserveMismatch
+        );
+        let vault = TokenAccount::try_deserialize(&mut &vault_info.try_borrow_data()?[..])?;
+        require!(
+            vault.mint == mint && vault.amount == reserve,
+            ErrorCode::ReserveMismatch
+        );
+    }
+    Ok(())
+}
+
+/// Pay out a session's held committer bond to `recipient`
+fn release_bond<'info>(
+    session_account: &mut Account<'info, RollupSession>,
+    recipient: &AccountInfo<'info>,
+) -> Result<u64> {
+    let bond = session_account.bond;
+    if bond > 0 {
+        session_account.sub_lamports(bond)?;
+        recipient.add_lamports(bond)?;
+        session_account.bond = 0;
+    }
+    Ok(bond)
 }
 
 /// Generate unique session ID
@@ -187,104 +1000,358 @@ fn generate_session_id(transaction_id: &[u8; 32], timestamp: i64) -> RollupSessi
     id
 }
 
-/// Simulate rollup execution
-fn simulate_rollup_execution(instruction_data: &[u8]) -> Result<ExecutionResult> {
-    // Simplified simulation - in production this would:
-    // 1. Parse the instruction
-    // 2. Execute it in the rollup environment
-    // 3. Return the actual result
-    
-    let success = instruction_data.len() > 0; // Simple validation
-    let execution_time_ms = 10 + (instruction_data.len() as u32 % 50); // Simulate timing
-    
-    Ok(ExecutionResult {
-        success,
-        output_data: instruction_data.to_vec(),
-        execution_time_ms,
-        gas_used: instruction_data.len() as u64 * 1000,
+/// Reset a session account for a new session keyed by `transaction_id`
+fn start_session(
+    session_account: &mut RollupSession,
+    transaction_id: [u8; 32],
+    payer: Pubkey,
+    executor: Option<Pubkey>,
+    budget: SessionBudget,
+    clock: &Clock,
+) -> Result<RollupSessionId> {
+    require!(
+        budget.max_ops > 0
+            && budget.max_ops <= MAX_SESSION_OPS
+            && budget.max_compute_units > 0
+            && budget.max_output_bytes > 0,
+        ErrorCode::InvalidSessionBudget
+    );
+
+    // Generate unique session ID
+    let session_id = generate_session_id(&transaction_id, clock.unix_timestamp);
+
+    // Initialize session account
+    session_account.id = session_id;
+    session_account.transaction_id = transaction_id;
+    session_account.status = RollupStatus::Active;
+    session_account.created_at = clock.unix_timestamp;
+    session_account.expires_at = clock.unix_timestamp + ROLLUP_SESSION_TIMEOUT;
+    session_account.instructions_executed = 0;
+    session_account.state_hash = [0u8; 32]; // Will be updated as instructions are executed
+    session_account.committed_at = None;
+    session_account.rolled_back_at = None;
+    session_account.state = RollupState::default();
+    session_account.op_frontier = [[0u8; 32]; ACCUMULATOR_DEPTH];
+    session_account.op_root = accumulator_root(&session_account.op_frontier, 0);
+    session_account.payer = payer;
+    session_account.creator = payer;
+    session_account.executor = executor;
+    session_account.committer = None;
+    session_account.finalizes_at_slot = None;
+    session_account.bond = 0;
+    session_account.linked_transaction = None;
+    session_account.transaction_snapshot = None;
+    session_account.rollback_reason = None;
+    session_account.batch_id = None;
+    session_account.members = Vec::new();
+    session_account.budget = budget;
+    session_account.usage = SessionUsage::default();
+
+    Ok(session_id)
+}
+
+/// Apply a decoded operation to the session state
+fn apply_rollup_op(
+    state: &mut RollupState,
+    members: &mut [BatchMember],
+    op_index: u32,
+    op: &RollupOp,
+) -> Result<RollupResult> {
+    let (success, output) = match *op {
+        RollupOp::Quote {
+            input_amount,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+        } => {
+            let quote = quote_constant_product(input_amount, reserve_in, reserve_out, fee_bps)?;
+            state.quoted_output = quote.output_amount;
+            (true, quote.output_amount.to_le_bytes().to_vec())
+        }
+        RollupOp::SimulateSwap {
+            input_amount,
+            min_output,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+        } => {
+            let quote = quote_constant_product(input_amount, reserve_in, reserve_out, fee_bps)?;
+            state.simulated_output = quote.output_amount;
+            state.price_impact_bps = quote.price_impact_bps;
+            (
+                quote.output_amount >= min_output,
+                quote.output_amount.to_le_bytes().to_vec(),
+            )
+        }
+        RollupOp::UpdateRisk {
+            risk_score,
+            mev_detected,
+        } => {
+            require!(risk_score <= 100, ErrorCode::InvalidRiskScore);
+            state.risk_score = risk_score;
+            state.mev_detected = mev_detected;
+            (true, (risk_score as u64).to_le_bytes().to_vec())
+        }
+        RollupOp::SetRoute {
+            route,
+            expected_output,
+        } => {
+            state.selected_route = Some(route);
+            state.expected_output = expected_output;
+            (true, expected_output.to_le_bytes().to_vec())
+        }
+        RollupOp::SimulateMember {
+            member_index,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+        } => {
+            let member = members
+                .get_mut(member_index as usize)
+                .ok_or(ErrorCode::InvalidInstructionData)?;
+            let quote =
+                quote_constant_product(member.input_amount, reserve_in, reserve_out, fee_bps)?;
+            let success = quote.output_amount >= member.min_output_amount;
+            member.result = Some(MemberResult {
+                success,
+                output_amount: quote.output_amount,
+                price_impact_bps: quote.price_impact_bps,
+                op_index,
+            });
+            (success, quote.output_amount.to_le_bytes().to_vec())
+        }
+        RollupOp::QuoteLadder {
+            step_amount,
+            steps,
+            reserve_in,
+            reserve_out,
+            fee_bps,
+        } => {
+            require!(
+                steps > 0 && steps <= MAX_LADDER_STEPS,
+                ErrorCode::InvalidInstructionData
+            );
+            let mut output = Vec::with_capacity(steps as usize * 8);
+            for step in 1..=steps as u64 {
+                let input_amount = step_amount
+                    .checked_mul(step)
+                    .ok_or(ErrorCode::InvalidInstructionData)?;
+                let quote =
+                    quote_constant_product(input_amount, reserve_in, reserve_out, fee_bps)?;
+                state.quoted_output = quote.output_amount;
+                output.extend_from_slice(&quote.output_amount.to_le_bytes());
+            }
+            (true, output)
+        }
+    };
+
+    Ok(RollupResult::new(success, &output))
+}
+
+/// Constant-product quote with the fee taken from the input, from the quote
+/// math shared with the route executor and off-chain clients
+fn quote_constant_product(
+    input_amount: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+) -> Result<Quote> {
+    let quote = Fee::from_bps(fee_bps)
+        .and_then(|fee| {
+            constant_product::quote_exact_in(input_amount, reserve_in, reserve_out, fee)
+        })
+        .map_err(|_| ErrorCode::InvalidInstructionData)?;
+
+    Ok(Quote {
+        output_amount: quote.amount_out,
+        price_impact_bps: quote.price_impact_bps,
     })
 }
 
-/// Update state hash after instruction execution
+/// Extend the session hash chain with an executed op and its result
 fn update_state_hash(
     current_hash: [u8; 32],
-    instruction_data: &[u8],
-    success: bool,
+    op_bytes: &[u8],
+    result_bytes: &[u8],
 ) -> [u8; 32] {
-    let mut hash = current_hash;
-    
-    // XOR instruction data
-    for (i, byte) in instruction_data.iter().enumerate() {
-        hash[i % 32] ^= byte;
-    }
-    
-    // XOR success flag
-    hash[31] ^= success as u8;
-    
-    hash
+    hashv(&[&current_hash, op_bytes, result_bytes]).to_bytes()
 }
 
-/// Generate commit proof
-fn generate_commit_proof(
-    state_hash: &[u8; 32],
-    instructions_executed: u32,
-    timestamp: i64,
-) -> CommitProof {
-    let mut hash = [0u8; 32];
-    
-    // XOR state_hash
-    for (i, byte) in state_hash.iter().enumerate() {
-        hash[i] ^= byte;
-    }
-    
-    // XOR instructions_executed
-    let instr_bytes = instructions_executed.to_le_bytes();
-    for (i, byte) in instr_bytes.iter().enumerate() {
-        hash[(i + 8) % 32] ^= byte;
-    }
-    
-    // XOR timestamp
-    let timestamp_bytes = timestamp.to_le_bytes();
-    for (i, byte) in timestamp_bytes.iter().enumerate() {
-        hash[(i + 16) % 32] ^= byte;
+/// Merkle leaf committing to an executed op, its position and its result
+fn op_leaf(index: u32, op_bytes: &[u8], result_bytes: &[u8]) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, &index.to_le_bytes(), op_bytes, result_bytes]).to_bytes()
+}
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Hash-chain commitment to an output, taken over its `OUTPUT_CHUNK_LEN` chunks
+fn chunked_output_hash(output: &[u8]) -> [u8; 32] {
+    output
+        .chunks(OUTPUT_CHUNK_LEN)
+        .fold([0u8; 32], |hash, chunk| hash_output_chunk(&hash, chunk))
+}
+
+fn hash_output_chunk(previous: &[u8; 32], chunk: &[u8]) -> [u8; 32] {
+    hashv(&[OUTPUT_CHUNK_PREFIX, previous, chunk]).to_bytes()
+}
+
+/// Roots of empty subtrees at each height
+fn zero_hashes() -> [[u8; 32]; ACCUMULATOR_DEPTH] {
+    let mut zeros = [[0u8; 32]; ACCUMULATOR_DEPTH];
+    for height in 1..ACCUMULATOR_DEPTH {
+        zeros[height] = hash_node(&zeros[height - 1], &zeros[height - 1]);
     }
-    
-    CommitProof {
-        hash,
-        state_hash: *state_hash,
-        instructions_executed,
-        timestamp,
-        signature: generate_commit_signature(&hash),
+    zeros
+}
+
+/// Append a leaf to the incremental Merkle accumulator
+fn accumulator_insert(
+    frontier: &mut [[u8; 32]; ACCUMULATOR_DEPTH],
+    index: u32,
+    leaf: [u8; 32],
+) {
+    let mut node = leaf;
+    let mut size = index + 1;
+    for branch in frontier.iter_mut() {
+        if size & 1 == 1 {
+            *branch = node;
+            return;
+        }
+        node = hash_node(branch, &node);
+        size >>= 1;
     }
 }
 
-/// Generate commit signature
-fn generate_commit_signature(hash: &[u8; 32]) -> [u8; 64] {
-    let mut signature = [0u8; 64];
-    let slot = Clock::get().unwrap().slot;
-    let slot_bytes = slot.to_le_bytes();
-    
-    // First half: XOR hash with slot
-    for i in 0..32 {
-        signature[i] = hash[i] ^ slot_bytes[i % 8];
+/// Root of the accumulator holding `count` leaves
+fn accumulator_root(frontier: &[[u8; 32]; ACCUMULATOR_DEPTH], count: u32) -> [u8; 32] {
+    let zeros = zero_hashes();
+    let mut node = [0u8; 32];
+    let mut size = count;
+    for height in 0..ACCUMULATOR_DEPTH {
+        node = if size & 1 == 1 {
+            hash_node(&frontier[height], &node)
+        } else {
+            hash_node(&node, &zeros[height])
+        };
+        size >>= 1;
     }
-    
-    // Second half: reverse XOR
-    for i in 0..32 {
-        signature[32 + i] = hash[31 - i] ^ slot_bytes[7 - (i % 8)];
+    node
+}
+
+/// Root implied by a leaf at `index` and its sibling path
+fn merkle_root_from_proof(leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> [u8; 32] {
+    let mut node = leaf;
+    for (height, sibling) in proof.iter().enumerate() {
+        node = if (index >> height) & 1 == 1 {
+            hash_node(sibling, &node)
+        } else {
+            hash_node(&node, sibling)
+        };
     }
-    
-    signature
+    node
+}
+
+/// Hash of the session state a rollup validator signs on commit
+pub fn commit_hash(
+    session_id: &RollupSessionId,
+    state_hash: &[u8; 32],
+    op_root: &[u8; 32],
+    instructions_executed: u32,
+) -> [u8; 32] {
+    hashv(&[
+        COMMIT_PREFIX,
+        session_id,
+        state_hash,
+        op_root,
+        &instructions_executed.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Check that the preceding instruction is an Ed25519 program verification
+/// of `validator`'s signature over `message`, and return the signature
+fn verify_commit_signature(
+    instructions_sysvar: &AccountInfo,
+    validator: &Pubkey,
+    message: &[u8; 32],
+) -> Result<[u8; 64]> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    require!(current_index > 0, ErrorCode::InvalidCommitSignature);
+
+    let instruction =
+        load_instruction_at_checked((current_index - 1) as usize, instructions_sysvar)?;
+    require_keys_eq!(
+        instruction.program_id,
+        ED25519_PROGRAM_ID,
+        ErrorCode::InvalidCommitSignature
+    );
+
+    // Single signature whose offsets all point into the Ed25519 instruction itself
+    let data = &instruction.data;
+    require!(
+        data.len() >= ED25519_HEADER_LEN && data[0] == 1,
+        ErrorCode::InvalidCommitSignature
+    );
+    let offset = |position: usize| u16::from_le_bytes([data[position], data[position + 1]]);
+    let signature_offset = offset(2) as usize;
+    let public_key_offset = offset(6) as usize;
+    let message_offset = offset(10) as usize;
+    let message_size = offset(12) as usize;
+    require!(
+        offset(4) == u16::MAX && offset(8) == u16::MAX && offset(14) == u16::MAX,
+        ErrorCode::InvalidCommitSignature
+    );
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(ErrorCode::InvalidCommitSignature)?;
+    let signature = data
+        .get(signature_offset..signature_offset + 64)
+        .ok_or(ErrorCode::InvalidCommitSignature)?;
+    let signed_message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(ErrorCode::InvalidCommitSignature)?;
+
+    require!(
+        public_key == validator.as_ref(),
+        ErrorCode::UnknownRollupValidator
+    );
+    require!(
+        signed_message == message.as_ref(),
+        ErrorCode::InvalidCommitSignature
+    );
+
+    let mut result = [0u8; 64];
+    result.copy_from_slice(signature);
+    Ok(result)
 }
 
 // Constants
 const ROLLUP_SESSION_TIMEOUT: i64 = 300; // 5 minutes
+const ACCUMULATOR_DEPTH: usize = 16;
+const MAX_SESSION_OPS: u32 = (1 << ACCUMULATOR_DEPTH) - 1;
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+const COMMIT_PREFIX: &[u8] = b"zephyra-rollup-commit";
+const ED25519_PROGRAM_ID: Pubkey = pubkey!("Ed25519SigVerify111111111111111111111111111");
+const ED25519_HEADER_LEN: usize = 16; // signature count, padding and one offsets entry
+const OUTPUT_CHUNK_PREFIX: &[u8] = &[2];
+pub const OUTPUT_CHUNK_LEN: usize = 900;
+const DEFAULT_MAX_OPS: u32 = 64;
+const DEFAULT_MAX_COMPUTE_UNITS: u64 = 200_000;
+const DEFAULT_MAX_OUTPUT_BYTES: u32 = 4_096;
+const MAX_ROLLBACK_NOTE_LEN: usize = 64;
+const MAX_LADDER_STEPS: u16 = 256;
+pub const RAYDIUM_AMM_PROGRAM_ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+pub const WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
 
 // Account structures
 #[account]
 #[derive(InitSpace)]
 pub struct RollupSession {
-    pub id: RollupSessionId,
+    pub id: [u8; 32],
     pub transaction_id: [u8; 32],
     pub status: RollupStatus,
     pub created_at: i64,
@@ -293,6 +1360,268 @@ pub struct RollupSession {
     pub state_hash: [u8; 32],
     pub committed_at: Option<i64>,
     pub rolled_back_at: Option<i64>,
+    pub state: RollupState,
+    pub op_root: [u8; 32], // Merkle root of executed ops
+    pub op_frontier: [[u8; 32]; ACCUMULATOR_DEPTH], // incremental accumulator branch
+    pub payer: Pubkey, // receives the rent when the session is closed
+    pub linked_transaction: Option<Pubkey>, // transaction of a single-transaction session
+    pub transaction_snapshot: Option<TransactionSnapshot>, // restored if the session does not commit
+    pub creator: Pubkey,
+    pub executor: Option<Pubkey>, // executes and commits in place of the creator when set
+    pub committer: Option<Pubkey>,
+    pub finalizes_at_slot: Option<u64>, // end of the challenge window
+    pub bond: u64, // committer bond held until finalization, in lamports
+    pub batch_id: Option<[u8; 32]>, // set for batch sessions, which are keyed by batch ID
+    #[max_len(10)]
+    pub members: Vec<BatchMember>, // batch transactions in batch order
+    pub budget: SessionBudget,
+    pub usage: SessionUsage,
+    pub rollback_reason: Option<RollbackReason>,
+}
+
+/// Commitment to the output of one executed op, whose bytes live in chunk accounts
+#[account]
+#[derive(InitSpace)]
+pub struct RollupOutput {
+    pub session: Pubkey,
+    pub session_id: [u8; 32], // tells a re-opened session's outputs apart
+    pub payer: Pubkey, // session operator, paid the rent of the output and its chunks
+    pub op_index: u32,
+    pub output_hash: [u8; 32], // hash chain over the output's chunks
+    pub output_len: u32,
+    pub chunk_count: u16,
+    pub chunks_written: u16,
+    pub written_hash: [u8; 32], // hash chain over the chunks written so far
+    pub bump: u8,
+}
+
+impl RollupOutput {
+    /// Whether every chunk has been written and matched `output_hash`
+    pub fn is_complete(&self) -> bool {
+        self.chunks_written == self.chunk_count
+    }
+}
+
+/// One `OUTPUT_CHUNK_LEN` slice of an op's output
+#[account]
+#[derive(InitSpace)]
+pub struct RollupOutputChunk {
+    pub output: Pubkey,
+    pub chunk_index: u16,
+    #[max_len(OUTPUT_CHUNK_LEN)]
+    pub data: Vec<u8>,
+    pub bump: u8,
+}
+
+/// Resource limits for a rollup session, fixed at initialization
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct SessionBudget {
+    pub max_ops: u32,
+    pub max_compute_units: u64,
+    pub max_output_bytes: u32,
+}
+
+impl Default for SessionBudget {
+    fn default() -> Self {
+        Self {
+            max_ops: DEFAULT_MAX_OPS,
+            max_compute_units: DEFAULT_MAX_COMPUTE_UNITS,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+}
+
+/// Resources consumed by the ops executed in a session
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct SessionUsage {
+    pub ops: u32,
+    pub compute_units: u64, // measured around each op with `sol_remaining_compute_units`
+    pub output_bytes: u32,
+}
+
+impl SessionUsage {
+    /// Record one op, failing if it would exceed any limit of the budget
+    pub fn charge(
+        &mut self,
+        budget: &SessionBudget,
+        compute_units: u64,
+        output_bytes: u32,
+    ) -> Result<()> {
+        let ops = self
+            .ops
+            .checked_add(1)
+            .ok_or(ErrorCode::SessionBudgetExceeded)?;
+        let compute_units = self
+            .compute_units
+            .checked_add(compute_units)
+            .ok_or(ErrorCode::SessionBudgetExceeded)?;
+        let output_bytes = self
+            .output_bytes
+            .checked_add(output_bytes)
+            .ok_or(ErrorCode::SessionBudgetExceeded)?;
+        require!(
+            ops <= budget.max_ops
+                && compute_units <= budget.max_compute_units
+                && output_bytes <= budget.max_output_bytes,
+            ErrorCode::SessionBudgetExceeded
+        );
+
+        self.ops = ops;
+        self.compute_units = compute_units;
+        self.output_bytes = output_bytes;
+        Ok(())
+    }
+}
+
+/// Fields of a linked transaction a session may change, captured at initialization
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TransactionSnapshot {
+    pub status: TransactionStatus,
+    pub output_amount: u64,
+    pub risk_score: u8,
+    pub selected_route: Option<SelectedRoute>,
+    pub proof_hash: [u8; 32],
+}
+
+impl TransactionSnapshot {
+    pub fn capture(transaction: &Transaction) -> Self {
+        Self {
+            status: transaction.status,
+            output_amount: transaction.output_amount,
+            risk_score: transaction.risk_score,
+            selected_route: transaction.selected_route,
+            proof_hash: transaction.proof_hash,
+        }
+    }
+
+    /// Write the snapshot back, recording the status change on the timeline.
+    /// Returns false without touching a transaction that has left `Simulating`.
+    pub fn restore(&self, transaction: &mut Transaction, signer: Pubkey, clock: &Clock) -> bool {
+        if transaction.status != TransactionStatus::Simulating {
+            return false;
+        }
+
+        transaction.output_amount = self.output_amount;
+        transaction.risk_score = self.risk_score;
+        transaction.selected_route = self.selected_route;
+        transaction.proof_hash = self.proof_hash;
+        record_status_change(transaction, self.status, signer, clock);
+        true
+    }
+}
+
+/// Why a session was rolled back
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub enum RollbackReason {
+    SimulationFailed,
+    SlippageExceeded,
+    MevRisk,
+    RouteUnavailable,
+    BudgetExhausted,
+    Cancelled,
+    Other,
+    FraudProven, // a challenge showed a committed op result was wrong
+}
+
+/// Batch transaction simulated in a session, with the amounts it was opened with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct BatchMember {
+    pub transaction_id: [u8; 32],
+    pub transaction: Pubkey,
+    pub input_token: Pubkey,
+    pub output_token: Pubkey,
+    pub input_amount: u64,
+    pub min_output_amount: u64,
+    pub snapshot: Option<TransactionSnapshot>, // restored if the session does not commit
+    pub result: Option<MemberResult>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct MemberResult {
+    pub success: bool,
+    pub output_amount: u64,
+    pub price_impact_bps: u16,
+    pub op_index: u32, // position of the simulation in the session's op order
+}
+
+impl RollupSession {
+    /// First PDA seed, keeping batch sessions apart from single-transaction ones
+    pub fn seed_prefix(&self) -> &'static [u8] {
+        if self.batch_id.is_some() {
+            b"batch_rollup_session"
+        } else {
+            b"rollup_session"
+        }
+    }
+
+    /// Second PDA seed: the linked transaction's address, or the batch ID
+    pub fn seed_key(&self) -> &[u8] {
+        match &self.linked_transaction {
+            Some(transaction) => transaction.as_ref(),
+            None => self.transaction_id.as_ref(),
+        }
+    }
+
+    /// Authority allowed to execute ops and commit the session
+    pub fn is_operator(&self, authority: &Pubkey) -> bool {
+        *authority == self.executor.unwrap_or(self.creator)
+    }
+
+    /// Authorities allowed to roll the session back
+    pub fn can_abort(&self, authority: &Pubkey) -> bool {
+        *authority == self.creator || Some(*authority) == self.executor
+    }
+}
+
+/// Session state built up by applied rollup operations
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct RollupState {
+    pub quoted_output: u64,
+    pub simulated_output: u64,
+    pub price_impact_bps: u16,
+    pub risk_score: u8, // 0-100
+    pub mev_detected: bool,
+    pub selected_route: Option<SelectedRoute>,
+    pub expected_output: u64,
+}
+
+/// Operation executed inside a rollup session, borsh-encoded as instruction data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+pub enum RollupOp {
+    Quote {
+        input_amount: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u16,
+    },
+    SimulateSwap {
+        input_amount: u64,
+        min_output: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u16,
+    },
+    UpdateRisk {
+        risk_score: u8,
+        mev_detected: bool,
+    },
+    SetRoute {
+        route: SelectedRoute,
+        expected_output: u64,
+    },
+    SimulateMember {
+        member_index: u8, // position of the transaction in a batch session
+        reserve_in: u64, // checked against the pool's vaults, see `execute_in_rollup`
+        reserve_out: u64,
+        fee_bps: u16,
+    },
+    QuoteLadder {
+        step_amount: u64, // quotes `step_amount * n` for n in 1..=steps, one u64 each
+        steps: u16,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_bps: u16,
+    },
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
@@ -301,33 +1630,44 @@ pub enum RollupStatus {
     Committed,
     RolledBack,
     Expired,
+    PendingFinalization,
 }
 
+/// Result of an op, committed to in the op root. Compute usage varies with where
+/// the op runs, so it is metered on the session instead of recorded here, and
+/// the output itself is too large to return, so only its commitment is.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct RollupResult {
     pub success: bool,
-    #[max_len(1000)]
-    pub output_data: Vec<u8>,
-    pub execution_time_ms: u32,
-    pub gas_used: u64,
+    pub output_hash: [u8; 32], // chunk hash chain over the output, see `RollupOutput`
+    pub output_len: u32,
+}
+
+impl RollupResult {
+    /// Result committing to `output`, whose bytes are written to chunk accounts
+    pub fn new(success: bool, output: &[u8]) -> Self {
+        Self {
+            success,
+            output_hash: chunked_output_hash(output),
+            output_len: output.len() as u32,
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct CommitProof {
     pub hash: [u8; 32],
     pub state_hash: [u8; 32],
+    pub op_root: [u8; 32],
     pub instructions_executed: u32,
     pub timestamp: i64,
-    pub signature: [u8; 64],
+    pub signature: [u8; 64], // Ed25519 signature over `hash`
+    pub validator: Pubkey,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
-pub struct ExecutionResult {
-    pub success: bool,
-    #[max_len(1000)]
-    pub output_data: Vec<u8>,
-    pub execution_time_ms: u32,
-    pub gas_used: u64,
+struct Quote {
+    output_amount: u64,
+    price_impact_bps: u16,
 }
 
 // Type aliases
@@ -341,10 +1681,41 @@ pub struct InitRollupSession<'info> {
         init,
         payer = payer,
         space = 8 + RollupSession::INIT_SPACE,
-        seeds = [b"rollup_session", &transaction_id],
+        seeds = [b"rollup_session", transaction_account.key().as_ref()],
         bump
     )]
     pub session_account: Account<'info, RollupSession>,
+    // Only the transaction's owner opens a session for it
+    #[account(
+        mut,
+        constraint = transaction_account.id == transaction_id @ ErrorCode::InvalidTransactionId,
+        constraint = transaction_account.owner == payer.key() @ ErrorCode::Unauthorized
+    )]
+    pub transaction_account: Account<'info, Transaction>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(batch_id: [u8; 32])]
+pub struct InitBatchRollupSession<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RollupSession::INIT_SPACE,
+        seeds = [b"batch_rollup_session", batch_id.as_ref()],
+        bump
+    )]
+    pub session_account: Account<'info, RollupSession>,
+    /// CHECK: Batch coordinator batch, deserialized in the handler
+    #[account(owner = BATCH_COORDINATOR_PROGRAM_ID)]
+    pub batch_account: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -355,10 +1726,58 @@ pub struct InitRollupSession<'info> {
 pub struct ExecuteInRollup<'info> {
     #[account(
         mut,
-        seeds = [b"rollup_session", &session_account.transaction_id],
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
         bump
     )]
     pub session_account: Account<'info, RollupSession>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RollupOutput::INIT_SPACE,
+        seeds = [
+            b"rollup_output",
+            session_account.key().as_ref(),
+            session_account.id.as_ref(),
+            &session_account.instructions_executed.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub output_account: Option<Account<'info, RollupOutput>>, // created when the output will be stored
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_id: RollupSessionId, op_index: u32, chunk_index: u16)]
+pub struct WriteRollupOutput<'info> {
+    #[account(
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
+        bump
+    )]
+    pub session_account: Account<'info, RollupSession>,
+    #[account(
+        mut,
+        seeds = [
+            b"rollup_output",
+            session_account.key().as_ref(),
+            session_account.id.as_ref(),
+            &op_index.to_le_bytes(),
+        ],
+        bump = output_account.bump
+    )]
+    pub output_account: Account<'info, RollupOutput>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RollupOutputChunk::INIT_SPACE,
+        seeds = [b"rollup_output_chunk", output_account.key().as_ref(), &chunk_index.to_le_bytes()],
+        bump
+    )]
+    pub chunk_account: Account<'info, RollupOutputChunk>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -366,18 +1785,134 @@ pub struct ExecuteInRollup<'info> {
 pub struct CommitRollup<'info> {
     #[account(
         mut,
-        seeds = [b"rollup_session", &session_account.transaction_id],
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
         bump
     )]
     pub session_account: Account<'info, RollupSession>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        seeds = [b"rollup_validator", rollup_validator.validator.as_ref()],
+        bump = rollup_validator.bump
+    )]
+    pub rollup_validator: Account<'info, RollupValidator>,
+    /// Registered validator vouching for the commit, which posts the bond
+    #[account(mut, address = rollup_validator.validator @ ErrorCode::UnknownRollupValidator)]
+    pub validator: Signer<'info>,
+    /// CHECK: Instructions sysvar holding the Ed25519 signature verification
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    // Only supplied when the session is delegated to an ephemeral rollup
+    #[account(
+        mut,
+        constraint = session_account.linked_transaction == Some(transaction_account.key())
+            @ ErrorCode::InvalidTransactionId
+    )]
+    pub transaction_account: Option<Account<'info, Transaction>>,
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+    /// CHECK: MagicBlock commit context
+    #[account(mut, address = MAGIC_CONTEXT_ID)]
+    pub magic_context: Option<UncheckedAccount<'info>>,
+    /// CHECK: MagicBlock magic program
+    #[account(address = MAGIC_PROGRAM_ID)]
+    pub magic_program: Option<UncheckedAccount<'info>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct FinalizeRollup<'info> {
+    #[account(
+        mut,
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
+        bump,
+        constraint = session_account.transaction_id == transaction_id @ ErrorCode::InvalidTransactionId
+    )]
+    pub session_account: Account<'info, RollupSession>,
+    /// CHECK: Receives the returned bond, validated against the session
+    #[account(
+        mut,
+        address = session_account.committer.unwrap_or_default() @ ErrorCode::Unauthorized
+    )]
+    pub committer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct ChallengeRollup<'info> {
+    #[account(
+        mut,
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
+        bump,
+        constraint = session_account.transaction_id == transaction_id @ ErrorCode::InvalidTransactionId
+    )]
+    pub session_account: Account<'info, RollupSession>,
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+    /// CHECK: Linked transaction, required when the session has one and validated in the handler
+    #[account(mut)]
+    pub transaction_account: Option<UncheckedAccount<'info>>,
 }
 
 #[derive(Accounts)]
-#[instruction(session_id: RollupSessionId, reason: String)]
+#[instruction(session_id: RollupSessionId)]
 pub struct RollbackRollup<'info> {
     #[account(
         mut,
-        seeds = [b"rollup_session", &session_account.transaction_id],
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
+        bump
+    )]
+    pub session_account: Account<'info, RollupSession>,
+    pub authority: Signer<'info>,
+    /// CHECK: Linked transaction, required when the session has one and validated in the handler
+    #[account(mut)]
+    pub transaction_account: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct ExpireRollupSession<'info> {
+    #[account(
+        mut,
+        close = payer,
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
+        bump,
+        constraint = session_account.transaction_id == transaction_id @ ErrorCode::InvalidTransactionId
+    )]
+    pub session_account: Account<'info, RollupSession>,
+    /// CHECK: Linked transaction, validated against the session in the handler
+    #[account(mut)]
+    pub transaction_account: Option<UncheckedAccount<'info>>,
+    /// CHECK: Receives the session rent, validated against the session
+    #[account(mut, address = session_account.payer)]
+    pub payer: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRollupOutput<'info> {
+    /// CHECK: The output's session, closed or re-opened since, checked in the handler
+    #[account(address = output_account.session)]
+    pub session_account: UncheckedAccount<'info>,
+    #[account(mut, close = payer)]
+    pub output_account: Account<'info, RollupOutput>,
+    /// CHECK: Receives the output and chunk rent, validated against the output
+    #[account(mut, address = output_account.payer)]
+    pub payer: UncheckedAccount<'info>,
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(session_id: RollupSessionId)]
+pub struct VerifyRollupOp<'info> {
+    #[account(
+        seeds = [session_account.seed_prefix(), session_account.seed_key()],
         bump
     )]
     pub session_account: Account<'info, RollupSession>,
@@ -391,13 +1926,35 @@ pub struct RollupSessionInitialized {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct BatchRollupSessionInitialized {
+    pub session_id: RollupSessionId,
+    pub batch_id: [u8; 32],
+    pub member_count: u8,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct InstructionExecuted {
     pub session_id: RollupSessionId,
-    #[max_len(1000)]
-    pub instruction_data: Vec<u8>,
+    pub op_index: u32,
+    pub op_hash: [u8; 32], // sha256 of the borsh-encoded op
+    pub op_len: u32,
+    pub output_hash: [u8; 32], // chunk hash chain, see `RollupOutput`
+    pub output_len: u32,
     pub success: bool,
-    pub execution_time_ms: u32,
+    pub compute_units: u64, // consumed by the op, as charged to the session
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RollupOutputChunkWritten {
+    pub session_id: RollupSessionId,
+    pub op_index: u32,
+    pub chunk_index: u16,
+    pub chunk_hash: [u8; 32],
+    pub chunk_len: u32,
+    pub complete: bool,
     pub timestamp: i64,
 }
 
@@ -405,30 +1962,50 @@ pub struct InstructionExecuted {
 pub struct RollupCommitted {
     pub session_id: RollupSessionId,
     pub state_hash: [u8; 32],
+    pub op_root: [u8; 32],
     pub instructions_executed: u32,
     pub commit_proof: [u8; 32],
+    pub validator: Pubkey,
+    pub batch_id: Option<[u8; 32]>,
+    pub budget: SessionBudget,
+    pub usage: SessionUsage, // resources consumed by the committed ops
     pub timestamp: i64,
 }
 
 #[event]
-pub struct RollupRolledBack {
+pub struct RollupSessionClosed {
+    pub session_id: RollupSessionId,
+    pub transaction_id: [u8; 32],
+    pub status: RollupStatus,
+    pub payer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RollupFinalized {
+    pub session_id: RollupSessionId,
+    pub transaction_id: [u8; 32],
+    pub committer: Pubkey,
+    pub bond_returned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RollupChallenged {
     pub session_id: RollupSessionId,
-    #[max_len(200)]
-    pub reason: String,
+    pub transaction_id: [u8; 32],
+    pub op_index: u32,
+    pub challenger: Pubkey,
+    pub bond_slashed: u64,
+    pub transaction_restored: bool,
     pub timestamp: i64,
 }
 
-// Error codes
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid session ID")]
-    InvalidSessionId,
-    #[msg("Session is not active")]
-    SessionNotActive,
-    #[msg("Session has expired")]
-    SessionExpired,
-    #[msg("Rollup execution failed")]
-    ExecutionFailed,
-    #[msg("Invalid instruction data")]
-    InvalidInstructionData,
+#[event]
+pub struct RollupRolledBack {
+    pub session_id: RollupSessionId,
+    pub reason: RollbackReason,
+    pub note: Option<String>,
+    pub transaction_restored: bool,
+    pub timestamp: i64,
 }
@@ -0,0 +1,372 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::TokenAccount;
+use zephyra_quote::pools::raydium_amm::AmmInfo;
+use zephyra_quote::pools::whirlpool::{TickArray, Whirlpool};
+use zephyra_quote::pools::{self, Address};
+use zephyra_quote::{Quote, QuoteError};
+
+use crate::{AdapterKind, ErrorCode};
+
+// Programs of the venues with built-in adapters, registered as venues by the admin
+pub const JUPITER_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+pub const RAYDIUM_AMM_PROGRAM_ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+pub const WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+const RAYDIUM_SWAP_BASE_IN: u8 = 9;
+const RAYDIUM_POOL_ACCOUNTS: usize = 14; // amm through serum vault signer
+const RAYDIUM_COIN_VAULT: usize = 4;
+const RAYDIUM_PC_VAULT: usize = 5;
+const WHIRLPOOL_SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+const WHIRLPOOL_POOL_ACCOUNTS: usize = 7; // whirlpool, vaults, tick arrays and oracle
+const WHIRLPOOL_TICK_ARRAYS: core::ops::Range<usize> = 3..6;
+const WHIRLPOOL_MIN_SQRT_PRICE: u128 = 4_295_048_016;
+const WHIRLPOOL_MAX_SQRT_PRICE: u128 = 79_226_673_515_401_279_992_447_579_055;
+/// Longest instruction discriminator a passthrough venue can be registered with
+pub const MAX_DISCRIMINATOR_LEN: usize = 8;
+
+/// Accounts and amounts of one swap through a venue
+pub struct SwapLeg<'a, 'info> {
+    pub amount_in: u64,
+    pub min_output: u64,
+    pub dex_program: &'a AccountInfo<'info>, // registered venue program, receives the CPI
+    pub token_program: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>, // owns `source`, signs through PDA seeds
+    pub source: &'a AccountInfo<'info>,
+    pub destination: &'a AccountInfo<'info>,
+    pub venue_accounts: &'a [AccountInfo<'info>], // venue-specific, from `remaining_accounts`
+    pub venue_data: &'a [u8],
+}
+
+/// Builds the swap instruction of one kind of DEX program
+pub trait DexAdapter {
+    /// Pool the swap trades against, checked by the execution guard
+    fn pool(&self, _leg: &SwapLeg) -> Option<Pubkey> {
+        None
+    }
+
+    /// Quote of the swap from the pool's live accounts, or `None` for venues
+    /// that route off-chain
+    fn quote(&self, _leg: &SwapLeg) -> Result<Option<Quote>> {
+        Ok(None)
+    }
+
+    /// Venue instruction swapping `leg.amount_in` from `source` into `destination`
+    fn swap_instruction(&self, leg: &SwapLeg) -> Result<Instruction>;
+}
+
+/// The one instruction a passthrough venue is registered for and where it
+/// takes the swap's authority, source, destination and pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub struct PassthroughLayout {
+    #[max_len(MAX_DISCRIMINATOR_LEN)]
+    pub discriminator: Vec<u8>,
+    pub authority_index: u8,
+    pub source_index: u8,
+    pub destination_index: u8,
+    pub pool_index: u8, // first pool the route trades against, checked by the execution guard
+}
+
+/// Venues whose instruction is built off-chain with the escrow authority as
+/// the user and passed through as `venue_data`, such as Jupiter v6 routes.
+/// The escrow authority only signs the registered instruction, with the
+/// authority and source at their registered places and nowhere else.
+pub struct PassthroughAdapter<'a>(pub &'a PassthroughLayout);
+
+impl DexAdapter for PassthroughAdapter<'_> {
+    fn pool(&self, leg: &SwapLeg) -> Option<Pubkey> {
+        leg.venue_accounts
+            .get(self.0.pool_index as usize)
+            .map(|pool| pool.key())
+    }
+
+    fn swap_instruction(&self, leg: &SwapLeg) -> Result<Instruction> {
+        let layout = self.0;
+        require!(
+            !layout.discriminator.is_empty() && leg.venue_data.starts_with(&layout.discriminator),
+            ErrorCode::InvalidRouteData
+        );
+        let at = |index: u8| leg.venue_accounts.get(index as usize).map(|account| account.key);
+        require!(
+            at(layout.authority_index) == Some(leg.authority.key)
+                && at(layout.source_index) == Some(leg.source.key)
+                && at(layout.destination_index) == Some(leg.destination.key)
+                && at(layout.pool_index).is_some(),
+            ErrorCode::InvalidRouteData
+        );
+
+        let mut accounts = Vec::with_capacity(leg.venue_accounts.len());
+        for (index, account) in leg.venue_accounts.iter().enumerate() {
+            let is_authority = index == layout.authority_index as usize;
+            require!(
+                is_authority || account.key != leg.authority.key,
+                ErrorCode::InvalidRouteData
+            );
+            require!(
+                index == layout.source_index as usize || account.key != leg.source.key,
+                ErrorCode::InvalidRouteData
+            );
+            let mut meta = account_meta(account);
+            meta.is_signer = is_authority;
+            accounts.push(meta);
+        }
+
+        Ok(Instruction {
+            program_id: leg.dex_program.key(),
+            accounts,
+            data: leg.venue_data.to_vec(),
+        })
+    }
+}
+
+/// Raydium AMM v4 `swap_base_in`
+pub struct RaydiumAdapter;
+
+impl DexAdapter for RaydiumAdapter {
+    fn pool(&self, leg: &SwapLeg) -> Option<Pubkey> {
+        leg.venue_accounts.first().map(|amm| amm.key())
+    }
+
+    fn quote(&self, leg: &SwapLeg) -> Result<Option<Quote>> {
+        require!(
+            leg.venue_accounts.len() == RAYDIUM_POOL_ACCOUNTS,
+            ErrorCode::InvalidRouteData
+        );
+        let amm = &leg.venue_accounts[0];
+        let coin_vault = &leg.venue_accounts[RAYDIUM_COIN_VAULT];
+        let pc_vault = &leg.venue_accounts[RAYDIUM_PC_VAULT];
+        require_keys_eq!(
+            *amm.owner,
+            leg.dex_program.key(),
+            ErrorCode::InvalidPoolAccount
+        );
+
+        let amm_data = amm.try_borrow_data()?;
+        let amm_info = AmmInfo::decode(&amm_data).map_err(quote_error)?;
+        require!(
+            is_key(coin_vault, amm_info.coin_vault()) && is_key(pc_vault, amm_info.pc_vault()),
+            ErrorCode::InvalidPoolAccount
+        );
+
+        let coin_data = coin_vault.try_borrow_data()?;
+        let pc_data = pc_vault.try_borrow_data()?;
+        let source_data = leg.source.try_borrow_data()?;
+        let quote = amm_info
+            .quote_exact_in(
+                &pools::TokenAccount::decode(&coin_data).map_err(quote_error)?,
+                &pools::TokenAccount::decode(&pc_data).map_err(quote_error)?,
+                pools::TokenAccount::decode(&source_data).map_err(quote_error)?.mint(),
+                leg.amount_in,
+            )
+            .map_err(quote_error)?;
+        Ok(Some(quote))
+    }
+
+    fn swap_instruction(&self, leg: &SwapLeg) -> Result<Instruction> {
+        require!(
+            leg.venue_accounts.len() == RAYDIUM_POOL_ACCOUNTS,
+            ErrorCode::InvalidRouteData
+        );
+
+        let mut accounts = vec![AccountMeta::new_readonly(leg.token_program.key(), false)];
+        accounts.extend(leg.venue_accounts.iter().map(account_meta));
+        accounts.push(AccountMeta::new(leg.source.key(), false));
+        accounts.push(AccountMeta::new(leg.destination.key(), false));
+        accounts.push(AccountMeta::new_readonly(leg.authority.key(), true));
+
+        let mut data = Vec::with_capacity(17);
+        data.push(RAYDIUM_SWAP_BASE_IN);
+        data.extend_from_slice(&leg.amount_in.to_le_bytes());
+        data.extend_from_slice(&leg.min_output.to_le_bytes());
+
+        Ok(Instruction {
+            program_id: leg.dex_program.key(),
+            accounts,
+            data,
+        })
+    }
+}
+
+/// Orca Whirlpool `swap`, exact input
+pub struct WhirlpoolAdapter;
+
+impl DexAdapter for WhirlpoolAdapter {
+    fn pool(&self, leg: &SwapLeg) -> Option<Pubkey> {
+        leg.venue_accounts.first().map(|whirlpool| whirlpool.key())
+    }
+
+    fn quote(&self, leg: &SwapLeg) -> Result<Option<Quote>> {
+        require!(
+            leg.venue_accounts.len() == WHIRLPOOL_POOL_ACCOUNTS,
+            ErrorCode::InvalidRouteData
+        );
+        let pool = &leg.venue_accounts[0];
+        require_keys_eq!(
+            *pool.owner,
+            leg.dex_program.key(),
+            ErrorCode::InvalidPoolAccount
+        );
+
+        let pool_data = pool.try_borrow_data()?;
+        let whirlpool = Whirlpool::decode(&pool_data).map_err(quote_error)?;
+        require!(
+            is_key(&leg.venue_accounts[1], whirlpool.token_vault_a())
+                && is_key(&leg.venue_accounts[2], whirlpool.token_vault_b()),
+            ErrorCode::InvalidPoolAccount
+        );
+        let source_data = leg.source.try_borrow_data()?;
+        let source_mint = pools::TokenAccount::decode(&source_data)
+            .map_err(quote_error)?
+            .mint();
+        let a_to_b = source_mint == whirlpool.token_mint_a();
+        require!(
+            a_to_b || source_mint == whirlpool.token_mint_b(),
+            ErrorCode::InvalidRouteData
+        );
+
+        // Clients repeat the last tick array when fewer are needed
+        let mut tick_accounts: Vec<&AccountInfo> =
+            leg.venue_accounts[WHIRLPOOL_TICK_ARRAYS].iter().collect();
+        tick_accounts.dedup_by_key(|account| account.key);
+        let tick_data = tick_accounts
+            .iter()
+            .map(|account| {
+                require_keys_eq!(
+                    *account.owner,
+                    leg.dex_program.key(),
+                    ErrorCode::InvalidPoolAccount
+                );
+                Ok(account.try_borrow_data()?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let tick_arrays = tick_data
+            .iter()
+            .map(|data| TickArray::decode(data))
+            .collect::<core::result::Result<Vec<_>, _>>()
+            .map_err(quote_error)?;
+        require!(
+            tick_arrays.iter().all(|array| is_key(pool, array.whirlpool())),
+            ErrorCode::InvalidPoolAccount
+        );
+
+        let quote = whirlpool
+            .quote_exact_in(&tick_arrays, leg.amount_in, a_to_b)
+            .map_err(quote_error)?;
+        Ok(Some(quote.quote))
+    }
+
+    fn swap_instruction(&self, leg: &SwapLeg) -> Result<Instruction> {
+        require!(
+            leg.venue_accounts.len() == WHIRLPOOL_POOL_ACCOUNTS,
+            ErrorCode::InvalidRouteData
+        );
+        let whirlpool = &leg.venue_accounts[0];
+        let vault_a = &leg.venue_accounts[1];
+        let vault_b = &leg.venue_accounts[2];
+
+        // Trade direction follows from which vault holds the input mint
+        let source_mint = token_mint(leg.source)?;
+        let a_to_b = token_mint(vault_a)? == source_mint;
+        require!(
+            a_to_b || token_mint(vault_b)? == source_mint,
+            ErrorCode::InvalidRouteData
+        );
+        let (owner_a, owner_b) = if a_to_b {
+            (leg.source, leg.destination)
+        } else {
+            (leg.destination, leg.source)
+        };
+        let sqrt_price_limit = if a_to_b {
+            WHIRLPOOL_MIN_SQRT_PRICE
+        } else {
+            WHIRLPOOL_MAX_SQRT_PRICE
+        };
+
+        let mut accounts = vec![
+            AccountMeta::new_readonly(leg.token_program.key(), false),
+            AccountMeta::new_readonly(leg.authority.key(), true),
+            account_meta(whirlpool),
+            AccountMeta::new(owner_a.key(), false),
+            account_meta(vault_a),
+            AccountMeta::new(owner_b.key(), false),
+            account_meta(vault_b),
+        ];
+        accounts.extend(leg.venue_accounts[3..].iter().map(account_meta));
+
+        let mut data = WHIRLPOOL_SWAP_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&leg.amount_in.to_le_bytes());
+        data.extend_from_slice(&leg.min_output.to_le_bytes()); // other_amount_threshold
+        data.extend_from_slice(&sqrt_price_limit.to_le_bytes());
+        data.push(1); // amount_specified_is_input
+        data.push(a_to_b as u8);
+
+        Ok(Instruction {
+            program_id: leg.dex_program.key(),
+            accounts,
+            data,
+        })
+    }
+}
+
+/// Adapter for a venue's kind
+pub fn adapter_for(kind: &AdapterKind) -> Box<dyn DexAdapter + '_> {
+    match kind {
+        AdapterKind::Passthrough { layout } => Box::new(PassthroughAdapter(layout)),
+        AdapterKind::RaydiumAmmV4 => Box::new(RaydiumAdapter),
+        AdapterKind::Whirlpool => Box::new(WhirlpoolAdapter),
+    }
+}
+
+/// Send the venue's swap instruction, signing for the escrow authority
+pub fn invoke_swap(
+    adapter: &dyn DexAdapter,
+    leg: &SwapLeg<'_, '_>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let instruction = adapter.swap_instruction(leg)?;
+    let mut account_infos = vec![
+        leg.dex_program.clone(),
+        leg.token_program.clone(),
+        leg.authority.clone(),
+        leg.source.clone(),
+        leg.destination.clone(),
+    ];
+    account_infos.extend_from_slice(leg.venue_accounts);
+
+    invoke_signed(&instruction, &account_infos, signer_seeds)?;
+    Ok(())
+}
+
+fn account_meta(account: &AccountInfo) -> AccountMeta {
+    if account.is_writable {
+        AccountMeta::new(account.key(), account.is_signer)
+    } else {
+        AccountMeta::new_readonly(account.key(), account.is_signer)
+    }
+}
+
+fn is_key(account: &AccountInfo, address: &Address) -> bool {
+    account.key.as_ref() == address
+}
+
+fn quote_error(error: QuoteError) -> Error {
+    match error {
+        QuoteError::InvalidAccountData | QuoteError::MintMismatch => {
+            ErrorCode::InvalidPoolAccount.into()
+        }
+        _ => ErrorCode::QuoteFailed.into(),
+    }
+}
+
+/// Mint of a token account
+pub fn token_mint(account: &AccountInfo) -> Result<Pubkey> {
+    let data = account.try_borrow_data()?;
+    Ok(TokenAccount::try_deserialize(&mut &data[..])?.mint)
+}
+
+/// Balance of a token account
+pub fn token_amount(account: &AccountInfo) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+}
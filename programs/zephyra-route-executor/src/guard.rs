@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+
+use zephyra_protection_manager::ExecutionGuard;
+
+use crate::ErrorCode;
+
+/// Run the checks the owner enabled in `guard` against the instructions sysvar
+pub fn enforce_execution_guard(
+    guard: &ExecutionGuard,
+    instructions_sysvar: &AccountInfo,
+    owner: &Pubkey,
+    pool_address: Option<&Pubkey>,
+) -> Result<()> {
+    if !guard.enforce_caller && !guard.reject_prior_pool_access {
+        return Ok(());
+    }
+
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+
+    if guard.enforce_caller {
+        // No Zephyra program swaps through CPI, so any caller program is foreign
+        require!(
+            get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT,
+            ErrorCode::UnauthorizedCaller
+        );
+
+        // Called directly: the owner must have signed the swap instruction
+        let current = load_instruction_at_checked(current_index as usize, instructions_sysvar)?;
+        require!(
+            current
+                .accounts
+                .iter()
+                .any(|meta| meta.pubkey == *owner && meta.is_signer),
+            ErrorCode::UnauthorizedCaller
+        );
+    }
+
+    if guard.reject_prior_pool_access {
+        if let Some(pool_address) = pool_address {
+            for index in 0..current_index {
+                let instruction = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+
+                // Protection manager instructions only keep records. Earlier swaps,
+                // this program's included, count as access to the pool.
+                if instruction.program_id == zephyra_protection_manager::ID {
+                    continue;
+                }
+
+                require!(
+                    !instruction
+                        .accounts
+                        .iter()
+                        .any(|meta| meta.pubkey == *pool_address),
+                    ErrorCode::PoolAccessedBeforeSwap
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,202 +1,749 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
+use solana_sha256_hasher::hashv;
+use zephyra_protection_manager::cpi::accounts::CompleteTransaction;
+use zephyra_protection_manager::program::ZephyraProtectionManager;
+use zephyra_protection_manager::{
+    ExecutionGuard, ProtectionAccount, ProtocolConfig, SelectedRoute, Transaction,
+    TransactionStatus,
+};
+
+pub mod adapters;
+pub mod guard;
+pub mod oracle;
+
+use adapters::*;
+use guard::*;
+use oracle::*;
 
 declare_id!("ED8b3n8TAyFGohFofCFiUFFT2X3xpsUpSEajWmCZbJ8n");
 
+/// Most hops a route can chain
+pub const MAX_HOPS: usize = 4;
+/// Most venues a split route can divide its input across, each leg being
+/// stored as one of the route's hops
+pub const MAX_SPLIT_LEGS: usize = MAX_HOPS;
+/// Longest venue name in the registry
+pub const MAX_VENUE_NAME_LEN: usize = 32;
+/// Fixed-point scale of a route score component, reached by the best possible value
+pub const SCORE_SCALE: u64 = 1_000_000;
+/// Most mint pairs the scoring config can bind a price feed to
+pub const MAX_PRICE_FEEDS: usize = 16;
+
 /// Zephyra Route Executor Program
 /// Execute swaps across multiple DEXs with MEV protection
 #[program]
 pub mod route_executor {
     use super::*;
 
-    /// Execute protected swap on Jupiter
-    pub fn execute_jupiter_swap(
-        ctx: Context<ExecuteJupiterSwap>,
+    /// Create the venue registry, making the program's upgrade authority its admin
+    pub fn initialize_venue_registry(ctx: Context<InitializeVenueRegistry>) -> Result<()> {
+        let venue_registry = &mut ctx.accounts.venue_registry;
+
+        venue_registry.admin = ctx.accounts.admin.key();
+        venue_registry.bump = ctx.bumps.venue_registry;
+
+        Ok(())
+    }
+
+    /// Register a venue the executor can route through
+    pub fn register_venue(
+        ctx: Context<RegisterVenue>,
+        program_id: Pubkey,
+        adapter: AdapterKind,
+        name: String,
+    ) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= MAX_VENUE_NAME_LEN,
+            ErrorCode::InvalidVenueName
+        );
+
+        let venue = &mut ctx.accounts.venue;
+        venue.program_id = program_id;
+        venue.adapter = adapter.clone();
+        venue.enabled = true;
+        venue.name = name.clone();
+        venue.bump = ctx.bumps.venue;
+
+        emit!(VenueRegistered {
+            program_id,
+            adapter,
+            name,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Change a venue's adapter, or enable and disable routing through it
+    pub fn update_venue(
+        ctx: Context<UpdateVenue>,
+        adapter: AdapterKind,
+        enabled: bool,
+    ) -> Result<()> {
+        let venue = &mut ctx.accounts.venue;
+        venue.adapter = adapter.clone();
+        venue.enabled = enabled;
+
+        emit!(VenueUpdated {
+            program_id: venue.program_id,
+            adapter,
+            enabled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create the route scoring config with its initial weights
+    pub fn initialize_scoring_config(
+        ctx: Context<InitializeScoringConfig>,
+        weights: ScoringWeights,
+    ) -> Result<()> {
+        require!(weights.is_valid(), ErrorCode::InvalidScoringWeights);
+
+        let scoring_config = &mut ctx.accounts.scoring_config;
+        scoring_config.weights = weights;
+        scoring_config.price_feeds = Vec::new();
+        scoring_config.bump = ctx.bumps.scoring_config;
+
+        emit!(ScoringConfigUpdated {
+            weights,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Change the weights routes are scored with
+    pub fn update_scoring_config(
+        ctx: Context<UpdateScoringConfig>,
+        weights: ScoringWeights,
+    ) -> Result<()> {
+        require!(weights.is_valid(), ErrorCode::InvalidScoringWeights);
+
+        ctx.accounts.scoring_config.weights = weights;
+
+        emit!(ScoringConfigUpdated {
+            weights,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Bind the price feed oracle checks use for swaps between two mints,
+    /// replacing the pair's current feed, or unbind it with no source
+    pub fn set_price_feed(
+        ctx: Context<UpdateScoringConfig>,
+        base_mint: Pubkey,
+        quote_mint: Pubkey,
+        source: Option<PriceFeedSource>,
+    ) -> Result<()> {
+        require_keys_neq!(base_mint, quote_mint, ErrorCode::InvalidPriceFeed);
+
+        // One feed per pair, whichever way round
+        let price_feeds = &mut ctx.accounts.scoring_config.price_feeds;
+        price_feeds.retain(|feed| !feed.prices(&base_mint, &quote_mint));
+        if let Some(source) = source {
+            require!(
+                price_feeds.len() < MAX_PRICE_FEEDS,
+                ErrorCode::TooManyPriceFeeds
+            );
+            price_feeds.push(PriceFeed {
+                base_mint,
+                quote_mint,
+                source,
+            });
+        }
+
+        emit!(PriceFeedUpdated {
+            base_mint,
+            quote_mint,
+            source,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Execute a protected swap of the escrowed input through a registered venue's
+    /// adapter, for at least the transaction's minimum output and under the
+    /// owner's execution guard. Signed by the transaction's owner or a keeper,
+    /// the transaction must have selected the venue and completes with the
+    /// swap's output. Input the swap leaves in the escrow is returned to the
+    /// owner. Venue-specific accounts are passed as remaining accounts.
+    pub fn execute_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteSwap<'info>>,
         transaction_id: [u8; 32],
-        route_data: Vec<u8>,
-        min_output: u64,
+        venue_data: Vec<u8>,
     ) -> Result<u64> {
-        let route_execution = &mut ctx.accounts.route_execution;
-        let clock = Clock::get()?;
-
-        // Initialize route execution account
-        route_execution.transaction_id = transaction_id;
-        route_execution.dex = DEX::Jupiter;
-        route_execution.input_amount = ctx.accounts.transaction_account.input_amount;
-        route_execution.executed_at = clock.unix_timestamp;
-
-        // Simulate Jupiter swap execution
-        // In production, this would interact with Jupiter's program
-        let simulated_output = simulate_jupiter_swap(
-            ctx.accounts.transaction_account.input_amount,
-            &route_data,
+        begin_execution(
+            &ctx.accounts.protection_manager,
+            &ctx.accounts.config,
+            &ctx.accounts.protection_account,
+            &ctx.accounts.transaction_account,
+            &ctx.accounts.payer,
+            SelectedRoute::Venue {
+                program_id: ctx.accounts.dex_program.key(),
+            },
         )?;
 
-        require!(simulated_output >= min_output, ErrorCode::SlippageExceeded);
+        let guard = ctx.accounts.protection_account.settings.execution_guard;
+        let input_amount = ctx.accounts.transaction_account.input_amount;
+        let min_output = ctx.accounts.transaction_account.min_output_amount;
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let authority = ctx.accounts.escrow_authority.to_account_info();
+        let source = ctx.accounts.input_escrow.to_account_info();
+        let destination = ctx.accounts.output_token_account.to_account_info();
+        let dex_program = ctx.accounts.dex_program.to_account_info();
+        let venue = &ctx.accounts.venue;
+        let leg = SwapLeg {
+            amount_in: input_amount,
+            min_output,
+            dex_program: &dex_program,
+            token_program: &token_program,
+            authority: &authority,
+            source: &source,
+            destination: &destination,
+            venue_accounts: ctx.remaining_accounts,
+            venue_data: &venue_data,
+        };
+        let hop = RouteHop {
+            venue: venue.program_id,
+            pool: adapter_for(&venue.adapter).pool(&leg).unwrap_or_default(),
+            input_mint: ctx.accounts.transaction_account.input_token,
+            output_mint: ctx.accounts.transaction_account.output_token,
+        };
 
-        route_execution.output_amount = simulated_output;
-        route_execution.price_impact_bps = calculate_price_impact(
-            ctx.accounts.transaction_account.input_amount,
-            simulated_output,
+        require!(
+            ctx.accounts.input_escrow.amount >= input_amount,
+            ErrorCode::InsufficientEscrow
         );
-        route_execution.mev_risk_score = calculate_mev_risk(&route_data);
-
-        // Generate execution proof
-        route_execution.proof = ExecutionProof {
-            pre_balance: ctx.accounts.transaction_account.input_amount,
-            post_balance: simulated_output,
-            signature: generate_execution_signature(&transaction_id, simulated_output),
-            timestamp: clock.unix_timestamp,
-        };
+        let pre_input = ctx.accounts.input_escrow.amount;
+        let owner = ctx.accounts.transaction_account.owner;
+        let bump = [ctx.bumps.escrow_authority];
+        let signer_seeds: &[&[u8]] = &[b"escrow", owner.as_ref(), transaction_id.as_ref(), &bump];
+        let hop_execution = execute_hop(
+            venue,
+            &hop,
+            &leg,
+            &guard,
+            &ctx.accounts.instructions_sysvar,
+            &ctx.accounts.transaction_account.owner,
+            &[signer_seeds],
+        )?;
 
-        emit!(RouteExecuted {
+        ctx.accounts.output_token_account.reload()?;
+        let output_amount = record_route_execution(
+            &mut ctx.accounts.route_execution,
             transaction_id,
-            dex: DEX::Jupiter,
-            input_amount: ctx.accounts.transaction_account.input_amount,
-            output_amount: simulated_output,
-            execution_time_ms: 50, // Simulated execution time
-            timestamp: clock.unix_timestamp,
-        });
+            vec![hop_execution],
+            Vec::new(),
+            calculate_mev_risk(&venue_data),
+            pre_input,
+            ctx.accounts.output_token_account.amount,
+        )?;
+        check_oracle_price(
+            &guard,
+            &ctx.accounts.scoring_config,
+            ctx.accounts.price_feed.as_ref(),
+            ctx.accounts.input_mint.as_ref(),
+            ctx.accounts.output_mint.as_ref(),
+            &ctx.accounts.route_execution,
+        )?;
 
-        Ok(simulated_output)
+        // Return what the swap did not consume to the owner
+        sweep_escrow(
+            &token_program,
+            &authority,
+            &source,
+            &ctx.accounts.refund_token_account.to_account_info(),
+            transaction_id,
+            owner,
+            &[signer_seeds],
+        )?;
+        complete_execution(
+            &ctx.accounts.protection_manager,
+            CompleteTransaction {
+                config: ctx.accounts.config.to_account_info(),
+                protection_account: ctx.accounts.protection_account.to_account_info(),
+                transaction_account: ctx.accounts.transaction_account.to_account_info(),
+                depositor: ctx.accounts.depositor.to_account_info(),
+                authority,
+            },
+            &ctx.accounts.route_execution,
+            &[signer_seeds],
+        )?;
+
+        Ok(output_amount)
     }
 
-    /// Execute protected swap on Raydium
-    pub fn execute_raydium_swap(
-        ctx: Context<ExecuteRaydiumSwap>,
+    /// Execute a multi-hop route, each hop swapping the previous hop's output
+    /// through its own venue, for at least the transaction's minimum output.
+    /// The transaction must have selected a route and completes with its output.
+    /// Per hop, remaining accounts hold the venue's registry entry and
+    /// program, for every hop but the last the escrow's token account for the
+    /// hop's output and the owner's account for that mint, then the hop's
+    /// venue accounts. Whatever the hops leave in the escrow's accounts is
+    /// returned to the owner once the route has executed.
+    pub fn execute_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteRoute<'info>>,
         transaction_id: [u8; 32],
-        pool_address: Pubkey,
-        min_output: u64,
+        hops: Vec<SwapHop>,
     ) -> Result<u64> {
-        let route_execution = &mut ctx.accounts.route_execution;
-        let clock = Clock::get()?;
-
-        route_execution.transaction_id = transaction_id;
-        route_execution.dex = DEX::Raydium;
-        route_execution.input_amount = ctx.accounts.transaction_account.input_amount;
-        route_execution.executed_at = clock.unix_timestamp;
-
-        // Simulate Raydium swap execution
-        let simulated_output = simulate_raydium_swap(
-            ctx.accounts.transaction_account.input_amount,
-            &pool_address,
+        begin_execution(
+            &ctx.accounts.protection_manager,
+            &ctx.accounts.config,
+            &ctx.accounts.protection_account,
+            &ctx.accounts.transaction_account,
+            &ctx.accounts.payer,
+            SelectedRoute::Route {
+                route_hash: route_hash(
+                    &hops.iter().map(|hop| hop.hop.clone()).collect::<Vec<_>>(),
+                    &[],
+                )?,
+            },
         )?;
 
-        require!(simulated_output >= min_output, ErrorCode::SlippageExceeded);
-
-        route_execution.output_amount = simulated_output;
-        route_execution.price_impact_bps = calculate_price_impact(
-            ctx.accounts.transaction_account.input_amount,
-            simulated_output,
+        let guard = ctx.accounts.protection_account.settings.execution_guard;
+        let transaction = &ctx.accounts.transaction_account;
+        require!(
+            !hops.is_empty() && hops.len() <= MAX_HOPS,
+            ErrorCode::InvalidRouteData
+        );
+        // Each hop must trade the previous hop's output, from the
+        // transaction's input token to its output token
+        require!(
+            hops[0].hop.input_mint == transaction.input_token
+                && hops[hops.len() - 1].hop.output_mint == transaction.output_token
+                && hops.iter().all(|swap_hop| swap_hop.hop.input_mint != swap_hop.hop.output_mint)
+                && hops
+                    .windows(2)
+                    .all(|pair| pair[0].hop.output_mint == pair[1].hop.input_mint),
+            ErrorCode::InvalidRouteData
+        );
+        let input_amount = transaction.input_amount;
+        require!(
+            ctx.accounts.input_escrow.amount >= input_amount,
+            ErrorCode::InsufficientEscrow
         );
-        route_execution.mev_risk_score = calculate_raydium_mev_risk(&pool_address);
 
-        route_execution.proof = ExecutionProof {
-            pre_balance: ctx.accounts.transaction_account.input_amount,
-            post_balance: simulated_output,
-            signature: generate_execution_signature(&transaction_id, simulated_output),
-            timestamp: clock.unix_timestamp,
-        };
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let authority = ctx.accounts.escrow_authority.to_account_info();
+        let output = ctx.accounts.output_token_account.to_account_info();
+        let bump = [ctx.bumps.escrow_authority];
+        let signer_seeds: &[&[u8]] = &[
+            b"escrow",
+            transaction.owner.as_ref(),
+            transaction_id.as_ref(),
+            &bump,
+        ];
+        let pre_input = ctx.accounts.input_escrow.amount;
+
+        let accounts = ctx.remaining_accounts;
+        let mut cursor = 0;
+        let source_escrow = ctx.accounts.input_escrow.to_account_info();
+        let mut source = source_escrow.clone();
+        let mut amount_in = input_amount;
+        let mut hop_executions = Vec::with_capacity(hops.len());
+        let mut intermediates = Vec::with_capacity(hops.len() - 1);
+        let mut mev_risk_score = 0;
 
-        emit!(RouteExecuted {
+        for (index, swap_hop) in hops.iter().enumerate() {
+            let venue = Account::<Venue>::try_from(
+                accounts.get(cursor).ok_or(ErrorCode::InvalidRouteData)?,
+            )?;
+            let dex_program = accounts.get(cursor + 1).ok_or(ErrorCode::InvalidRouteData)?;
+            cursor += 2;
+            let destination = if index + 1 == hops.len() {
+                output.clone()
+            } else {
+                let intermediate = accounts.get(cursor).ok_or(ErrorCode::InvalidRouteData)?;
+                let refund = accounts.get(cursor + 1).ok_or(ErrorCode::InvalidRouteData)?;
+                cursor += 2;
+                let token_account = Account::<TokenAccount>::try_from(intermediate)?;
+                require!(
+                    token_account.owner == authority.key()
+                        && token_account.mint == swap_hop.hop.output_mint,
+                    ErrorCode::InvalidRouteData
+                );
+                let refund_account = Account::<TokenAccount>::try_from(refund)?;
+                require!(
+                    refund_account.owner == transaction.owner
+                        && refund_account.mint == swap_hop.hop.output_mint,
+                    ErrorCode::InvalidRouteData
+                );
+                intermediates.push((intermediate.clone(), refund.clone()));
+                intermediate.clone()
+            };
+            let venue_accounts = accounts
+                .get(cursor..cursor + swap_hop.venue_account_count as usize)
+                .ok_or(ErrorCode::InvalidRouteData)?;
+            cursor += venue_accounts.len();
+
+            let leg = SwapLeg {
+                amount_in,
+                min_output: swap_hop.min_output,
+                dex_program,
+                token_program: &token_program,
+                authority: &authority,
+                source: &source,
+                destination: &destination,
+                venue_accounts,
+                venue_data: &swap_hop.venue_data,
+            };
+            let hop_execution = execute_hop(
+                &venue,
+                &swap_hop.hop,
+                &leg,
+                &guard,
+                &ctx.accounts.instructions_sysvar,
+                &transaction.owner,
+                &[signer_seeds],
+            )?;
+
+            // The next hop swaps exactly what this one delivered
+            amount_in = hop_execution.output_amount;
+            mev_risk_score = mev_risk_score.max(calculate_mev_risk(&swap_hop.venue_data));
+            hop_executions.push(hop_execution);
+            source = destination;
+        }
+        require!(cursor == accounts.len(), ErrorCode::InvalidRouteData);
+
+        ctx.accounts.output_token_account.reload()?;
+        let output_amount = record_route_execution(
+            &mut ctx.accounts.route_execution,
             transaction_id,
-            dex: DEX::Raydium,
-            input_amount: ctx.accounts.transaction_account.input_amount,
-            output_amount: simulated_output,
-            execution_time_ms: 75, // Simulated execution time
-            timestamp: clock.unix_timestamp,
-        });
+            hop_executions,
+            Vec::new(),
+            mev_risk_score,
+            pre_input,
+            ctx.accounts.output_token_account.amount,
+        )?;
+        require!(
+            output_amount >= transaction.min_output_amount,
+            ErrorCode::SlippageExceeded
+        );
+        check_oracle_price(
+            &guard,
+            &ctx.accounts.scoring_config,
+            ctx.accounts.price_feed.as_ref(),
+            ctx.accounts.input_mint.as_ref(),
+            ctx.accounts.output_mint.as_ref(),
+            &ctx.accounts.route_execution,
+        )?;
+
+        // Return what the hops did not consume to the owner
+        let refund = ctx.accounts.refund_token_account.to_account_info();
+        let escrows = std::iter::once((source_escrow, refund)).chain(intermediates);
+        for (escrow, destination) in escrows {
+            sweep_escrow(
+                &token_program,
+                &authority,
+                &escrow,
+                &destination,
+                transaction_id,
+                transaction.owner,
+                &[signer_seeds],
+            )?;
+        }
+        complete_execution(
+            &ctx.accounts.protection_manager,
+            CompleteTransaction {
+                config: ctx.accounts.config.to_account_info(),
+                protection_account: ctx.accounts.protection_account.to_account_info(),
+                transaction_account: ctx.accounts.transaction_account.to_account_info(),
+                depositor: ctx.accounts.depositor.to_account_info(),
+                authority,
+            },
+            &ctx.accounts.route_execution,
+            &[signer_seeds],
+        )?;
 
-        Ok(simulated_output)
+        Ok(output_amount)
     }
 
-    /// Execute protected swap on Orca
-    pub fn execute_orca_swap(
-        ctx: Context<ExecuteOrcaSwap>,
+    /// Execute a split route, dividing the escrowed input across venues by
+    /// basis-point weights and swapping every leg straight into the output
+    /// account, for at least the transaction's minimum output in total. The
+    /// transaction must have selected a route and completes with its output. Per
+    /// leg, remaining accounts hold the venue's registry entry and program,
+    /// then the leg's venue accounts. Input the legs leave in the escrow is
+    /// returned to the owner once the route has executed.
+    pub fn execute_split_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteRoute<'info>>,
         transaction_id: [u8; 32],
-        pool_address: Pubkey,
-        min_output: u64,
+        splits: Vec<SwapSplit>,
     ) -> Result<u64> {
-        let route_execution = &mut ctx.accounts.route_execution;
-        let clock = Clock::get()?;
-
-        route_execution.transaction_id = transaction_id;
-        route_execution.dex = DEX::Orca;
-        route_execution.input_amount = ctx.accounts.transaction_account.input_amount;
-        route_execution.executed_at = clock.unix_timestamp;
-
-        // Simulate Orca swap execution
-        let simulated_output = simulate_orca_swap(
-            ctx.accounts.transaction_account.input_amount,
-            &pool_address,
+        begin_execution(
+            &ctx.accounts.protection_manager,
+            &ctx.accounts.config,
+            &ctx.accounts.protection_account,
+            &ctx.accounts.transaction_account,
+            &ctx.accounts.payer,
+            SelectedRoute::Route {
+                route_hash: route_hash(
+                    &splits.iter().map(|split| split.swap.hop.clone()).collect::<Vec<_>>(),
+                    &splits.iter().map(|split| split.weight_bps).collect::<Vec<_>>(),
+                )?,
+            },
         )?;
 
-        require!(simulated_output >= min_output, ErrorCode::SlippageExceeded);
+        let guard = ctx.accounts.protection_account.settings.execution_guard;
+        let transaction = &ctx.accounts.transaction_account;
+        let weights_bps: Vec<u16> = splits.iter().map(|split| split.weight_bps).collect();
+        require!(
+            is_valid_split(&weights_bps)
+                && splits.iter().all(|split| {
+                    split.swap.hop.input_mint == transaction.input_token
+                        && split.swap.hop.output_mint == transaction.output_token
+                }),
+            ErrorCode::InvalidRouteData
+        );
+        let input_amount = transaction.input_amount;
+        require!(
+            ctx.accounts.input_escrow.amount >= input_amount,
+            ErrorCode::InsufficientEscrow
+        );
+
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let authority = ctx.accounts.escrow_authority.to_account_info();
+        let source = ctx.accounts.input_escrow.to_account_info();
+        let destination = ctx.accounts.output_token_account.to_account_info();
+        let bump = [ctx.bumps.escrow_authority];
+        let signer_seeds: &[&[u8]] = &[
+            b"escrow",
+            transaction.owner.as_ref(),
+            transaction_id.as_ref(),
+            &bump,
+        ];
+        let pre_input = ctx.accounts.input_escrow.amount;
+
+        let accounts = ctx.remaining_accounts;
+        let mut cursor = 0;
+        let mut hop_executions = Vec::with_capacity(splits.len());
+        let mut mev_risk_score = 0;
+
+        for (split, amount_in) in splits.iter().zip(split_amounts(input_amount, &weights_bps)) {
+            let venue = Account::<Venue>::try_from(
+                accounts.get(cursor).ok_or(ErrorCode::InvalidRouteData)?,
+            )?;
+            let dex_program = accounts.get(cursor + 1).ok_or(ErrorCode::InvalidRouteData)?;
+            cursor += 2;
+            let venue_accounts = accounts
+                .get(cursor..cursor + split.swap.venue_account_count as usize)
+                .ok_or(ErrorCode::InvalidRouteData)?;
+            cursor += venue_accounts.len();
+
+            let leg = SwapLeg {
+                amount_in,
+                min_output: split.swap.min_output,
+                dex_program,
+                token_program: &token_program,
+                authority: &authority,
+                source: &source,
+                destination: &destination,
+                venue_accounts,
+                venue_data: &split.swap.venue_data,
+            };
+            hop_executions.push(execute_hop(
+                &venue,
+                &split.swap.hop,
+                &leg,
+                &guard,
+                &ctx.accounts.instructions_sysvar,
+                &transaction.owner,
+                &[signer_seeds],
+            )?);
+            mev_risk_score = mev_risk_score.max(calculate_mev_risk(&split.swap.venue_data));
+        }
+        require!(cursor == accounts.len(), ErrorCode::InvalidRouteData);
 
-        route_execution.output_amount = simulated_output;
-        route_execution.price_impact_bps = calculate_price_impact(
-            ctx.accounts.transaction_account.input_amount,
-            simulated_output,
+        ctx.accounts.output_token_account.reload()?;
+        let output_amount = record_route_execution(
+            &mut ctx.accounts.route_execution,
+            transaction_id,
+            hop_executions,
+            weights_bps,
+            mev_risk_score,
+            pre_input,
+            ctx.accounts.output_token_account.amount,
+        )?;
+        require!(
+            output_amount >= transaction.min_output_amount,
+            ErrorCode::SlippageExceeded
         );
-        route_execution.mev_risk_score = calculate_orca_mev_risk(&pool_address);
+        check_oracle_price(
+            &guard,
+            &ctx.accounts.scoring_config,
+            ctx.accounts.price_feed.as_ref(),
+            ctx.accounts.input_mint.as_ref(),
+            ctx.accounts.output_mint.as_ref(),
+            &ctx.accounts.route_execution,
+        )?;
 
-        route_execution.proof = ExecutionProof {
-            pre_balance: ctx.accounts.transaction_account.input_amount,
-            post_balance: simulated_output,
-            signature: generate_execution_signature(&transaction_id, simulated_output),
-            timestamp: clock.unix_timestamp,
-        };
+        // Return what the legs did not consume to the owner
+        sweep_escrow(
+            &token_program,
+            &authority,
+            &source,
+            &ctx.accounts.refund_token_account.to_account_info(),
+            transaction_id,
+            transaction.owner,
+            &[signer_seeds],
+        )?;
+        complete_execution(
+            &ctx.accounts.protection_manager,
+            CompleteTransaction {
+                config: ctx.accounts.config.to_account_info(),
+                protection_account: ctx.accounts.protection_account.to_account_info(),
+                transaction_account: ctx.accounts.transaction_account.to_account_info(),
+                depositor: ctx.accounts.depositor.to_account_info(),
+                authority,
+            },
+            &ctx.accounts.route_execution,
+            &[signer_seeds],
+        )?;
+
+        Ok(output_amount)
+    }
 
-        emit!(RouteExecuted {
+    /// Return an escrow-owned token account's balance to the transaction's
+    /// owner and close it, after a cancellation, expiry or failed execution,
+    /// or to collect what an execution left behind. Signed by the owner only.
+    pub fn refund_escrow(ctx: Context<RefundEscrow>, transaction_id: [u8; 32]) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let bump = [ctx.bumps.escrow_authority];
+        let signer_seeds: &[&[u8]] = &[b"escrow", owner.as_ref(), transaction_id.as_ref(), &bump];
+        let amount = ctx.accounts.escrow_token_account.amount;
+
+        if amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.escrow_token_account.to_account_info(),
+                        to: ctx.accounts.destination.to_account_info(),
+                        authority: ctx.accounts.escrow_authority.to_account_info(),
+                    },
+                    &[signer_seeds],
+                ),
+                amount,
+            )?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow_token_account.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            },
+            &[signer_seeds],
+        ))?;
+
+        emit!(EscrowRefunded {
             transaction_id,
-            dex: DEX::Orca,
-            input_amount: ctx.accounts.transaction_account.input_amount,
-            output_amount: simulated_output,
-            execution_time_ms: 60, // Simulated execution time
-            timestamp: clock.unix_timestamp,
+            owner,
+            mint: ctx.accounts.escrow_token_account.mint,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
         });
 
-        Ok(simulated_output)
+        Ok(())
     }
 
-    /// Compare routes and select best
-    pub fn select_best_route(
-        ctx: Context<SelectRoute>,
+    /// Compare routes and select the one with the best weighted score. The
+    /// registry entries of every venue the routes use are passed as remaining
+    /// accounts.
+    pub fn select_best_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SelectRoute<'info>>,
         routes: Vec<RouteOption>,
     ) -> Result<RouteSelection> {
         require!(!routes.is_empty(), ErrorCode::NoRoutesProvided);
         require!(routes.len() <= 10, ErrorCode::TooManyRoutes);
+        require!(
+            routes.iter().all(|route| {
+                !route.hops.is_empty()
+                    && route.hops.len() <= MAX_HOPS
+                    && route.mev_risk_score <= 100
+                    && route.price_impact_bps <= 10_000
+                    && (route.split_weights_bps.is_empty()
+                        || (route.split_weights_bps.len() == route.hops.len()
+                            && is_valid_split(&route.split_weights_bps)))
+            }),
+            ErrorCode::InvalidRouteData
+        );
+
+        // Only enabled venues can be selected
+        let venues = ctx
+            .remaining_accounts
+            .iter()
+            .map(Account::<Venue>::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let venue_of = |program_id: &Pubkey| {
+            venues
+                .iter()
+                .find(|venue| venue.program_id == *program_id)
+                .ok_or(ErrorCode::UnknownVenue)
+        };
+        for hop in routes.iter().flat_map(|route| &route.hops) {
+            require!(venue_of(&hop.venue)?.enabled, ErrorCode::VenueDisabled);
+        }
+
+        // Output and liquidity depth are scored relative to the best route's
+        let weights = &ctx.accounts.scoring_config.weights;
+        let best_output = routes.iter().map(|route| route.estimated_output).max().unwrap_or(0);
+        let best_depth = routes.iter().map(|route| route.liquidity_depth).max().unwrap_or(0);
 
-        let clock = Clock::get()?;
         let mut best_route = routes[0].clone();
-        let mut best_score = calculate_route_score(&routes[0]);
+        let mut best_score = score_route(&routes[0], weights, best_output, best_depth)?;
 
-        // Find the route with the best score (lowest MEV risk + highest output)
+        // Find the route with the best score, the first one on ties
         for route in routes.iter().skip(1) {
-            let score = calculate_route_score(route);
-            if score > best_score {
+            let score = score_route(route, weights, best_output, best_depth)?;
+            if score.total > best_score.total {
                 best_score = score;
                 best_route = route.clone();
             }
         }
 
+        let names = best_route
+            .hops
+            .iter()
+            .map(|hop| Ok(venue_of(&hop.venue)?.name.as_str()))
+            .collect::<Result<Vec<_>>>()?;
         let selection = RouteSelection {
-            selected_dex: best_route.dex.clone(),
+            route_hash: route_hash(&best_route.hops, &best_route.split_weights_bps)?,
+            selected_hops: best_route.hops.clone(),
+            split_weights_bps: best_route.split_weights_bps.clone(),
             estimated_output: best_route.estimated_output,
+            score: best_score,
             reasoning: format!(
-                "Selected {} due to optimal MEV risk ({}) and output amount ({})",
-                match best_route.dex {
-                    DEX::Jupiter => "Jupiter",
-                    DEX::Raydium => "Raydium",
-                    DEX::Orca => "Orca",
+                "Selected {} scoring {} of {}: output {} ({}), MEV risk {} ({}), price impact {} ({} bps), liquidity {} ({})",
+                if best_route.split_weights_bps.is_empty() {
+                    names.join(" -> ")
+                } else {
+                    names.join(" + ")
                 },
+                best_score.total,
+                SCORE_SCALE,
+                best_score.output,
+                best_route.estimated_output,
+                best_score.mev_risk,
                 best_route.mev_risk_score,
-                best_route.estimated_output
+                best_score.price_impact,
+                best_route.price_impact_bps,
+                best_score.liquidity_depth,
+                best_route.liquidity_depth
             ),
         };
 
         emit!(RouteSelected {
             transaction_id: ctx.accounts.transaction_account.id,
-            selected_dex: best_route.dex,
+            selected_hops: best_route.hops,
+            split_weights_bps: best_route.split_weights_bps,
+            score: best_score,
             alternatives_count: routes.len() as u8,
             reasoning: selection.reasoning.clone(),
         });
@@ -205,36 +752,304 @@ pub mod route_executor {
     }
 }
 
-/// Calculate route score based on MEV risk and output amount
-fn calculate_route_score(route: &RouteOption) -> u64 {
-    // Higher output is better, lower MEV risk is better
-    // Score = output_amount * (100 - mev_risk_score) / 100
-    route.estimated_output * (100 - route.mev_risk_score as u64) / 100
+/// Check the transaction selected `route` and is approved for execution,
+/// moving a routed or batched transaction to Executing in the protection
+/// manager, signed by the payer
+fn begin_execution<'info>(
+    protection_manager: &Program<'info, ZephyraProtectionManager>,
+    config: &Account<'info, ProtocolConfig>,
+    protection_account: &Account<'info, ProtectionAccount>,
+    transaction_account: &Account<'info, Transaction>,
+    payer: &Signer<'info>,
+    route: SelectedRoute,
+) -> Result<()> {
+    require!(
+        transaction_account.selected_route == Some(route),
+        ErrorCode::RouteNotSelected
+    );
+    match transaction_account.status {
+        TransactionStatus::Executing => Ok(()),
+        TransactionStatus::RouteSelected | TransactionStatus::Batched => {
+            zephyra_protection_manager::cpi::update_transaction_status(
+                CpiContext::new(
+                    protection_manager.to_account_info(),
+                    zephyra_protection_manager::cpi::accounts::UpdateTransactionStatus {
+                        config: config.to_account_info(),
+                        protection_account: protection_account.to_account_info(),
+                        transaction_account: transaction_account.to_account_info(),
+                        authority: payer.to_account_info(),
+                    },
+                ),
+                transaction_account.id,
+                TransactionStatus::Executing,
+            )
+        }
+        _ => err!(ErrorCode::TransactionNotApproved),
+    }
+}
+
+/// Swap `leg` through the hop's registered venue and measure the amounts it
+/// moved from the token balances, checked against the hop's pool and minimum
+/// output
+fn execute_hop<'info>(
+    venue: &Venue,
+    hop: &RouteHop,
+    leg: &SwapLeg<'_, 'info>,
+    guard: &ExecutionGuard,
+    instructions_sysvar: &AccountInfo<'info>,
+    owner: &Pubkey,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<HopExecution> {
+    require_keys_eq!(venue.program_id, hop.venue, ErrorCode::InvalidRouteData);
+    require_keys_eq!(
+        leg.dex_program.key(),
+        venue.program_id,
+        ErrorCode::InvalidDexProgram
+    );
+    require!(venue.enabled, ErrorCode::VenueDisabled);
+    let adapter = adapter_for(&venue.adapter);
+    let pool = adapter.pool(leg);
+    if let Some(pool) = &pool {
+        require_keys_eq!(*pool, hop.pool, ErrorCode::InvalidRouteData);
+    }
+    enforce_execution_guard(guard, instructions_sysvar, owner, pool.as_ref())?;
+
+    // Venues with on-chain pools are quoted from their live accounts first
+    let quote = adapter.quote(leg)?;
+    if let Some(quote) = &quote {
+        require!(quote.amount_in == leg.amount_in, ErrorCode::QuoteFailed);
+        require!(quote.amount_out >= leg.min_output, ErrorCode::SlippageExceeded);
+    }
+
+    // Swap through the venue, then read the real amounts back from the balances
+    let pre_input = token_amount(leg.source)?;
+    let pre_output = token_amount(leg.destination)?;
+    invoke_swap(adapter.as_ref(), leg, signer_seeds)?;
+
+    let input_amount = pre_input
+        .checked_sub(token_amount(leg.source)?)
+        .ok_or(ErrorCode::ExecutionFailed)?;
+    let output_amount = token_amount(leg.destination)?
+        .checked_sub(pre_output)
+        .ok_or(ErrorCode::ExecutionFailed)?;
+    require!(input_amount <= leg.amount_in, ErrorCode::ExecutionFailed);
+    require!(output_amount >= leg.min_output, ErrorCode::SlippageExceeded);
+
+    Ok(HopExecution {
+        hop: hop.clone(),
+        input_amount,
+        output_amount,
+        quoted_output: quote.map(|quote| quote.amount_out),
+        price_impact_bps: match quote {
+            Some(quote) => quote.price_impact_bps,
+            None => calculate_price_impact(input_amount, output_amount),
+        },
+    })
+}
+
+/// Move what an execution left in an escrow-owned account to the owner's account
+fn sweep_escrow<'info>(
+    token_program: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    escrow: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    transaction_id: [u8; 32],
+    owner: Pubkey,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let amount = token_amount(escrow)?;
+    if amount == 0 {
+        return Ok(());
+    }
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            Transfer {
+                from: escrow.clone(),
+                to: destination.clone(),
+                authority: authority.clone(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+
+    emit!(EscrowRefunded {
+        transaction_id,
+        owner,
+        mint: token_mint(escrow)?,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Complete the transaction with the execution's measured output. The escrow
+/// authority signs, which the protection manager only accepts from this program.
+fn complete_execution<'info>(
+    protection_manager: &Program<'info, ZephyraProtectionManager>,
+    accounts: CompleteTransaction<'info>,
+    route_execution: &RouteExecution,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let proof_hash = hashv(&[&route_execution.proof.try_to_vec()?]).to_bytes();
+    zephyra_protection_manager::cpi::complete_transaction(
+        CpiContext::new_with_signer(protection_manager.to_account_info(), accounts, signer_seeds),
+        route_execution.transaction_id,
+        route_execution.output_amount,
+        proof_hash,
+    )
+}
+
+/// Check the route's amounts against the oracle price when the guard sets an
+/// oracle check, which needs the price feed bound to the mints and both mints
+fn check_oracle_price(
+    guard: &ExecutionGuard,
+    scoring_config: &ScoringConfig,
+    price_feed: Option<&UncheckedAccount>,
+    input_mint: Option<&Account<Mint>>,
+    output_mint: Option<&Account<Mint>>,
+    route_execution: &RouteExecution,
+) -> Result<()> {
+    let Some(oracle) = &guard.oracle else {
+        return Ok(());
+    };
+    let (Some(price_feed), Some(input_mint), Some(output_mint)) =
+        (price_feed, input_mint, output_mint)
+    else {
+        return err!(ErrorCode::MissingOracleAccounts);
+    };
+    let feed = scoring_config
+        .price_feed(&input_mint.key(), &output_mint.key())
+        .ok_or(ErrorCode::UnknownPriceFeed)?;
+
+    enforce_oracle_guard(
+        oracle,
+        feed,
+        price_feed,
+        input_mint,
+        output_mint,
+        route_execution.input_amount,
+        route_execution.output_amount,
+    )
 }
 
-/// Simulate Jupiter swap execution
-fn simulate_jupiter_swap(input_amount: u64, route_data: &[u8]) -> Result<u64> {
-    // Simplified simulation - in production this would parse Jupiter route data
-    // and simulate the actual swap
-    let base_output = input_amount * 99 / 100; // 1% fee
-    let slippage_factor = 100 - (route_data.len() as u64 % 5); // Simulate slippage
-    Ok(base_output * slippage_factor / 100)
+/// Record the executed hops, chained or split by `split_weights_bps`, and
+/// emit the route's execution
+fn record_route_execution(
+    route_execution: &mut RouteExecution,
+    transaction_id: [u8; 32],
+    hops: Vec<HopExecution>,
+    split_weights_bps: Vec<u16>,
+    mev_risk_score: u8,
+    pre_balance: u64,
+    post_balance: u64,
+) -> Result<u64> {
+    let clock = Clock::get()?;
+    let (input_amount, output_amount, price_impact_bps) = if split_weights_bps.is_empty() {
+        (
+            hops.first().map_or(0, |hop| hop.input_amount),
+            hops.last().map_or(0, |hop| hop.output_amount),
+            compound_price_impact(&hops),
+        )
+    } else {
+        let total = |amount: fn(&HopExecution) -> u64| {
+            hops.iter()
+                .try_fold(0u64, |total, hop| total.checked_add(amount(hop)))
+                .ok_or(ErrorCode::ExecutionFailed)
+        };
+        (
+            total(|hop| hop.input_amount)?,
+            total(|hop| hop.output_amount)?,
+            split_price_impact(&hops),
+        )
+    };
+
+    route_execution.transaction_id = transaction_id;
+    route_execution.input_amount = input_amount;
+    route_execution.output_amount = output_amount;
+    route_execution.price_impact_bps = price_impact_bps;
+    route_execution.mev_risk_score = mev_risk_score;
+    route_execution.executed_at = clock.unix_timestamp;
+    route_execution.proof = ExecutionProof {
+        pre_balance,
+        post_balance,
+        signature: generate_execution_signature(&transaction_id, output_amount),
+        timestamp: clock.unix_timestamp,
+    };
+
+    emit!(RouteExecuted {
+        transaction_id,
+        hops: hops.iter().map(|execution| execution.hop.clone()).collect(),
+        split_weights_bps: split_weights_bps.clone(),
+        input_amount,
+        output_amount,
+        timestamp: clock.unix_timestamp,
+    });
+    route_execution.hops = hops;
+    route_execution.split_weights_bps = split_weights_bps;
+
+    Ok(output_amount)
 }
 
-/// Simulate Raydium swap execution
-fn simulate_raydium_swap(input_amount: u64, _pool_address: &Pubkey) -> Result<u64> {
-    // Simplified simulation for Raydium
-    let base_output = input_amount * 98 / 100; // 2% fee
-    let slippage_factor = 100 - (input_amount % 3); // Simulate slippage
-    Ok(base_output * slippage_factor / 100)
+/// Score a route by its weighted output, MEV risk, price impact and liquidity
+/// depth. Output and depth are scaled against the best among the routes
+/// considered, MEV risk and price impact against their worst possible value.
+fn score_route(
+    route: &RouteOption,
+    weights: &ScoringWeights,
+    best_output: u64,
+    best_depth: u64,
+) -> Result<ScoreBreakdown> {
+    let mev_safety = 100u128
+        .checked_sub(route.mev_risk_score as u128)
+        .ok_or(ErrorCode::InvalidRouteData)?;
+    let impact_retained = 10_000u128
+        .checked_sub(route.price_impact_bps as u128)
+        .ok_or(ErrorCode::InvalidRouteData)?;
+
+    let output = weighted_score(
+        route.estimated_output as u128,
+        best_output as u128,
+        weights.output_bps,
+    )?;
+    let mev_risk = weighted_score(mev_safety, 100, weights.mev_risk_bps)?;
+    let price_impact = weighted_score(impact_retained, 10_000, weights.price_impact_bps)?;
+    let liquidity_depth = weighted_score(
+        route.liquidity_depth as u128,
+        best_depth as u128,
+        weights.liquidity_depth_bps,
+    )?;
+
+    let total = [mev_risk, price_impact, liquidity_depth]
+        .iter()
+        .try_fold(output, |total, component| total.checked_add(*component))
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    Ok(ScoreBreakdown {
+        output,
+        mev_risk,
+        price_impact,
+        liquidity_depth,
+        total,
+    })
 }
 
-/// Simulate Orca swap execution
-fn simulate_orca_swap(input_amount: u64, _pool_address: &Pubkey) -> Result<u64> {
-    // Simplified simulation for Orca
-    let base_output = input_amount * 97 / 100; // 3% fee
-    let slippage_factor = 100 - (input_amount % 4); // Simulate slippage
-    Ok(base_output * slippage_factor / 100)
+/// `value` out of `best` on the `SCORE_SCALE`, weighted by `weight_bps`
+fn weighted_score(value: u128, best: u128, weight_bps: u16) -> Result<u64> {
+    if best == 0 {
+        return Ok(0);
+    }
+
+    value
+        .min(best)
+        .checked_mul(SCORE_SCALE as u128)
+        .and_then(|scaled| scaled.checked_div(best))
+        .and_then(|scaled| scaled.checked_mul(weight_bps as u128))
+        .and_then(|weighted| weighted.checked_div(10_000))
+        .and_then(|weighted| u64::try_from(weighted).ok())
+        .ok_or_else(|| ErrorCode::MathOverflow.into())
 }
 
 /// Calculate price impact in basis points
@@ -247,23 +1062,62 @@ fn calculate_price_impact(input_amount: u64, output_amount: u64) -> u16 {
     impact.min(10000) // Cap at 100%
 }
 
-/// Calculate MEV risk for Jupiter route
-fn calculate_mev_risk(route_data: &[u8]) -> u8 {
-    // Simplified MEV risk calculation based on route complexity
-    let complexity = route_data.len() as u8;
-    (complexity * 2).min(100)
+/// Price impact of hops executed in sequence, each hop's impact applying to
+/// what is left after the previous ones
+fn compound_price_impact(hops: &[HopExecution]) -> u16 {
+    let retained = hops.iter().fold(10_000u32, |retained, hop| {
+        retained * (10_000 - hop.price_impact_bps.min(10_000) as u32) / 10_000
+    });
+    (10_000 - retained) as u16
+}
+
+/// Price impact of split legs, weighted by the input each leg swapped
+fn split_price_impact(legs: &[HopExecution]) -> u16 {
+    let (weighted, input) = legs.iter().fold((0u128, 0u128), |(weighted, input), leg| {
+        (
+            weighted + leg.price_impact_bps as u128 * leg.input_amount as u128,
+            input + leg.input_amount as u128,
+        )
+    });
+    if input == 0 {
+        return 0;
+    }
+    (weighted / input).min(10_000) as u16
+}
+
+/// Split weights must cover the whole input across two to
+/// [`MAX_SPLIT_LEGS`] legs, none of them empty
+fn is_valid_split(weights_bps: &[u16]) -> bool {
+    (2..=MAX_SPLIT_LEGS).contains(&weights_bps.len())
+        && weights_bps.iter().all(|weight| *weight > 0)
+        && weights_bps.iter().map(|weight| *weight as u32).sum::<u32>() == 10_000
 }
 
-/// Calculate MEV risk for Raydium pool
-fn calculate_raydium_mev_risk(_pool_address: &Pubkey) -> u8 {
-    // Simplified MEV risk calculation for Raydium
-    30 + (Clock::get().unwrap().unix_timestamp % 40) as u8
+/// Hash of a route's hops and split weights, which a transaction selecting
+/// the route stores and its execution must match
+pub fn route_hash(hops: &[RouteHop], split_weights_bps: &[u16]) -> Result<[u8; 32]> {
+    Ok(hashv(&[&hops.try_to_vec()?, &split_weights_bps.try_to_vec()?]).to_bytes())
 }
 
-/// Calculate MEV risk for Orca pool
-fn calculate_orca_mev_risk(_pool_address: &Pubkey) -> u8 {
-    // Simplified MEV risk calculation for Orca
-    25 + (Clock::get().unwrap().unix_timestamp % 35) as u8
+/// Input of each split leg by weight, the rounding remainder going to the
+/// last leg so the legs swap exactly `input_amount`
+fn split_amounts(input_amount: u64, weights_bps: &[u16]) -> Vec<u64> {
+    let mut amounts: Vec<u64> = weights_bps
+        .iter()
+        .map(|weight| (input_amount as u128 * *weight as u128 / 10_000) as u64)
+        .collect();
+    let allocated: u64 = amounts.iter().sum();
+    if let Some(last) = amounts.last_mut() {
+        *last += input_amount - allocated;
+    }
+    amounts
+}
+
+/// Calculate MEV risk from route complexity
+fn calculate_mev_risk(route_data: &[u8]) -> u8 {
+    // Simplified MEV risk calculation based on route complexity
+    let complexity = route_data.len().min(50) as u8;
+    (complexity * 2).min(100)
 }
 
 /// Generate execution signature
@@ -285,101 +1139,446 @@ fn generate_execution_signature(transaction_id: &[u8; 32], output_amount: u64) -
 }
 
 #[derive(Accounts)]
-#[instruction(transaction_id: [u8; 32], route_data: Vec<u8>, min_output: u64)]
-pub struct ExecuteJupiterSwap<'info> {
+pub struct InitializeVenueRegistry<'info> {
     #[account(
-        seeds = [b"transaction", transaction_account.owner.as_ref(), &transaction_id],
+        init,
+        payer = admin,
+        space = 8 + VenueRegistry::INIT_SPACE,
+        seeds = [b"venue_registry"],
         bump
     )]
-    pub transaction_account: Account<'info, Transaction>,
+    pub venue_registry: Account<'info, VenueRegistry>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::RouteExecutor>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey)]
+pub struct RegisterVenue<'info> {
+    #[account(
+        seeds = [b"venue_registry"],
+        bump = venue_registry.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub venue_registry: Account<'info, VenueRegistry>,
     #[account(
         init,
-        payer = payer,
-        space = 8 + RouteExecution::INIT_SPACE,
-        seeds = [b"route_execution", transaction_id.as_ref(), b"jupiter"],
+        payer = admin,
+        space = 8 + Venue::INIT_SPACE,
+        seeds = [b"venue", program_id.as_ref()],
         bump
     )]
-    pub route_execution: Account<'info, RouteExecution>,
+    pub venue: Account<'info, Venue>,
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub admin: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(transaction_id: [u8; 32], pool_address: Pubkey, min_output: u64)]
-pub struct ExecuteRaydiumSwap<'info> {
+pub struct UpdateVenue<'info> {
     #[account(
-        seeds = [b"transaction", transaction_account.owner.as_ref(), &transaction_id],
+        seeds = [b"venue_registry"],
+        bump = venue_registry.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub venue_registry: Account<'info, VenueRegistry>,
+    #[account(
+        mut,
+        seeds = [b"venue", venue.program_id.as_ref()],
+        bump = venue.bump
+    )]
+    pub venue: Account<'info, Venue>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeScoringConfig<'info> {
+    #[account(
+        seeds = [b"venue_registry"],
+        bump = venue_registry.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub venue_registry: Account<'info, VenueRegistry>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ScoringConfig::INIT_SPACE,
+        seeds = [b"scoring_config"],
         bump
     )]
+    pub scoring_config: Account<'info, ScoringConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateScoringConfig<'info> {
+    #[account(
+        seeds = [b"venue_registry"],
+        bump = venue_registry.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub venue_registry: Account<'info, VenueRegistry>,
+    #[account(mut, seeds = [b"scoring_config"], bump = scoring_config.bump)]
+    pub scoring_config: Account<'info, ScoringConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct ExecuteSwap<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        seeds::program = zephyra_protection_manager::ID
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"transaction", transaction_account.owner.as_ref(), &transaction_id],
+        bump,
+        seeds::program = zephyra_protection_manager::ID
+    )]
     pub transaction_account: Account<'info, Transaction>,
+    /// Owner's protection settings, holding the execution guard their swaps run under
+    #[account(
+        mut,
+        seeds = [b"protection", transaction_account.owner.as_ref()],
+        bump = protection_account.bump,
+        seeds::program = zephyra_protection_manager::ID
+    )]
+    pub protection_account: Account<'info, ProtectionAccount>,
+    /// CHECK: Venue program, checked against its registry entry before the CPI
+    pub dex_program: UncheckedAccount<'info>,
+    #[account(seeds = [b"venue", dex_program.key().as_ref()], bump = venue.bump)]
+    pub venue: Account<'info, Venue>,
     #[account(
         init,
         payer = payer,
         space = 8 + RouteExecution::INIT_SPACE,
-        seeds = [b"route_execution", transaction_id.as_ref(), b"raydium"],
+        seeds = [b"route_execution", transaction_id.as_ref(), dex_program.key().as_ref()],
         bump
     )]
     pub route_execution: Account<'info, RouteExecution>,
-    #[account(mut)]
+    /// CHECK: PDA owning the escrowed input, signs the venue CPI
+    #[account(
+        seeds = [b"escrow", transaction_account.owner.as_ref(), transaction_id.as_ref()],
+        bump
+    )]
+    pub escrow_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint = transaction_account.input_token,
+        token::authority = escrow_authority
+    )]
+    pub input_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = transaction_account.output_token,
+        token::authority = transaction_account.owner
+    )]
+    pub output_token_account: Account<'info, TokenAccount>,
+    /// Owner's input token account, receiving the input the swap leaves in the escrow
+    #[account(
+        mut,
+        token::mint = transaction_account.input_token,
+        token::authority = transaction_account.owner
+    )]
+    pub refund_token_account: Account<'info, TokenAccount>,
+    /// Price feeds bound to mint pairs, read by the oracle check
+    #[account(seeds = [b"scoring_config"], bump = scoring_config.bump)]
+    pub scoring_config: Account<'info, ScoringConfig>,
+    /// CHECK: Pyth or Switchboard price account, decoded by the oracle program
+    /// owning it and checked against the mints' bound feed. Only needed when
+    /// the owner's guard sets an oracle check.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    #[account(address = transaction_account.input_token)]
+    pub input_mint: Option<Account<'info, Mint>>,
+    #[account(address = transaction_account.output_token)]
+    pub output_mint: Option<Account<'info, Mint>>,
+    /// Transaction owner or keeper, paying for the route execution
+    #[account(
+        mut,
+        constraint = payer.key() == transaction_account.owner
+            || config.is_keeper(&payer.key()) @ ErrorCode::Unauthorized
+    )]
     pub payer: Signer<'info>,
+    /// CHECK: Receives the transaction's refunded deposit on completion, validated against the transaction
+    #[account(mut, address = transaction_account.depositor)]
+    pub depositor: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, used by the execution guard
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    /// Moves the transaction to Executing, then completes it
+    pub protection_manager: Program<'info, ZephyraProtectionManager>,
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(transaction_id: [u8; 32], pool_address: Pubkey, min_output: u64)]
-pub struct ExecuteOrcaSwap<'info> {
+#[instruction(transaction_id: [u8; 32])]
+pub struct ExecuteRoute<'info> {
     #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        seeds::program = zephyra_protection_manager::ID
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
         seeds = [b"transaction", transaction_account.owner.as_ref(), &transaction_id],
-        bump
+        bump,
+        seeds::program = zephyra_protection_manager::ID
     )]
     pub transaction_account: Account<'info, Transaction>,
+    /// Owner's protection settings, holding the execution guard their swaps run under
+    #[account(
+        mut,
+        seeds = [b"protection", transaction_account.owner.as_ref()],
+        bump = protection_account.bump,
+        seeds::program = zephyra_protection_manager::ID
+    )]
+    pub protection_account: Account<'info, ProtectionAccount>,
     #[account(
         init,
         payer = payer,
         space = 8 + RouteExecution::INIT_SPACE,
-        seeds = [b"route_execution", transaction_id.as_ref(), b"orca"],
+        seeds = [b"route_execution", transaction_id.as_ref(), b"route"],
         bump
     )]
     pub route_execution: Account<'info, RouteExecution>,
-    #[account(mut)]
+    /// CHECK: PDA owning the escrowed input and intermediate accounts, signs the venue CPIs
+    #[account(
+        seeds = [b"escrow", transaction_account.owner.as_ref(), transaction_id.as_ref()],
+        bump
+    )]
+    pub escrow_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint = transaction_account.input_token,
+        token::authority = escrow_authority
+    )]
+    pub input_escrow: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = transaction_account.output_token,
+        token::authority = transaction_account.owner
+    )]
+    pub output_token_account: Account<'info, TokenAccount>,
+    /// Owner's input token account, receiving the input the route leaves in the escrow
+    #[account(
+        mut,
+        token::mint = transaction_account.input_token,
+        token::authority = transaction_account.owner
+    )]
+    pub refund_token_account: Account<'info, TokenAccount>,
+    /// Price feeds bound to mint pairs, read by the oracle check
+    #[account(seeds = [b"scoring_config"], bump = scoring_config.bump)]
+    pub scoring_config: Account<'info, ScoringConfig>,
+    /// CHECK: Pyth or Switchboard price account, decoded by the oracle program
+    /// owning it and checked against the mints' bound feed. Only needed when
+    /// the owner's guard sets an oracle check.
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    #[account(address = transaction_account.input_token)]
+    pub input_mint: Option<Account<'info, Mint>>,
+    #[account(address = transaction_account.output_token)]
+    pub output_mint: Option<Account<'info, Mint>>,
+    /// Transaction owner or keeper, paying for the route execution
+    #[account(
+        mut,
+        constraint = payer.key() == transaction_account.owner
+            || config.is_keeper(&payer.key()) @ ErrorCode::Unauthorized
+    )]
     pub payer: Signer<'info>,
+    /// CHECK: Receives the transaction's refunded deposit on completion, validated against the transaction
+    #[account(mut, address = transaction_account.depositor)]
+    pub depositor: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, used by the execution guard
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    /// Moves the transaction to Executing, then completes it
+    pub protection_manager: Program<'info, ZephyraProtectionManager>,
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(routes: Vec<RouteOption>)]
+#[instruction(transaction_id: [u8; 32])]
+pub struct RefundEscrow<'info> {
+    /// CHECK: PDA owning the owner's escrowed tokens for the transaction, signs the refund
+    #[account(
+        seeds = [b"escrow", owner.key().as_ref(), transaction_id.as_ref()],
+        bump
+    )]
+    pub escrow_authority: UncheckedAccount<'info>,
+    #[account(mut, token::authority = escrow_authority)]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        token::mint = escrow_token_account.mint,
+        token::authority = owner
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
 pub struct SelectRoute<'info> {
     #[account(
         seeds = [b"transaction", transaction_account.owner.as_ref(), &transaction_account.id],
-        bump
+        bump,
+        seeds::program = zephyra_protection_manager::ID
     )]
     pub transaction_account: Account<'info, Transaction>,
+    #[account(seeds = [b"scoring_config"], bump = scoring_config.bump)]
+    pub scoring_config: Account<'info, ScoringConfig>,
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct RouteExecution {
     pub transaction_id: [u8; 32],
-    pub dex: DEX,
     pub input_amount: u64,
     pub output_amount: u64,
-    pub price_impact_bps: u16,
+    pub price_impact_bps: u16, // compounded over chained hops, input-weighted over split legs
     pub mev_risk_score: u8,
     pub executed_at: i64,
+    #[max_len(MAX_HOPS)]
+    pub hops: Vec<HopExecution>,
+    /// Weights the input was split by across `hops`, empty when they were chained
+    #[max_len(MAX_SPLIT_LEGS)]
+    pub split_weights_bps: Vec<u16>,
     pub proof: ExecutionProof,
 }
 
+/// One swap of a route, through one venue's pool
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RouteHop {
+    pub venue: Pubkey, // venue program, keying its registry entry
+    pub pool: Pubkey, // default for venues that route off-chain
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+}
+
+/// Hop of a route to execute, with its venue accounts' count in the
+/// remaining accounts and the adapter's venue data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SwapHop {
+    pub hop: RouteHop,
+    pub min_output: u64,
+    pub venue_account_count: u8,
+    pub venue_data: Vec<u8>,
+}
+
+/// Leg of a split route to execute, swapping `weight_bps` of the input
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SwapSplit {
+    pub weight_bps: u16,
+    pub swap: SwapHop,
+}
+
+/// Amounts one hop of a route moved
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
-pub enum DEX {
-    Jupiter,
-    Raydium,
-    Orca,
+pub struct HopExecution {
+    pub hop: RouteHop,
+    pub input_amount: u64,
+    pub output_amount: u64,
+    pub quoted_output: Option<u64>,
+    pub price_impact_bps: u16,
+}
+
+/// Admin of the venue registry and the scoring config
+#[account]
+#[derive(InitSpace)]
+pub struct VenueRegistry {
+    pub admin: Pubkey,
+    pub bump: u8,
+}
+
+/// Registry entry of a venue the executor can route through, keyed by the
+/// venue's program
+#[account]
+#[derive(InitSpace)]
+pub struct Venue {
+    pub program_id: Pubkey,
+    pub adapter: AdapterKind,
+    pub enabled: bool,
+    #[max_len(MAX_VENUE_NAME_LEN)]
+    pub name: String,
+    pub bump: u8,
+}
+
+/// Weights routes are scored with, and the price feeds oracle checks use
+#[account]
+#[derive(InitSpace)]
+pub struct ScoringConfig {
+    pub weights: ScoringWeights,
+    pub bump: u8,
+    #[max_len(MAX_PRICE_FEEDS)]
+    pub price_feeds: Vec<PriceFeed>,
+}
+
+impl ScoringConfig {
+    /// Feed bound to the pair of `input_mint` and `output_mint`, either way round
+    pub fn price_feed(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Option<&PriceFeed> {
+        self.price_feeds
+            .iter()
+            .find(|feed| feed.prices(input_mint, output_mint))
+    }
+}
+
+/// Share of each route property in the route score, in basis points summing
+/// to 10000
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct ScoringWeights {
+    pub output_bps: u16,
+    pub mev_risk_bps: u16,
+    pub price_impact_bps: u16,
+    pub liquidity_depth_bps: u16,
+}
+
+impl ScoringWeights {
+    fn is_valid(&self) -> bool {
+        let weights = [
+            self.output_bps,
+            self.mev_risk_bps,
+            self.price_impact_bps,
+            self.liquidity_depth_bps,
+        ];
+        weights.iter().map(|weight| *weight as u32).sum::<u32>() == 10_000
+    }
+}
+
+/// How the executor builds a venue's swap instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum AdapterKind {
+    Passthrough { layout: PassthroughLayout }, // built off-chain, like Jupiter routes
+    RaydiumAmmV4,
+    Whirlpool,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct RouteOption {
-    pub dex: DEX,
+    #[max_len(MAX_HOPS)]
+    pub hops: Vec<RouteHop>,
+    /// Weights splitting the input across `hops` as parallel legs, empty when
+    /// the hops are chained
+    #[max_len(MAX_SPLIT_LEGS)]
+    pub split_weights_bps: Vec<u16>,
     pub estimated_output: u64,
     pub price_impact_bps: u16,
     pub mev_risk_score: u8,
@@ -388,12 +1587,30 @@ pub struct RouteOption {
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct RouteSelection {
-    pub selected_dex: DEX,
+    /// Hash of the selected hops and split weights, to select the route with
+    /// in the protection manager
+    pub route_hash: [u8; 32],
+    #[max_len(MAX_HOPS)]
+    pub selected_hops: Vec<RouteHop>,
+    #[max_len(MAX_SPLIT_LEGS)]
+    pub split_weights_bps: Vec<u16>,
     pub estimated_output: u64,
+    pub score: ScoreBreakdown,
     #[max_len(500)]
     pub reasoning: String,
 }
 
+/// Weighted components of a route's score, each out of its weight's share of
+/// `SCORE_SCALE`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct ScoreBreakdown {
+    pub output: u64,
+    pub mev_risk: u64,
+    pub price_impact: u64,
+    pub liquidity_depth: u64,
+    pub total: u64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
 pub struct ExecutionProof {
     pub pre_balance: u64,
@@ -402,20 +1619,61 @@ pub struct ExecutionProof {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct VenueRegistered {
+    pub program_id: Pubkey,
+    pub adapter: AdapterKind,
+    pub name: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VenueUpdated {
+    pub program_id: Pubkey,
+    pub adapter: AdapterKind,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ScoringConfigUpdated {
+    pub weights: ScoringWeights,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceFeedUpdated {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub source: Option<PriceFeedSource>,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct RouteExecuted {
     pub transaction_id: [u8; 32],
-    pub dex: DEX,
+    pub hops: Vec<RouteHop>,
+    pub split_weights_bps: Vec<u16>,
     pub input_amount: u64,
     pub output_amount: u64,
-    pub execution_time_ms: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub transaction_id: [u8; 32],
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct RouteSelected {
     pub transaction_id: [u8; 32],
-    pub selected_dex: DEX,
+    pub selected_hops: Vec<RouteHop>,
+    pub split_weights_bps: Vec<u16>,
+    pub score: ScoreBreakdown,
     pub alternatives_count: u8,
     pub reasoning: String,
 }
@@ -432,35 +1690,52 @@ pub enum ErrorCode {
     InvalidRouteData,
     #[msg("Execution failed")]
     ExecutionFailed,
+    #[msg("Swap was not called directly by the owner")]
+    UnauthorizedCaller,
+    #[msg("Pool was accessed by another program before the protected swap")]
+    PoolAccessedBeforeSwap,
+    #[msg("DEX program does not match the venue's registry entry")]
+    InvalidDexProgram,
+    #[msg("Escrow holds less than the transaction's input amount")]
+    InsufficientEscrow,
+    #[msg("Pool account does not match the venue's layout or the swap")]
+    InvalidPoolAccount,
+    #[msg("Pool cannot fill the swap from the accounts provided")]
+    QuoteFailed,
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    #[msg("Venue name is empty or too long")]
+    InvalidVenueName,
+    #[msg("Venue is not in the registry")]
+    UnknownVenue,
+    #[msg("Venue is disabled")]
+    VenueDisabled,
+    #[msg("Scoring weights must sum to 10000 basis points")]
+    InvalidScoringWeights,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Oracle check needs the price feed and both mints")]
+    MissingOracleAccounts,
+    #[msg("A price feed must price two different mints")]
+    InvalidPriceFeed,
+    #[msg("Scoring config already binds the most price feeds")]
+    TooManyPriceFeeds,
+    #[msg("No price feed is bound to the swap's mints")]
+    UnknownPriceFeed,
+    #[msg("Price account is not the feed bound to the swap's mints")]
+    WrongPriceFeed,
+    #[msg("Price feed is not a Pyth or Switchboard price account")]
+    InvalidOracleAccount,
+    #[msg("Oracle price is not positive or not fully verified")]
+    InvalidOraclePrice,
+    #[msg("Oracle price is too old")]
+    StaleOraclePrice,
+    #[msg("Oracle confidence interval is too wide")]
+    OracleConfidenceTooWide,
+    #[msg("Swap price deviates from the oracle price by more than allowed")]
+    OraclePriceDeviation,
+    #[msg("Transaction did not select this route")]
+    RouteNotSelected,
+    #[msg("Transaction is not approved for execution")]
+    TransactionNotApproved,
 }
-
-// Transaction struct (simplified version for cross-program compatibility)
-#[account]
-#[derive(InitSpace)]
-pub struct Transaction {
-    pub id: [u8; 32],
-    pub owner: Pubkey,
-    pub input_token: Pubkey,
-    pub output_token: Pubkey,
-    pub input_amount: u64,
-    pub output_amount: u64,
-    pub risk_score: u8,
-    pub selected_route: u8,
-    pub status: TransactionStatus,
-    pub proof_hash: [u8; 32],
-    pub batch_id: Option<[u8; 32]>,
-    pub created_at: i64,
-    pub completed_at: Option<i64>,
-}
-
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
-pub enum TransactionStatus {
-    Pending,
-    Simulating,
-    Analyzing,
-    Executing,
-    Completed,
-    Failed,
-}
-
-
@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use zephyra_quote::oracles::pyth::PriceUpdateV2;
+use zephyra_quote::oracles::switchboard::PullFeed;
+use zephyra_quote::QuoteError;
+use zephyra_protection_manager::OracleGuard;
+
+use crate::ErrorCode;
+
+/// Oracle feed pricing `base_mint` in `quote_mint`, the only one accepted for
+/// swaps between the two
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub struct PriceFeed {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub source: PriceFeedSource,
+}
+
+impl PriceFeed {
+    /// Whether the feed prices the pair of `mint_a` and `mint_b`, either way round
+    pub fn prices(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> bool {
+        (self.base_mint == *mint_a && self.quote_mint == *mint_b)
+            || (self.base_mint == *mint_b && self.quote_mint == *mint_a)
+    }
+}
+
+/// Where a price feed is published
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub enum PriceFeedSource {
+    /// Pyth price updates carrying this feed ID, from any price account
+    Pyth { feed_id: [u8; 32] },
+    /// This Switchboard On-Demand pull feed account
+    Switchboard { feed: Pubkey },
+}
+
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey =
+    pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+
+/// Check a swap of `amount_in` for `amount_out` against the price in
+/// `price_feed`, which must publish `feed`, decoded by the oracle program
+/// owning it
+pub fn enforce_oracle_guard(
+    oracle: &OracleGuard,
+    feed: &PriceFeed,
+    price_feed: &AccountInfo,
+    input_mint: &Account<Mint>,
+    output_mint: &Account<Mint>,
+    amount_in: u64,
+    amount_out: u64,
+) -> Result<()> {
+    let data = price_feed.try_borrow_data()?;
+    let price = match feed.source {
+        PriceFeedSource::Pyth { feed_id } if *price_feed.owner == PYTH_RECEIVER_PROGRAM_ID => {
+            let update = PriceUpdateV2::decode(&data).map_err(oracle_error)?;
+            require!(*update.feed_id() == feed_id, ErrorCode::WrongPriceFeed);
+            update.oracle_price()
+        }
+        PriceFeedSource::Switchboard { feed }
+            if *price_feed.owner == SWITCHBOARD_ON_DEMAND_PROGRAM_ID =>
+        {
+            require_keys_eq!(price_feed.key(), feed, ErrorCode::WrongPriceFeed);
+            PullFeed::decode(&data).and_then(|feed| feed.oracle_price())
+        }
+        _ => return err!(ErrorCode::InvalidOracleAccount),
+    }
+    .map_err(oracle_error)?;
+
+    price
+        .validate(
+            Clock::get()?.unix_timestamp,
+            oracle.max_age_secs,
+            oracle.max_confidence_bps,
+        )
+        .map_err(oracle_error)?;
+
+    // The feed's pair fixes which side of the swap is its base asset
+    let input_is_base = feed.base_mint == input_mint.key();
+    let ((base_amount, base_decimals), (quote_amount, quote_decimals)) = if input_is_base {
+        ((amount_in, input_mint.decimals), (amount_out, output_mint.decimals))
+    } else {
+        ((amount_out, output_mint.decimals), (amount_in, input_mint.decimals))
+    };
+    let deviation_bps = price
+        .deviation_bps(base_amount, base_decimals, quote_amount, quote_decimals)
+        .map_err(oracle_error)?;
+    require!(
+        deviation_bps <= oracle.max_deviation_bps as u64,
+        ErrorCode::OraclePriceDeviation
+    );
+
+    Ok(())
+}
+
+fn oracle_error(error: QuoteError) -> Error {
+    match error {
+        QuoteError::InvalidAccountData => ErrorCode::InvalidOracleAccount,
+        QuoteError::StalePrice => ErrorCode::StaleOraclePrice,
+        QuoteError::PriceUncertain => ErrorCode::OracleConfidenceTooWide,
+        _ => ErrorCode::InvalidOraclePrice,
+    }
+    .into()
+}
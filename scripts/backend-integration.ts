@@ -137,12 +137,20 @@ export class ZephyraSmartContractsIntegration {
       priceImpact: number;
       mevRisk: number;
       liquidityDepth: number;
+      hops?: Array<{
+        dex: 'Jupiter' | 'Raydium' | 'Orca';
+        pool: string;
+        inputMint: string;
+        outputMint: string;
+      }>;
+      splitWeightsBps?: number[]; // splits the input across `hops` instead of chaining them
     }>
   ): Promise<{ selectedDex: string; estimatedOutput: number; reasoning: string }> {
     const txIdBuffer = Buffer.from(transactionId, 'hex');
     
     const routeOptions = routes.map(route => ({
-      dex: this.mapDexToEnum(route.dex),
+      hops: this.mapRouteHops(route),
+      splitWeightsBps: route.splitWeightsBps ?? [],
       estimatedOutput: new anchor.BN(Math.floor(route.estimatedOutput * 1e9)),
       priceImpactBps: Math.floor(route.priceImpact * 100),
       mevRiskScore: route.mevRisk,
@@ -153,7 +161,12 @@ export class ZephyraSmartContractsIntegration {
       .selectBestRoute(routeOptions)
       .accounts({
         transactionAccount: this.getTransactionAccountAddress(transactionId),
+        scoringConfig: PublicKey.findProgramAddressSync(
+          [Buffer.from('scoring_config')],
+          this.routeExecutor.programId
+        )[0],
       })
+      .remainingAccounts(this.venueAccounts(routeOptions))
       .rpc();
 
     // Parse result from events
@@ -169,7 +182,7 @@ export class ZephyraSmartContractsIntegration {
   async generateProof(
     transactionId: string,
     routesConsidered: any[],
-    selectedRoute: string,
+    selectedRoute: string, // DEX of the selected route
     reasoning: string
   ): Promise<{ proofHash: string; signature: string }> {
     const txIdBuffer = Buffer.from(transactionId, 'hex');
@@ -178,7 +191,7 @@ export class ZephyraSmartContractsIntegration {
       .generateProof(
         txIdBuffer,
         routesConsidered,
-        this.mapDexToEnum(selectedRoute),
+        Math.max(0, routesConsidered.findIndex((route) => route.dex === selectedRoute)),
         reasoning
       )
       .accounts({
@@ -290,6 +303,8 @@ export class ZephyraSmartContractsIntegration {
       .accounts({
         protectionAccount: this.getProtectionAccountAddress(transactionId),
         transactionAccount: this.getTransactionAccountAddress(transactionId),
+        depositor: this.provider.wallet.publicKey,
+        authority: this.provider.wallet.publicKey,
       })
       .rpc();
 
@@ -311,6 +326,7 @@ export class ZephyraSmartContractsIntegration {
       .accounts({
         protectionAccount: this.getProtectionAccountAddress(transactionId),
         transactionAccount: this.getTransactionAccountAddress(transactionId),
+        authority: this.provider.wallet.publicKey,
       })
       .rpc();
 
@@ -318,19 +334,60 @@ export class ZephyraSmartContractsIntegration {
   }
 
   // Helper methods
-  private mapDexToEnum(dex: string): any {
+  // Routes without explicit hops are a single swap on their venue
+  private mapRouteHops(
+    route: {
+      dex: string;
+      hops?: Array<{ dex: string; pool: string; inputMint: string; outputMint: string }>;
+    },
+    inputMint: PublicKey = PublicKey.default,
+    outputMint: PublicKey = PublicKey.default
+  ): any[] {
+    const hops = route.hops ?? [
+      {
+        dex: route.dex,
+        pool: PublicKey.default.toBase58(),
+        inputMint: inputMint.toBase58(),
+        outputMint: outputMint.toBase58(),
+      },
+    ];
+    return hops.map((hop) => ({
+      venue: this.mapDexToVenue(hop.dex),
+      pool: new PublicKey(hop.pool),
+      inputMint: new PublicKey(hop.inputMint),
+      outputMint: new PublicKey(hop.outputMint),
+    }));
+  }
+
+  // Venues are keyed by their program ID in the route executor's registry
+  private mapDexToVenue(dex: string): PublicKey {
     switch (dex) {
       case 'Jupiter':
-        return { jupiter: {} };
+        return new PublicKey('JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4');
       case 'Raydium':
-        return { raydium: {} };
+        return new PublicKey('675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8');
       case 'Orca':
-        return { orca: {} };
+        return new PublicKey('whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc');
       default:
         throw new Error(`Unknown DEX: ${dex}`);
     }
   }
 
+  // Registry entries of every venue the routes use, for select_best_route
+  private venueAccounts(routeOptions: Array<{ hops: Array<{ venue: PublicKey }> }>) {
+    const venues = new Set(
+      routeOptions.flatMap((route) => route.hops.map((hop) => hop.venue.toBase58()))
+    );
+    return [...venues].map((venue) => ({
+      pubkey: PublicKey.findProgramAddressSync(
+        [Buffer.from('venue'), new PublicKey(venue).toBuffer()],
+        this.routeExecutor.programId
+      )[0],
+      isSigner: false,
+      isWritable: false,
+    }));
+  }
+
   private getTransactionAccountAddress(transactionId: string): PublicKey {
     // This would need to be implemented based on the actual account derivation
     return PublicKey.generate();
@@ -0,0 +1,46 @@
+#!/bin/bash
+
+# Zephyra - Mainnet quote fixture capture
+# Dumps the live SOL/USDC pools and SOL/USD price accounts read by the
+# zephyra-quote decoder tests into crates/zephyra-quote/tests/fixtures,
+# next to the synthetic accounts with a `_mainnet` suffix.
+#
+# Run: SWITCHBOARD_SOL_USD_FEED=<pull feed address> bash scripts/capture-quote-fixtures.sh
+# Any address below can be overridden the same way.
+
+set -e
+
+export PATH="$HOME/.local/share/solana/install/active_release/bin:$PATH"
+
+RPC_URL="${RPC_URL:-https://api.mainnet-beta.solana.com}"
+FIXTURES="$(cd "$(dirname "$0")/.." && pwd)/crates/zephyra-quote/tests/fixtures"
+
+# SOL/USDC pools
+RAYDIUM_AMM_V4_POOL="${RAYDIUM_AMM_V4_POOL:-58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2}"
+WHIRLPOOL_POOL="${WHIRLPOOL_POOL:-Czfq3xZZDmsdGdUyrNLtRhGc47cXcZtLG4crryfu44zE}"
+RAYDIUM_CLMM_POOL="${RAYDIUM_CLMM_POOL:-2QdhepnKRTLjjSqPL1PtKNwqrUkoLee5Gqs8bvZhRdMv}"
+# SOL/USD price accounts: the Pyth sponsored price update, and the
+# Switchboard pull feed the route executor binds to SOL/USDC
+PYTH_SOL_USD="${PYTH_SOL_USD:-7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE}"
+SWITCHBOARD_SOL_USD_FEED="${SWITCHBOARD_SOL_USD_FEED:?set SWITCHBOARD_SOL_USD_FEED to the SOL/USD pull feed address}"
+
+if ! command -v solana &> /dev/null; then
+    echo "solana CLI not found"
+    exit 1
+fi
+
+capture() {
+    local name="$1"
+    local address="$2"
+    solana account "$address" --output json --url "$RPC_URL" > "$FIXTURES/${name}_mainnet.json"
+    echo "  ${name}_mainnet.json <- $address"
+}
+
+echo "Capturing at slot $(solana slot --url "$RPC_URL")"
+capture raydium_amm_v4_pool "$RAYDIUM_AMM_V4_POOL"
+capture whirlpool_pool "$WHIRLPOOL_POOL"
+capture raydium_clmm_pool "$RAYDIUM_CLMM_POOL"
+capture pyth_sol_usd_price_update "$PYTH_SOL_USD"
+capture switchboard_sol_usd_pull_feed "$SWITCHBOARD_SOL_USD_FEED"
+
+echo "Record the slot in tests/fixtures/README.md, then run: cargo test -p zephyra-quote -- --include-ignored"
@@ -1,5 +1,6 @@
-import { Connection, PublicKey, Transaction, SystemProgram } from '@solana/web3.js';
+import { Connection, PublicKey, Transaction, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY } from '@solana/web3.js';
 import { AnchorProvider, Program, Wallet } from '@coral-xyz/anchor';
+import { getAssociatedTokenAddressSync, TOKEN_PROGRAM_ID } from '@solana/spl-token';
 import { MagicBlockClient, createMagicBlockClient } from './magicblock-client';
 import { ZephyraProtectionManager } from '../target/types/zephyra_protection_manager';
 import { ZephyraRouteExecutor } from '../target/types/zephyra_route_executor';
@@ -82,6 +83,13 @@ export class ZephyraMagicBlockIntegration {
       priceImpact: number;
       mevRisk: number;
       liquidityDepth: number;
+      hops?: Array<{
+        dex: 'Jupiter' | 'Raydium' | 'Orca';
+        pool: string;
+        inputMint: string;
+        outputMint: string;
+      }>;
+      splitWeightsBps?: number[]; // splits the input across `hops` instead of chaining them
     }>
   ): Promise<{
     transactionId: string;
@@ -168,7 +176,8 @@ export class ZephyraMagicBlockIntegration {
       // Step 5: Select best route (in ER)
       console.log('🔍 Selecting best route...');
       const routeOptions = routes.map(route => ({
-        dex: this.mapDexToEnum(route.dex),
+        hops: this.mapRouteHops(route, inputTokenPubkey, outputTokenPubkey),
+        splitWeightsBps: route.splitWeightsBps ?? [],
         estimatedOutput: new anchor.BN(Math.floor(route.estimatedOutput * 1e9)),
         priceImpactBps: Math.floor(route.priceImpact * 100),
         mevRiskScore: route.mevRisk,
@@ -179,7 +188,12 @@ export class ZephyraMagicBlockIntegration {
         .selectBestRoute(routeOptions)
         .accounts({
           transactionAccount,
+          scoringConfig: PublicKey.findProgramAddressSync(
+            [Buffer.from('scoring_config')],
+            this.routeExecutor.programId
+          )[0],
         })
+        .remainingAccounts(this.venueAccounts(routeOptions))
         .transaction();
 
       const routeResult = await this.magicBlock.executeInEphemeralRollup(
@@ -188,6 +202,23 @@ export class ZephyraMagicBlockIntegration {
       );
       console.log(`✅ Best route selected: ${routeResult.signature}`);
 
+      // The protection manager records the venue the swap executes through
+      const recordRouteTx = await this.protectionManager.methods
+        .selectRoute(Buffer.from(transactionId, 'hex'), {
+          venue: { programId: this.mapDexToVenue('Jupiter') },
+        })
+        .accounts({
+          config: PublicKey.findProgramAddressSync(
+            [Buffer.from('config')],
+            this.protectionManager.programId
+          )[0],
+          protectionAccount,
+          transactionAccount,
+          authority: this.provider.wallet.publicKey,
+        })
+        .transaction();
+      await this.magicBlock.executeInEphemeralRollup(recordRouteTx, [transactionAccount]);
+
       // Step 6: Generate proof (in ER)
       console.log('🔐 Generating proof-of-route...');
       const [proofAccount] = PublicKey.findProgramAddressSync(
@@ -203,7 +234,7 @@ export class ZephyraMagicBlockIntegration {
         .generateProof(
           Buffer.from(transactionId, 'hex'),
           routeOptions,
-          this.mapDexToEnum(selectedRoute.dex),
+          routes.indexOf(selectedRoute),
           `Selected ${selectedRoute.dex} due to optimal MEV risk (${selectedRoute.mevRisk}) and output`
         )
         .accounts({
@@ -223,21 +254,52 @@ export class ZephyraMagicBlockIntegration {
 
       // Step 7: Execute the actual swap (in ER)
       console.log('⚡ Executing swap...');
+      const dexProgram = this.mapDexToVenue('Jupiter');
+      const [venue] = PublicKey.findProgramAddressSync(
+        [Buffer.from('venue'), dexProgram.toBuffer()],
+        this.routeExecutor.programId
+      );
       const [routeExecution] = PublicKey.findProgramAddressSync(
-        [Buffer.from('route_execution'), Buffer.from(transactionId, 'hex'), Buffer.from('jupiter')],
+        [Buffer.from('route_execution'), Buffer.from(transactionId, 'hex'), dexProgram.toBuffer()],
         this.routeExecutor.programId
       );
 
-      const executeTx = await this.routeExecutor.methods
-        .executeJupiterSwap(
+      // The input is escrowed under the executor's escrow PDA for the wallet;
+      // output goes to the wallet, at least the minimum output stored with the transaction
+      const [escrowAuthority] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('escrow'),
+          this.provider.wallet.publicKey.toBuffer(),
           Buffer.from(transactionId, 'hex'),
-          Buffer.from('mock-route-data'),
-          new anchor.BN(minOutputAmount)
-        )
+        ],
+        this.routeExecutor.programId
+      );
+      const [config] = PublicKey.findProgramAddressSync(
+        [Buffer.from('config')],
+        this.protectionManager.programId
+      );
+      const executeTx = await this.routeExecutor.methods
+        .executeSwap(Buffer.from(transactionId, 'hex'), Buffer.from('mock-route-data'))
         .accounts({
+          config,
           transactionAccount,
+          protectionAccount,
+          dexProgram,
+          venue,
           routeExecution,
+          escrowAuthority,
+          inputEscrow: getAssociatedTokenAddressSync(inputTokenPubkey, escrowAuthority, true),
+          outputTokenAccount: getAssociatedTokenAddressSync(
+            outputTokenPubkey,
+            this.provider.wallet.publicKey
+          ),
+          priceFeed: null,
+          inputMint: null,
+          outputMint: null,
           payer: this.provider.wallet.publicKey,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+          protectionManager: this.protectionManager.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .transaction();
@@ -260,6 +322,8 @@ export class ZephyraMagicBlockIntegration {
         .accounts({
           protectionAccount,
           transactionAccount,
+          depositor: this.provider.wallet.publicKey,
+          authority: this.provider.wallet.publicKey,
         })
         .transaction();
 
@@ -331,19 +395,60 @@ export class ZephyraMagicBlockIntegration {
   }
 
   // Helper methods
-  private mapDexToEnum(dex: string): any {
+  // Routes without explicit hops are a single swap on their venue
+  private mapRouteHops(
+    route: {
+      dex: string;
+      hops?: Array<{ dex: string; pool: string; inputMint: string; outputMint: string }>;
+    },
+    inputMint: PublicKey = PublicKey.default,
+    outputMint: PublicKey = PublicKey.default
+  ): any[] {
+    const hops = route.hops ?? [
+      {
+        dex: route.dex,
+        pool: PublicKey.default.toBase58(),
+        inputMint: inputMint.toBase58(),
+        outputMint: outputMint.toBase58(),
+      },
+    ];
+    return hops.map((hop) => ({
+      venue: this.mapDexToVenue(hop.dex),
+      pool: new PublicKey(hop.pool),
+      inputMint: new PublicKey(hop.inputMint),
+      outputMint: new PublicKey(hop.outputMint),
+    }));
+  }
+
+  // Venues are keyed by their program ID in the route executor's registry
+  private mapDexToVenue(dex: string): PublicKey {
     switch (dex) {
       case 'Jupiter':
-        return { jupiter: {} };
+        return new PublicKey('JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4');
       case 'Raydium':
-        return { raydium: {} };
+        return new PublicKey('675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8');
       case 'Orca':
-        return { orca: {} };
+        return new PublicKey('whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc');
       default:
         throw new Error(`Unknown DEX: ${dex}`);
     }
   }
 
+  // Registry entries of every venue the routes use, for select_best_route
+  private venueAccounts(routeOptions: Array<{ hops: Array<{ venue: PublicKey }> }>) {
+    const venues = new Set(
+      routeOptions.flatMap((route) => route.hops.map((hop) => hop.venue.toBase58()))
+    );
+    return [...venues].map((venue) => ({
+      pubkey: PublicKey.findProgramAddressSync(
+        [Buffer.from('venue'), new PublicKey(venue).toBuffer()],
+        this.routeExecutor.programId
+      )[0],
+      isSigner: false,
+      isWritable: false,
+    }));
+  }
+
   private extractTransactionId(signature: string): string {
     // In production, parse logs to extract actual transaction ID
     return `tx-${signature.slice(0, 16)}`;
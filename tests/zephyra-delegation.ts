@@ -0,0 +1,215 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { ZephyraProtectionManager } from "../target/types/zephyra_protection_manager";
+import { expect } from "chai";
+
+// MagicBlock delegation program. Load it into the local validator with:
+//   solana program dump -u d DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh tests/fixtures/dlp.so
+// and the `[[test.genesis]]` entry in Anchor.toml.
+const DELEGATION_PROGRAM_ID = new anchor.web3.PublicKey(
+  "DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh"
+);
+
+describe("zephyra-delegation", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+
+  const program = anchor.workspace.ZephyraProtectionManager as Program<ZephyraProtectionManager>;
+  const provider = anchor.getProvider();
+
+  // Single-transaction sessions are keyed by the transaction account's address
+  const sessionAddress = (transactionAccount: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("rollup_session"), transactionAccount.toBuffer()],
+      program.programId
+    )[0];
+
+  const delegationAccounts = (account: anchor.web3.PublicKey) => ({
+    buffer: anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("buffer"), account.toBuffer()],
+      program.programId
+    )[0],
+    delegationRecord: anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("delegation"), account.toBuffer()],
+      DELEGATION_PROGRAM_ID
+    )[0],
+    delegationMetadata: anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("delegation-metadata"), account.toBuffer()],
+      DELEGATION_PROGRAM_ID
+    )[0],
+    ownerProgram: program.programId,
+    delegationProgram: DELEGATION_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  });
+
+  // Owner of the transactions sessions are opened for, with the provider
+  // wallet running the sessions
+  const owner = anchor.web3.Keypair.generate();
+  const [configAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("protection"), owner.publicKey.toBuffer()],
+    program.programId
+  );
+
+  before(async () => {
+    if (!(await provider.connection.getAccountInfo(configAccount))) {
+      const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+      );
+      await program.methods
+        .initializeConfig(provider.wallet.publicKey, new anchor.BN(0), new anchor.BN(3600))
+        .accounts({
+          config: configAccount,
+          program: program.programId,
+          programData: programData,
+          admin: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(owner.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+    await program.methods
+      .initializeProtection(owner.publicKey)
+      .accounts({
+        protectionAccount: protectionAccount,
+        payer: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  // The owner submits a transaction and opens its session
+  const initSession = async () => {
+    const inputToken = anchor.web3.Keypair.generate().publicKey;
+    await program.methods
+      .submitTransaction(
+        inputToken,
+        anchor.web3.Keypair.generate().publicKey,
+        new anchor.BN(1_000_000),
+        new anchor.BN(950_000)
+      )
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        payer: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const [submitted] = await program.account.transaction.all([
+      { memcmp: { offset: 8 + 32 + 32, bytes: inputToken.toBase58() } },
+    ]);
+
+    const transactionId = Buffer.from(submitted.account.id);
+    const sessionAccount = sessionAddress(submitted.publicKey);
+    await program.methods
+      .initRollupSession(Array.from(transactionId), provider.wallet.publicKey, null)
+      .accounts({
+        sessionAccount: sessionAccount,
+        transactionAccount: submitted.publicKey,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    return { transactionId, transactionAccount: submitted.publicKey, sessionAccount };
+  };
+
+  it("Delegate rollup session to the delegation program", async function () {
+    const delegationProgram = await provider.connection.getAccountInfo(DELEGATION_PROGRAM_ID);
+    if (!delegationProgram) {
+      // Delegation program binary not loaded into the local validator
+      this.skip();
+    }
+
+    const { transactionId, sessionAccount } = await initSession();
+
+    const tx = await program.methods
+      .delegateRollupSession(Array.from(transactionId), 30_000, null)
+      .accounts({
+        payer: provider.wallet.publicKey,
+        sessionAccount: sessionAccount,
+        ...delegationAccounts(sessionAccount),
+      })
+      .rpc();
+
+    console.log("Delegate rollup session transaction signature", tx);
+
+    const delegated = await provider.connection.getAccountInfo(sessionAccount);
+    expect(delegated.owner.toString()).to.equal(DELEGATION_PROGRAM_ID.toString());
+
+    const record = await provider.connection.getAccountInfo(
+      delegationAccounts(sessionAccount).delegationRecord
+    );
+    expect(record).to.not.be.null;
+
+    // The temporary buffer is closed once delegation completes
+    const buffer = await provider.connection.getAccountInfo(
+      delegationAccounts(sessionAccount).buffer
+    );
+    expect(buffer).to.be.null;
+  });
+
+  it("Reject delegating an inactive rollup session", async () => {
+    const { transactionId, transactionAccount, sessionAccount } = await initSession();
+    const session = await program.account.rollupSession.fetch(sessionAccount);
+
+    await program.methods
+      .rollbackRollup(session.id, { mevRisk: {} }, null)
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+        transactionAccount: transactionAccount,
+      })
+      .rpc();
+
+    try {
+      await program.methods
+        .delegateRollupSession(Array.from(transactionId), 30_000, null)
+        .accounts({
+          payer: provider.wallet.publicKey,
+          sessionAccount: sessionAccount,
+          ...delegationAccounts(sessionAccount),
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected session not active error");
+    } catch (error) {
+      expect(error.message).to.include("SessionNotActive");
+    }
+  });
+
+  it("Reject undelegation callback not signed by the delegation program", async () => {
+    const { transactionAccount, sessionAccount } = await initSession();
+
+    const [undelegateBuffer] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("undelegate-buffer"), sessionAccount.toBuffer()],
+      DELEGATION_PROGRAM_ID
+    );
+
+    try {
+      await program.methods
+        .processUndelegation([Buffer.from("rollup_session"), transactionAccount.toBuffer()])
+        .accounts({
+          baseAccount: sessionAccount,
+          buffer: undelegateBuffer,
+          payer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid delegation account error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidDelegationAccount");
+    }
+  });
+});
@@ -0,0 +1,1040 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { ZephyraProtectionManager } from "../target/types/zephyra_protection_manager";
+import { ZephyraBatchCoordinator } from "../target/types/zephyra_batch_coordinator";
+import { expect } from "chai";
+import { createHash } from "crypto";
+
+describe("zephyra-magicblock-integration", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+
+  const program = anchor.workspace.ZephyraProtectionManager as Program<ZephyraProtectionManager>;
+  const batchProgram = anchor.workspace.ZephyraBatchCoordinator as Program<ZephyraBatchCoordinator>;
+  const provider = anchor.getProvider();
+
+  // Single-transaction sessions are keyed by the transaction account's address
+  const sessionAddress = (transactionAccount: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("rollup_session"), transactionAccount.toBuffer()],
+      program.programId
+    )[0];
+
+  const batchSessionAddress = (batchId: Buffer) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("batch_rollup_session"), batchId],
+      program.programId
+    )[0];
+
+  const encodeOp = (op: object) => program.coder.types.encode("rollupOp", op);
+
+  const raydiumProgram = new anchor.web3.PublicKey("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+
+  const simulateSwap = encodeOp({
+    simulateSwap: {
+      inputAmount: new anchor.BN(1_000_000),
+      minOutput: new anchor.BN(900_000),
+      reserveIn: new anchor.BN(1_000_000_000),
+      reserveOut: new anchor.BN(1_000_000_000),
+      feeBps: 30,
+    },
+  });
+
+  // Mirrors the on-chain op accumulator (depth 16, domain-separated SHA-256)
+  const ACCUMULATOR_DEPTH = 16;
+  const sha256 = (...parts: Buffer[]) =>
+    createHash("sha256").update(Buffer.concat(parts)).digest();
+  const hashNode = (left: Buffer, right: Buffer) => sha256(Buffer.from([1]), left, right);
+  const opLeaf = (index: number, op: Buffer, result: Buffer) => {
+    const indexBytes = Buffer.alloc(4);
+    indexBytes.writeUInt32LE(index);
+    return sha256(Buffer.from([0]), indexBytes, op, result);
+  };
+  const merkleProof = (leaves: Buffer[], index: number) => {
+    let level = [...leaves];
+    let zero = Buffer.alloc(32);
+    const proof: number[][] = [];
+    for (let height = 0; height < ACCUMULATOR_DEPTH; height++) {
+      if (level.length % 2 === 1) level.push(zero);
+      proof.push(Array.from(level[index ^ 1]));
+      const next: Buffer[] = [];
+      for (let i = 0; i < level.length; i += 2) next.push(hashNode(level[i], level[i + 1]));
+      level = next;
+      zero = hashNode(zero, zero);
+      index >>= 1;
+    }
+    return { root: level[0], proof };
+  };
+
+  // Registered rollup validator signing commits
+  const rollupValidator = anchor.web3.Keypair.generate();
+  const [configAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [rollupValidatorAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("rollup_validator"), rollupValidator.publicKey.toBuffer()],
+    program.programId
+  );
+
+  // Program data holding the upgrade authority, which initializes the config
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  // Owner of the transactions sessions are opened for
+  const owner = anchor.web3.Keypair.generate();
+  const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("protection"), owner.publicKey.toBuffer()],
+    program.programId
+  );
+
+  before(async () => {
+    const config = await provider.connection.getAccountInfo(configAccount);
+    if (!config) {
+      await program.methods
+        .initializeConfig(anchor.web3.Keypair.generate().publicKey, new anchor.BN(0), new anchor.BN(3600))
+        .accounts({
+          config: configAccount,
+          program: program.programId,
+          programData: programData,
+          admin: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    await program.methods
+      .registerRollupValidator(rollupValidator.publicKey)
+      .accounts({
+        config: configAccount,
+        rollupValidator: rollupValidatorAccount,
+        admin: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(owner.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+    // The validator bonds its commits
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(rollupValidator.publicKey, anchor.web3.LAMPORTS_PER_SOL)
+    );
+    await program.methods
+      .initializeProtection(owner.publicKey)
+      .accounts({
+        protectionAccount: protectionAccount,
+        payer: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  const commitHash = (session: any) => {
+    const count = Buffer.alloc(4);
+    count.writeUInt32LE(session.instructionsExecuted);
+    return sha256(
+      Buffer.from("zephyra-rollup-commit"),
+      Buffer.from(session.id),
+      Buffer.from(session.stateHash),
+      Buffer.from(session.opRoot),
+      count
+    );
+  };
+
+  const commitSession = async (
+    sessionAccount: anchor.web3.PublicKey,
+    sessionId?: number[],
+    signer: anchor.web3.Keypair = rollupValidator,
+    transactionAccount: anchor.web3.PublicKey | null = null
+  ) => {
+    const session = await program.account.rollupSession.fetch(sessionAccount);
+    const signature = anchor.web3.Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message: commitHash(session),
+    });
+
+    return program.methods
+      .commitRollup(sessionId ?? session.id)
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+        config: configAccount,
+        rollupValidator: rollupValidatorAccount,
+        validator: rollupValidator.publicKey,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        transactionAccount: transactionAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .preInstructions([signature])
+      .signers([rollupValidator])
+      .rpc();
+  };
+
+  // Submit a transaction for the owner, found by its fresh input token
+  const submitTransaction = async () => {
+    const inputToken = anchor.web3.Keypair.generate().publicKey;
+    await program.methods
+      .submitTransaction(
+        inputToken,
+        anchor.web3.Keypair.generate().publicKey,
+        new anchor.BN(1_000_000),
+        new anchor.BN(950_000)
+      )
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        payer: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const [submitted] = await program.account.transaction.all([
+      { memcmp: { offset: 8 + 32 + 32, bytes: inputToken.toBase58() } },
+    ]);
+    return {
+      transactionId: Buffer.from(submitted.account.id),
+      transactionAccount: transactionAccount,
+    };
+  };
+
+  // The owner opens a session for a fresh transaction, run by `executor`
+  const initSession = async (
+    budget: object | null = null,
+    executor: anchor.web3.PublicKey = provider.wallet.publicKey
+  ) => {
+    const { transactionId, transactionAccount } = await submitTransaction();
+    const sessionAccount = sessionAddress(transactionAccount);
+
+    await program.methods
+      .initRollupSession(Array.from(transactionId), executor, budget)
+      .accounts({
+        sessionAccount: sessionAccount,
+        transactionAccount: transactionAccount,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const session = await program.account.rollupSession.fetch(sessionAccount);
+    return { transactionId, transactionAccount, sessionAccount, session };
+  };
+
+  it("Reject a rollup session opened by anyone but the transaction's owner", async () => {
+    const { transactionId, transactionAccount } = await submitTransaction();
+
+    try {
+      await program.methods
+        .initRollupSession(Array.from(transactionId), null, null)
+        .accounts({
+          sessionAccount: sessionAddress(transactionAccount),
+          transactionAccount: transactionAccount,
+          payer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected unauthorized error");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+    expect(await provider.connection.getAccountInfo(sessionAddress(transactionAccount))).to.be.null;
+  });
+
+  it("Initialize rollup session", async () => {
+    const { transactionId, transactionAccount, session } = await initSession();
+
+    expect(Buffer.from(session.transactionId)).to.deep.equal(transactionId);
+    expect(session.linkedTransaction.toString()).to.equal(transactionAccount.toString());
+    expect(session.status).to.deep.equal({ active: {} });
+    expect(session.instructionsExecuted).to.equal(0);
+    expect(session.expiresAt.toNumber()).to.be.greaterThan(session.createdAt.toNumber());
+  });
+
+  it("Execute instruction and commit rollup", async () => {
+    const { sessionAccount, session } = await initSession();
+
+    const result = await program.methods
+      .executeInRollup(session.id, simulateSwap)
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+      })
+      .view();
+    expect(result.success).to.be.true;
+
+    await program.methods
+      .executeInRollup(session.id, simulateSwap)
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const tx = await commitSession(sessionAccount);
+
+    console.log("Commit rollup transaction signature", tx);
+
+    const committed = await program.account.rollupSession.fetch(sessionAccount);
+    expect(committed.status).to.deep.equal({ committed: {} });
+    expect(committed.instructionsExecuted).to.equal(1);
+    expect(committed.committedAt).to.not.be.null;
+    expect(committed.state.simulatedOutput.toNumber()).to.equal(996_006);
+  });
+
+  it("Apply risk and route operations to session state", async () => {
+    const { sessionAccount, session } = await initSession();
+
+    await program.methods
+      .executeInRollup(session.id, encodeOp({ updateRisk: { riskScore: 42, mevDetected: true } }))
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .executeInRollup(
+        session.id,
+        encodeOp({
+          setRoute: {
+            route: { venue: { programId: raydiumProgram } },
+            expectedOutput: new anchor.BN(995_000),
+          },
+        })
+      )
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const updated = await program.account.rollupSession.fetch(sessionAccount);
+    expect(updated.instructionsExecuted).to.equal(2);
+    expect(updated.state.riskScore).to.equal(42);
+    expect(updated.state.mevDetected).to.be.true;
+    expect(updated.state.selectedRoute.venue.programId.toString()).to.equal(
+      raydiumProgram.toString()
+    );
+    expect(updated.state.expectedOutput.toNumber()).to.equal(995_000);
+  });
+
+  it("Reject malformed rollup operation", async () => {
+    const { sessionAccount, session } = await initSession();
+
+    try {
+      await program.methods
+        .executeInRollup(session.id, Buffer.from("simulate-swap"))
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid instruction data error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidInstructionData");
+    }
+  });
+
+  it("Rollback rollup session", async () => {
+    const { transactionAccount, sessionAccount, session } = await initSession();
+
+    await program.methods
+      .rollbackRollup(session.id, { mevRisk: {} }, null)
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+        transactionAccount: transactionAccount,
+      })
+      .rpc();
+
+    const rolledBack = await program.account.rollupSession.fetch(sessionAccount);
+    expect(rolledBack.status).to.deep.equal({ rolledBack: {} });
+    expect(rolledBack.rolledBackAt).to.not.be.null;
+  });
+
+  it("Rollback restores the linked transaction", async () => {
+    const { transactionId, transactionAccount } = await submitTransaction();
+    const sessionAccount = sessionAddress(transactionAccount);
+    await program.methods
+      .initRollupSession(Array.from(transactionId), null, null)
+      .accounts({
+        sessionAccount: sessionAccount,
+        transactionAccount: transactionAccount,
+        payer: owner.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const simulating = await program.account.transaction.fetch(transactionAccount);
+    expect(simulating.status).to.deep.equal({ simulating: {} });
+    const session = await program.account.rollupSession.fetch(sessionAccount);
+    expect(session.transactionSnapshot.status).to.deep.equal({ pending: {} });
+
+    // The linked transaction must be supplied to roll back
+    try {
+      await program.methods
+        .rollbackRollup(session.id, { slippageExceeded: {} }, null)
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: owner.publicKey,
+        })
+        .signers([owner])
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid transaction ID error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidTransactionId");
+    }
+
+    await program.methods
+      .rollbackRollup(session.id, { slippageExceeded: {} }, "Quote moved 3%")
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: owner.publicKey,
+        transactionAccount: transactionAccount,
+      })
+      .signers([owner])
+      .rpc();
+
+    const restored = await program.account.transaction.fetch(transactionAccount);
+    expect(restored.status).to.deep.equal({ pending: {} });
+    expect(restored.timeline[restored.timeline.length - 1].status).to.deep.equal({ pending: {} });
+    const rolledBack = await program.account.rollupSession.fetch(sessionAccount);
+    expect(rolledBack.rollbackReason).to.deep.equal({ slippageExceeded: {} });
+    expect(rolledBack.transactionSnapshot).to.be.null;
+  });
+
+  it("Roll back a session whose transaction was cancelled", async () => {
+    const { transactionId, transactionAccount, sessionAccount, session } = await initSession();
+
+    await program.methods
+      .cancelTransaction(Array.from(transactionId))
+      .accounts({
+        protectionAccount: protectionAccount,
+        transactionAccount: transactionAccount,
+        depositor: provider.wallet.publicKey,
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+
+    // Nothing is left to restore, but the session still rolls back
+    await program.methods
+      .rollbackRollup(session.id, { cancelled: {} }, null)
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: owner.publicKey,
+        transactionAccount: transactionAccount,
+      })
+      .signers([owner])
+      .rpc();
+
+    const rolledBack = await program.account.rollupSession.fetch(sessionAccount);
+    expect(rolledBack.status).to.deep.equal({ rolledBack: {} });
+  });
+
+  it("Reject an overlong rollback note", async () => {
+    const { sessionAccount, session } = await initSession();
+
+    try {
+      await program.methods
+        .rollbackRollup(session.id, { other: {} }, "x".repeat(65))
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected rollback note too long error");
+    } catch (error) {
+      expect(error.message).to.include("RollbackNoteTooLong");
+    }
+  });
+
+  it("Reject execution in a committed session", async () => {
+    const { sessionAccount, session } = await initSession();
+    await commitSession(sessionAccount);
+
+    try {
+      await program.methods
+        .executeInRollup(session.id, simulateSwap)
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected session not active error");
+    } catch (error) {
+      expect(error.message).to.include("SessionNotActive");
+    }
+  });
+
+  it("Reject a commit with a transaction the session is not linked to", async () => {
+    const { sessionAccount } = await initSession();
+    const other = await initSession();
+
+    try {
+      await commitSession(sessionAccount, undefined, rollupValidator, other.transactionAccount);
+
+      // Should not reach here
+      expect.fail("Expected invalid transaction ID error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidTransactionId");
+    }
+  });
+
+  it("Reject mismatched session ID", async () => {
+    const { sessionAccount } = await initSession();
+
+    try {
+      await commitSession(sessionAccount, Array.from(Buffer.alloc(32, 99)));
+
+      // Should not reach here
+      expect.fail("Expected invalid session ID error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidSessionId");
+    }
+  });
+
+  it("Verify inclusion of an executed op", async () => {
+    const { sessionAccount, session } = await initSession();
+
+    const ops = [
+      { updateRisk: { riskScore: 12, mevDetected: false } },
+      { setRoute: { route: { route: { routeHash: Array(32).fill(7) } }, expectedOutput: new anchor.BN(990_000) } },
+      { updateRisk: { riskScore: 64, mevDetected: true } },
+    ];
+
+    const leaves: Buffer[] = [];
+    const results = [];
+    for (const [index, op] of ops.entries()) {
+      const method = program.methods
+        .executeInRollup(session.id, encodeOp(op))
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+        });
+      const result = await method.view();
+      await method.rpc();
+
+      results.push(result);
+      leaves.push(opLeaf(index, encodeOp(op), program.coder.types.encode("rollupResult", result)));
+    }
+
+    const updated = await program.account.rollupSession.fetch(sessionAccount);
+    const { root, proof } = merkleProof(leaves, 1);
+    expect(Buffer.from(updated.opRoot)).to.deep.equal(root);
+
+    await program.methods
+      .verifyRollupOp(session.id, 1, ops[1], results[1], proof)
+      .accounts({
+        sessionAccount: sessionAccount,
+      })
+      .rpc();
+
+    try {
+      await program.methods
+        .verifyRollupOp(session.id, 1, ops[1], { ...results[1], success: false }, proof)
+        .accounts({
+          sessionAccount: sessionAccount,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid inclusion proof error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidInclusionProof");
+    }
+  });
+
+  it("Reject commit signed by an unregistered validator", async () => {
+    const { sessionAccount } = await initSession();
+
+    try {
+      await commitSession(sessionAccount, undefined, anchor.web3.Keypair.generate());
+
+      // Should not reach here
+      expect.fail("Expected unknown rollup validator error");
+    } catch (error) {
+      expect(error.message).to.include("UnknownRollupValidator");
+    }
+  });
+
+  it("Reject commit without a signature verification", async () => {
+    const { sessionAccount, session } = await initSession();
+
+    try {
+      await program.methods
+        .commitRollup(session.id)
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+          config: configAccount,
+          rollupValidator: rollupValidatorAccount,
+          validator: rollupValidator.publicKey,
+          instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([rollupValidator])
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid commit signature error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidCommitSignature");
+    }
+  });
+
+  it("Close a rolled back session to its payer", async () => {
+    const { transactionId, transactionAccount, sessionAccount, session } = await initSession();
+
+    await program.methods
+      .rollbackRollup(session.id, { routeUnavailable: {} }, "No longer profitable")
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+        transactionAccount: transactionAccount,
+      })
+      .rpc();
+
+    // The owner opened the session and gets its rent back
+    await program.methods
+      .expireRollupSession(Array.from(transactionId))
+      .accounts({
+        sessionAccount: sessionAccount,
+        payer: owner.publicKey,
+        cranker: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const closed = await provider.connection.getAccountInfo(sessionAccount);
+    expect(closed).to.be.null;
+  });
+
+  it("Reject expiring an active session before its deadline", async () => {
+    const { transactionId, sessionAccount } = await initSession();
+
+    try {
+      await program.methods
+        .expireRollupSession(Array.from(transactionId))
+        .accounts({
+          sessionAccount: sessionAccount,
+          payer: owner.publicKey,
+          cranker: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected session not expired error");
+    } catch (error) {
+      expect(error.message).to.include("SessionNotExpired");
+    }
+  });
+
+  it("Restrict session actions to the creator and executor", async () => {
+    const executor = anchor.web3.Keypair.generate();
+    const stranger = anchor.web3.Keypair.generate();
+
+    const { sessionAccount, session } = await initSession(null, executor.publicKey);
+    expect(session.creator.toString()).to.equal(owner.publicKey.toString());
+    expect(session.executor.toString()).to.equal(executor.publicKey.toString());
+
+    // Executor runs ops once assigned, in place of the creator
+    await program.methods
+      .executeInRollup(session.id, simulateSwap)
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: executor.publicKey,
+      })
+      .signers([executor])
+      .rpc();
+
+    try {
+      await program.methods
+        .executeInRollup(session.id, simulateSwap)
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected unauthorized session access error");
+    } catch (error) {
+      expect(error.message).to.include("UnauthorizedSessionAccess");
+    }
+
+    try {
+      await program.methods
+        .rollbackRollup(session.id, { cancelled: {} }, null)
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: stranger.publicKey,
+        })
+        .signers([stranger])
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected unauthorized session access error");
+    } catch (error) {
+      expect(error.message).to.include("UnauthorizedSessionAccess");
+    }
+  });
+
+  it("Hold commits open for challenges in optimistic mode", async () => {
+    const bond = new anchor.BN(10_000_000); // 0.01 SOL
+    await program.methods
+      .updateRollupConfig(new anchor.BN(1_000), bond)
+      .accounts({
+        config: configAccount,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const { transactionId, sessionAccount, session } = await initSession();
+
+    const op = { updateRisk: { riskScore: 30, mevDetected: false } };
+    const method = program.methods
+      .executeInRollup(session.id, encodeOp(op))
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+      });
+    const result = await method.view();
+    await method.rpc();
+
+    const before = await provider.connection.getBalance(sessionAccount);
+    await commitSession(sessionAccount);
+
+    const pending = await program.account.rollupSession.fetch(sessionAccount);
+    expect(pending.status).to.deep.equal({ pendingFinalization: {} });
+    expect(pending.bond.toNumber()).to.equal(bond.toNumber());
+    expect(pending.committer.toString()).to.equal(rollupValidator.publicKey.toString());
+    expect(await provider.connection.getBalance(sessionAccount)).to.equal(before + bond.toNumber());
+
+    // An honest op re-executes to the committed result
+    const leaf = opLeaf(0, encodeOp(op), program.coder.types.encode("rollupResult", result));
+    const { proof } = merkleProof([leaf], 0);
+    try {
+      await program.methods
+        .challengeRollup(Array.from(transactionId), 0, op, result, proof)
+        .accounts({
+          sessionAccount: sessionAccount,
+          challenger: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected challenge rejected error");
+    } catch (error) {
+      expect(error.message).to.include("ChallengeRejected");
+    }
+
+    try {
+      await program.methods
+        .finalizeRollup(Array.from(transactionId))
+        .accounts({
+          sessionAccount: sessionAccount,
+          committer: rollupValidator.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected challenge window open error");
+    } catch (error) {
+      expect(error.message).to.include("ChallengeWindowOpen");
+    }
+
+    await program.methods
+      .updateRollupConfig(new anchor.BN(0), new anchor.BN(0))
+      .accounts({
+        config: configAccount,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Reject batch member simulation in a single-transaction session", async () => {
+    const { sessionAccount, session } = await initSession();
+
+    const op = encodeOp({
+      simulateMember: {
+        memberIndex: 0,
+        reserveIn: new anchor.BN(1_000_000_000),
+        reserveOut: new anchor.BN(1_000_000_000),
+        feeBps: 30,
+      },
+    });
+
+    try {
+      await program.methods
+        .executeInRollup(session.id, op)
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid instruction data error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidInstructionData");
+    }
+  });
+
+  it("Reject batch rollup session for an empty batch", async () => {
+    const timestamp = new anchor.BN(Math.floor(Date.now() / 1000));
+    const [batchAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("batch"), provider.wallet.publicKey.toBuffer(), timestamp.toArrayLike(Buffer, "le", 8)],
+      batchProgram.programId
+    );
+
+    await batchProgram.methods
+      .createBatch(timestamp)
+      .accounts({
+        batchAccount: batchAccount,
+        payer: provider.wallet.publicKey,
+        authority: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const batch = await batchProgram.account.batch.fetch(batchAccount);
+
+    try {
+      await program.methods
+        .initBatchRollupSession(batch.id, null, null)
+        .accounts({
+          sessionAccount: batchSessionAddress(Buffer.from(batch.id)),
+          batchAccount: batchAccount,
+          payer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid batch ID error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidBatchId");
+    }
+  });
+
+  it("Meter session usage against its budget", async () => {
+    const budget = {
+      maxOps: 2,
+      maxComputeUnits: new anchor.BN(100_000),
+      maxOutputBytes: 16,
+    };
+    const { sessionAccount, session } = await initSession(budget);
+
+    const execute = (op: Buffer) =>
+      program.methods
+        .executeInRollup(session.id, op)
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    await execute(simulateSwap);
+    await execute(encodeOp({ updateRisk: { riskScore: 10, mevDetected: false } }));
+
+    const metered = await program.account.rollupSession.fetch(sessionAccount);
+    expect(metered.usage.ops).to.equal(2);
+    expect(metered.usage.computeUnits.toNumber()).to.be.greaterThan(0);
+    expect(metered.usage.computeUnits.toNumber()).to.be.lessThan(budget.maxComputeUnits.toNumber());
+    expect(metered.usage.outputBytes).to.equal(16);
+
+    try {
+      await execute(
+        encodeOp({ setRoute: { route: { route: { routeHash: Array(32).fill(7) } }, expectedOutput: new anchor.BN(1) } })
+      );
+
+      // Should not reach here
+      expect.fail("Expected session budget exceeded error");
+    } catch (error) {
+      expect(error.message).to.include("SessionBudgetExceeded");
+    }
+
+    await commitSession(sessionAccount);
+  });
+
+  it("Reject an op consuming more compute than the session budget", async () => {
+    const { sessionAccount, session } = await initSession({
+      maxOps: 2,
+      maxComputeUnits: new anchor.BN(1),
+      maxOutputBytes: 16,
+    });
+
+    try {
+      await program.methods
+        .executeInRollup(session.id, simulateSwap)
+        .accounts({
+          sessionAccount: sessionAccount,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected session budget exceeded error");
+    } catch (error) {
+      expect(error.message).to.include("SessionBudgetExceeded");
+    }
+  });
+
+  it("Reject an empty session budget", async () => {
+    try {
+      await initSession({
+        maxOps: 0,
+        maxComputeUnits: new anchor.BN(5_000),
+        maxOutputBytes: 16,
+      });
+
+      // Should not reach here
+      expect.fail("Expected invalid session budget error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidSessionBudget");
+    }
+  });
+
+  it("Store an op output in chunk accounts and close them with the session", async () => {
+    const { transactionId, transactionAccount, sessionAccount, session } = await initSession();
+
+    // A 150-step ladder outputs 1200 bytes, split across two 900-byte chunks
+    const steps = 150;
+    const reserve = new anchor.BN(1_000_000_000);
+    const quoteLadder = encodeOp({
+      quoteLadder: {
+        stepAmount: new anchor.BN(1_000_000),
+        steps,
+        reserveIn: reserve,
+        reserveOut: reserve,
+        feeBps: 0,
+      },
+    });
+    const outputData = Buffer.concat(
+      Array.from({ length: steps }, (_, step) => {
+        const amountIn = new anchor.BN(1_000_000).muln(step + 1);
+        return amountIn.mul(reserve).div(reserve.add(amountIn)).toArrayLike(Buffer, "le", 8);
+      })
+    );
+    const chunks = [outputData.subarray(0, 900), outputData.subarray(900)];
+
+    const opIndex = Buffer.alloc(4);
+    opIndex.writeUInt32LE(0);
+    const [outputAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("rollup_output"), sessionAccount.toBuffer(), Buffer.from(session.id), opIndex],
+      program.programId
+    );
+    const chunkAddress = (chunkIndex: number) =>
+      anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("rollup_output_chunk"), outputAccount.toBuffer(), Buffer.from([chunkIndex, 0])],
+        program.programId
+      )[0];
+
+    const method = program.methods
+      .executeInRollup(session.id, quoteLadder)
+      .accounts({
+        sessionAccount: sessionAccount,
+        outputAccount: outputAccount,
+        authority: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      });
+    const result = await method.view();
+    await method.rpc();
+
+    // Only the output's commitment is returned; the bytes are recomputed off-chain
+    const outputHash = chunks.reduce(
+      (hash, chunk) => sha256(Buffer.from([2]), hash, chunk),
+      Buffer.alloc(32)
+    );
+    expect(result.outputLen).to.equal(outputData.length);
+    expect(Buffer.from(result.outputHash)).to.deep.equal(outputHash);
+    const output = await program.account.rollupOutput.fetch(outputAccount);
+    expect(output.outputLen).to.equal(outputData.length);
+    expect(output.chunkCount).to.equal(2);
+    expect(Buffer.from(output.outputHash)).to.deep.equal(outputHash);
+
+    for (const [chunkIndex, chunk] of chunks.entries()) {
+      await program.methods
+        .writeRollupOutput(session.id, 0, chunkIndex, chunk)
+        .accounts({
+          sessionAccount: sessionAccount,
+          outputAccount: outputAccount,
+          chunkAccount: chunkAddress(chunkIndex),
+          authority: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    const written = await program.account.rollupOutput.fetch(outputAccount);
+    expect(written.chunksWritten).to.equal(2);
+    const stored = await Promise.all(
+      chunks.map((_, chunkIndex) => program.account.rollupOutputChunk.fetch(chunkAddress(chunkIndex)))
+    );
+    expect(Buffer.concat(stored.map((chunk) => Buffer.from(chunk.data)))).to.deep.equal(outputData);
+
+    const closeOutput = () =>
+      program.methods
+        .closeRollupOutput()
+        .accounts({
+          sessionAccount: sessionAccount,
+          outputAccount: outputAccount,
+          payer: provider.wallet.publicKey,
+          cranker: provider.wallet.publicKey,
+        })
+        .remainingAccounts(
+          chunks.map((_, chunkIndex) => ({
+            pubkey: chunkAddress(chunkIndex),
+            isSigner: false,
+            isWritable: true,
+          }))
+        )
+        .rpc();
+
+    // Readers keep the output while its session is open
+    try {
+      await closeOutput();
+
+      // Should not reach here
+      expect.fail("Expected session still open error");
+    } catch (error) {
+      expect(error.message).to.include("SessionStillOpen");
+    }
+
+    await program.methods
+      .rollbackRollup(session.id, { other: {} }, null)
+      .accounts({
+        sessionAccount: sessionAccount,
+        authority: provider.wallet.publicKey,
+        transactionAccount: transactionAccount,
+      })
+      .rpc();
+    await program.methods
+      .expireRollupSession(Array.from(transactionId))
+      .accounts({
+        sessionAccount: sessionAccount,
+        payer: owner.publicKey,
+        cranker: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    await closeOutput();
+    expect(await provider.connection.getAccountInfo(outputAccount)).to.be.null;
+    for (const chunkIndex of chunks.keys()) {
+      expect(await provider.connection.getAccountInfo(chunkAddress(chunkIndex))).to.be.null;
+    }
+  });
+});
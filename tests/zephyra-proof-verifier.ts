@@ -11,23 +11,57 @@ describe("zephyra-proof-verifier", () => {
   const provider = anchor.getProvider();
 
   const transactionId = Buffer.alloc(32, 1);
+  // Venue program IDs, as keyed in the route executor's registry
+  const jupiterProgram = new anchor.web3.PublicKey("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+  const raydiumProgram = new anchor.web3.PublicKey("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+  const whirlpoolProgram = new anchor.web3.PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
   const routesConsidered = [
     {
-      dex: { jupiter: {} },
+      hops: [
+        {
+          venue: jupiterProgram,
+          pool: anchor.web3.PublicKey.default,
+          inputMint: anchor.web3.PublicKey.default,
+          outputMint: anchor.web3.PublicKey.default,
+        },
+      ],
+      splitWeightsBps: [],
       estimatedOutput: new anchor.BN(980000),
       priceImpactBps: 50,
       mevRiskScore: 25,
       liquidityDepth: new anchor.BN(1000000000),
     },
     {
-      dex: { raydium: {} },
+      hops: [
+        {
+          venue: raydiumProgram,
+          pool: anchor.web3.PublicKey.default,
+          inputMint: anchor.web3.PublicKey.default,
+          outputMint: anchor.web3.PublicKey.default,
+        },
+      ],
+      splitWeightsBps: [],
       estimatedOutput: new anchor.BN(970000),
       priceImpactBps: 75,
       mevRiskScore: 35,
       liquidityDepth: new anchor.BN(800000000),
     },
+    {
+      // Split 50/50 between Raydium and Orca
+      hops: [raydiumProgram, whirlpoolProgram].map((venue) => ({
+        venue,
+        pool: anchor.web3.Keypair.generate().publicKey,
+        inputMint: anchor.web3.PublicKey.default,
+        outputMint: anchor.web3.PublicKey.default,
+      })),
+      splitWeightsBps: [5000, 5000],
+      estimatedOutput: new anchor.BN(985000),
+      priceImpactBps: 40,
+      mevRiskScore: 30,
+      liquidityDepth: new anchor.BN(1800000000),
+    },
   ];
-  const selectedRoute = { jupiter: {} };
+  const selectedRoute = 0; // index into routesConsidered
   const reasoning = "Selected Jupiter due to optimal MEV risk and output amount";
 
   it("Generate proof of route", async () => {
@@ -208,6 +242,26 @@ describe("zephyra-proof-verifier", () => {
     }
   });
 
+  it("Reject a selected route outside the routes considered", async () => {
+    const proofAccount = anchor.web3.Keypair.generate();
+
+    try {
+      await program.methods
+        .generateProof(transactionId, routesConsidered, routesConsidered.length, reasoning)
+        .accounts({
+          proofAccount: proofAccount.publicKey,
+          payer: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid selected route error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidSelectedRoute");
+    }
+  });
+
   it("Handle invalid probability error", async () => {
     const proofAccount = anchor.web3.Keypair.generate();
     const proofHash = Buffer.alloc(32, 1);
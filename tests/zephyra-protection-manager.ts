@@ -13,6 +13,92 @@ describe("zephyra-protection-manager", () => {
   // Test wallet
   const walletAddress = anchor.web3.Keypair.generate().publicKey;
 
+  // Protocol config
+  const treasury = anchor.web3.Keypair.generate().publicKey;
+  const submissionDeposit = new anchor.BN(10_000_000); // 0.01 SOL
+  const [configAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  // Program data holding the upgrade authority, which initializes the config
+  const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
+  it("Reject initializing config by other than the upgrade authority", async () => {
+    // Another suite may already have created the shared config
+    if (await provider.connection.getAccountInfo(configAccount)) {
+      return;
+    }
+    const impostor = anchor.web3.Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      impostor.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+
+    try {
+      await program.methods
+        .initializeConfig(impostor.publicKey, new anchor.BN(0), new anchor.BN(3600))
+        .accounts({
+          config: configAccount,
+          program: program.programId,
+          programData: programData,
+          admin: impostor.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([impostor])
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected unauthorized error");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+  });
+
+  it("Initialize protocol config", async () => {
+    // Another suite may already have created the shared config
+    const existing = await provider.connection.getAccountInfo(configAccount);
+    const tx = existing
+      ? await program.methods
+          .updateConfig(treasury, submissionDeposit, new anchor.BN(3600))
+          .accounts({
+            config: configAccount,
+            admin: provider.wallet.publicKey,
+          })
+          .rpc()
+      : await program.methods
+          .initializeConfig(treasury, submissionDeposit, new anchor.BN(3600))
+          .accounts({
+            config: configAccount,
+            program: program.programId,
+            programData: programData,
+            admin: provider.wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .rpc();
+
+    console.log("Initialize config transaction signature", tx);
+
+    // The provider wallet advances other wallets' transactions as a keeper
+    await program.methods
+      .setKeepers([provider.wallet.publicKey])
+      .accounts({
+        config: configAccount,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    const config = await program.account.protocolConfig.fetch(configAccount);
+    expect(config.treasury.toString()).to.equal(treasury.toString());
+    expect(config.submissionDeposit.toNumber()).to.equal(submissionDeposit.toNumber());
+    expect(config.keepers.map((keeper) => keeper.toString())).to.deep.equal([
+      provider.wallet.publicKey.toString(),
+    ]);
+  });
+
   it("Initialize protection account", async () => {
     const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
       [Buffer.from("protection"), walletAddress.toBuffer()],
@@ -52,6 +138,7 @@ describe("zephyra-protection-manager", () => {
     const tx = await program.methods
       .submitTransaction(inputToken, outputToken, inputAmount, minOutputAmount)
       .accounts({
+        config: configAccount,
         protectionAccount: protectionAccount,
         transactionAccount: anchor.web3.Keypair.generate().publicKey,
         payer: provider.wallet.publicKey,
@@ -103,42 +190,598 @@ describe("zephyra-protection-manager", () => {
     const inputAmount = new anchor.BN(1000000);
     const minOutputAmount = new anchor.BN(950000);
 
-    const submitTx = await program.methods
+    await program.methods
       .submitTransaction(inputToken, outputToken, inputAmount, minOutputAmount)
       .accounts({
+        config: configAccount,
         protectionAccount: protectionAccount,
-        transactionAccount: anchor.web3.Keypair.generate().publicKey,
         payer: provider.wallet.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
+    const [submitted] = await program.account.transaction.all([
+      { memcmp: { offset: 8 + 32 + 32, bytes: inputToken.toBase58() } },
+    ]);
+    const complete = (outputAmount: anchor.BN) =>
+      program.methods
+        .completeTransaction(submitted.account.id, outputAmount, Buffer.alloc(32, 1))
+        .accounts({
+          config: configAccount,
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          depositor: provider.wallet.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    // Only an executing transaction has results to complete with
+    await complete(new anchor.BN(1020000)).then(
+      () => expect.fail("Expected invalid status transition error"),
+      (error) => expect(error.message).to.include("InvalidStatusTransition")
+    );
+
+    for (const status of [{ routeSelected: {} }, { executing: {} }]) {
+      await program.methods
+        .updateTransactionStatus(submitted.account.id, status)
+        .accounts({
+          config: configAccount,
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+    }
+    const completeTx = await complete(new anchor.BN(1020000));
+
+    console.log("Complete transaction signature", completeTx);
+
+    // Verify protection account was updated with savings
+    const account = await program.account.protectionAccount.fetch(protectionAccount);
+    expect(account.totalSavings.toNumber()).to.equal(20000);
+    const transaction = await program.account.transaction.fetch(submitted.publicKey);
+    expect(transaction.status).to.deep.equal({ completed: {} });
+  });
+
+  it("Reject re-analyzing a transaction by other than the owner or a keeper, and completing it by other than a keeper", async () => {
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), walletAddress.toBuffer()],
+      program.programId
+    );
+    const submitted = (
+      await program.account.transaction.all([
+        { memcmp: { offset: 8 + 32, bytes: walletAddress.toBase58() } },
+      ])
+    ).find((transaction) => "pending" in transaction.account.status);
+    const stranger = anchor.web3.Keypair.generate();
+
+    await program.methods
+      .updateRiskAnalysis(submitted.account.id, 90, true)
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        transactionAccount: submitted.publicKey,
+        authority: stranger.publicKey,
+      })
+      .signers([stranger])
+      .rpc()
+      .then(
+        () => expect.fail("Expected unauthorized error"),
+        (error) => expect(error.message).to.include("Unauthorized")
+      );
+    await program.methods
+      .completeTransaction(submitted.account.id, new anchor.BN(5000000), Buffer.alloc(32, 1))
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        transactionAccount: submitted.publicKey,
+        depositor: submitted.account.depositor,
+        authority: stranger.publicKey,
+      })
+      .signers([stranger])
+      .rpc()
+      .then(
+        () => expect.fail("Expected unauthorized error"),
+        (error) => expect(error.message).to.include("Unauthorized")
+      );
+  });
+
+  it("Reject risk analysis of a completed transaction", async () => {
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), walletAddress.toBuffer()],
+      program.programId
+    );
+    const completed = (
+      await program.account.transaction.all([
+        { memcmp: { offset: 8 + 32, bytes: walletAddress.toBase58() } },
+      ])
+    ).find((transaction) => "completed" in transaction.account.status);
+
+    // A finished transaction can't be sent back to analysis
+    await program.methods
+      .updateRiskAnalysis(completed.account.id, 10, false)
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        transactionAccount: completed.publicKey,
+        authority: provider.wallet.publicKey,
+      })
+      .rpc()
+      .then(
+        () => expect.fail("Expected invalid status transition error"),
+        (error) => expect(error.message).to.include("InvalidStatusTransition")
+      );
+  });
+
+  it("Get account summary view", async () => {
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), walletAddress.toBuffer()],
+      program.programId
+    );
+
+    // Views return data through simulation, no signature required
+    const summary = await program.methods
+      .getAccountSummary()
+      .accounts({
+        protectionAccount: protectionAccount,
+      })
+      .view();
+
+    expect(summary.owner.toString()).to.equal(walletAddress.toString());
+    expect(summary.totalTransactions.toNumber()).to.equal(2);
+    expect(summary.mintStats.length).to.equal(2);
+    expect(summary.mintStats[0].transactionCount.toNumber()).to.equal(1);
+    expect(summary.settings.maxSlippageBps).to.equal(200);
+  });
+
+  it("Get transaction view", async () => {
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), walletAddress.toBuffer()],
+      program.programId
+    );
+
+    // Find a pending transaction submitted earlier for this wallet (owner follows the 32-byte id)
+    const submitted = (
+      await program.account.transaction.all([
+        { memcmp: { offset: 8 + 32, bytes: walletAddress.toBase58() } },
+      ])
+    ).find((transaction) => "pending" in transaction.account.status);
+
+    const view = await program.methods
+      .getTransactionView(submitted.account.id)
+      .accounts({
+        protectionAccount: protectionAccount,
+        transactionAccount: submitted.publicKey,
+      })
+      .view();
+
+    expect(view.owner.toString()).to.equal(walletAddress.toString());
+    expect(view.createdSlot.toNumber()).to.be.greaterThan(0);
+    expect(view.batchId).to.be.null;
+    expect(view.timeline.length).to.equal(1);
+    expect(view.timeline[0].status).to.deep.equal({ pending: {} });
+    expect(view.timeline[0].signer.toString()).to.equal(provider.wallet.publicKey.toString());
+
+    const [proofAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("proof"), Buffer.from(submitted.account.id)],
+      new anchor.web3.PublicKey("CYeCspx1DjYxYQZgMWz1sRPzKg9UxVWhMx5rRM4HihzQ")
+    );
+    expect(view.proofAccount.toString()).to.equal(proofAccount.toString());
+  });
+
+  it("Record lifecycle transitions on the timeline", async () => {
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), walletAddress.toBuffer()],
+      program.programId
+    );
+
+    const submitted = (
+      await program.account.transaction.all([
+        { memcmp: { offset: 8 + 32, bytes: walletAddress.toBase58() } },
+      ])
+    ).find((transaction) => "pending" in transaction.account.status);
+
+    for (const status of [{ simulating: {} }, { routeSelected: {} }, { batched: {} }]) {
+      await program.methods
+        .updateTransactionStatus(submitted.account.id, status)
+        .accounts({
+          config: configAccount,
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+    }
+
+    const account = await program.account.transaction.fetch(submitted.publicKey);
+    expect(account.status).to.deep.equal({ batched: {} });
+    expect(account.timeline.length).to.equal(4);
+    expect(account.timeline[3].slot.toNumber()).to.be.at.least(
+      account.timeline[0].slot.toNumber()
+    );
+  });
+
+  it("Reject status updates by other than the owner or a keeper", async () => {
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), walletAddress.toBuffer()],
+      program.programId
+    );
+    const [submitted] = await program.account.transaction.all([
+      { memcmp: { offset: 8 + 32, bytes: walletAddress.toBase58() } },
+    ]);
+    const stranger = anchor.web3.Keypair.generate();
+
+    try {
+      // Marking a victim's transaction Executing would block its cancellation
+      await program.methods
+        .updateTransactionStatus(submitted.account.id, { executing: {} })
+        .accounts({
+          config: configAccount,
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          authority: stranger.publicKey,
+        })
+        .signers([stranger])
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected unauthorized error");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+  });
+
+  it("Reject illegal status transitions", async () => {
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), walletAddress.toBuffer()],
+      program.programId
+    );
+    const submitted = (
+      await program.account.transaction.all([
+        { memcmp: { offset: 8 + 32, bytes: walletAddress.toBase58() } },
+      ])
+    ).find((transaction) => "batched" in transaction.account.status);
+
+    // A batched transaction can't go back to simulation
+    try {
+      await program.methods
+        .updateTransactionStatus(submitted.account.id, { simulating: {} })
+        .accounts({
+          config: configAccount,
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          authority: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid status transition error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidStatusTransition");
+    }
+  });
+
+  it("Cancel transaction refunds deposit and rent", async () => {
+    const owner = provider.wallet.publicKey;
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), owner.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeProtection(owner)
+      .accounts({
+        protectionAccount: protectionAccount,
+        payer: owner,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .submitTransaction(
+        anchor.web3.Keypair.generate().publicKey,
+        anchor.web3.Keypair.generate().publicKey,
+        new anchor.BN(1000000),
+        new anchor.BN(950000)
+      )
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        payer: owner,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const [submitted] = await program.account.transaction.all([
+      { memcmp: { offset: 8 + 32, bytes: owner.toBase58() } },
+    ]);
+    expect(submitted.account.deposit.toNumber()).to.equal(submissionDeposit.toNumber());
+
+    await program.methods
+      .cancelTransaction(submitted.account.id)
+      .accounts({
+        protectionAccount: protectionAccount,
+        transactionAccount: submitted.publicKey,
+        depositor: owner,
+        owner: owner,
+      })
+      .rpc();
+
+    // The account is closed and its lamports returned to the depositor
+    const closed = await provider.connection.getAccountInfo(submitted.publicKey);
+    expect(closed).to.be.null;
+  });
+
+  it("Reject expiring a transaction before its expiry", async () => {
+    const [submitted] = await program.account.transaction.all([
+      { memcmp: { offset: 8 + 32, bytes: walletAddress.toBase58() } },
+    ]);
+
+    try {
+      await program.methods
+        .expireTransaction(submitted.account.id)
+        .accounts({
+          config: configAccount,
+          transactionAccount: submitted.publicKey,
+          treasury: treasury,
+          depositor: submitted.account.depositor,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected transaction not expired error");
+    } catch (error) {
+      expect(error.message).to.include("TransactionNotExpired");
+    }
+  });
+
+  it("Fail an executing transaction only once its execution times out", async () => {
+    const owner = provider.wallet.publicKey;
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), owner.toBuffer()],
+      program.programId
+    );
+    const setExpiry = (seconds: number) =>
+      program.methods
+        .updateConfig(treasury, submissionDeposit, new anchor.BN(seconds))
+        .accounts({
+          config: configAccount,
+          admin: owner,
+        })
+        .rpc();
+
+    await setExpiry(3);
+    try {
+      await program.methods
+        .submitTransaction(
+          anchor.web3.Keypair.generate().publicKey,
+          anchor.web3.Keypair.generate().publicKey,
+          new anchor.BN(1000000),
+          new anchor.BN(950000)
+        )
+        .accounts({
+          config: configAccount,
+          protectionAccount: protectionAccount,
+          payer: owner,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      const [submitted] = await program.account.transaction.all([
+        { memcmp: { offset: 8 + 32, bytes: owner.toBase58() } },
+      ]);
+      for (const status of [{ routeSelected: {} }, { executing: {} }]) {
+        await program.methods
+          .updateTransactionStatus(submitted.account.id, status)
+          .accounts({
+            config: configAccount,
+            protectionAccount: protectionAccount,
+            transactionAccount: submitted.publicKey,
+            authority: owner,
+          })
+          .rpc();
+      }
 
-    // Get transaction ID from events
-    const logs = await provider.connection.getParsedTransaction(submitTx);
-    // In a real test, you'd parse the logs to get the transaction ID
+      // Nor can it be sent back to analysis to make it expirable
+      await program.methods
+        .updateRiskAnalysis(submitted.account.id, 10, false)
+        .accounts({
+          config: configAccount,
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          authority: owner,
+        })
+        .rpc()
+        .then(
+          () => expect.fail("Expected invalid status transition error"),
+          (error) => expect(error.message).to.include("InvalidStatusTransition")
+        );
 
-    // Complete the transaction
-    const outputAmount = new anchor.BN(980000); // 0.98 SOL output
-    const proofHash = Buffer.alloc(32, 1); // Mock proof hash
+      const expire = () =>
+        program.methods
+          .expireTransaction(submitted.account.id)
+          .accounts({
+            config: configAccount,
+            transactionAccount: submitted.publicKey,
+            treasury: treasury,
+            depositor: submitted.account.depositor,
+          })
+          .rpc();
 
-    const completeTx = await program.methods
-      .completeTransaction(
-        Buffer.alloc(32, 1), // Mock transaction ID
-        outputAmount,
-        proofHash
+      // The swap may still settle
+      await expire().then(
+        () => expect.fail("Expected transaction not expired error"),
+        (error) => expect(error.message).to.include("TransactionNotExpired")
+      );
+
+      // Timed out: the transaction fails and keeps its deposit
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      await expire();
+      const failed = await program.account.transaction.fetch(submitted.publicKey);
+      expect(failed.status).to.deep.equal({ failed: {} });
+      expect(failed.deposit.toString()).to.equal(submitted.account.deposit.toString());
+
+      // Which the owner reclaims by cancelling
+      await program.methods
+        .cancelTransaction(submitted.account.id)
+        .accounts({
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          depositor: owner,
+          owner: owner,
+        })
+        .rpc();
+      expect(await provider.connection.getAccountInfo(submitted.publicKey)).to.be.null;
+    } finally {
+      await setExpiry(3600);
+    }
+  });
+
+  it("Derive the route execution account of the selected route", async () => {
+    const owner = provider.wallet.publicKey;
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), owner.toBuffer()],
+      program.programId
+    );
+    const routeExecutor = new anchor.web3.PublicKey("ED8b3n8TAyFGohFofCFiUFFT2X3xpsUpSEajWmCZbJ8n");
+    const raydiumProgram = new anchor.web3.PublicKey("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+
+    await program.methods
+      .submitTransaction(
+        anchor.web3.Keypair.generate().publicKey,
+        anchor.web3.Keypair.generate().publicKey,
+        new anchor.BN(1000000),
+        new anchor.BN(950000)
       )
       .accounts({
+        config: configAccount,
         protectionAccount: protectionAccount,
-        transactionAccount: anchor.web3.Keypair.generate().publicKey,
+        payer: owner,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
+    const submitted = (
+      await program.account.transaction.all([
+        { memcmp: { offset: 8 + 32, bytes: owner.toBase58() } },
+      ])
+    ).find((transaction) => "pending" in transaction.account.status);
+    expect(submitted.account.minOutputAmount.toNumber()).to.equal(950000);
+    const transactionId = Buffer.from(submitted.account.id);
+    const view = () =>
+      program.methods
+        .getTransactionView(submitted.account.id)
+        .accounts({
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+        })
+        .view();
 
-    console.log("Complete transaction signature", completeTx);
+    // No route selected yet
+    expect((await view()).routeExecutionAccount).to.be.null;
 
-    // Verify protection account was updated with savings
-    const account = await program.account.protectionAccount.fetch(protectionAccount);
-    expect(account.totalSavings.toNumber()).to.be.greaterThan(0);
+    // A single swap is keyed by its venue's program
+    await program.methods
+      .selectRoute(submitted.account.id, { venue: { programId: raydiumProgram } })
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        transactionAccount: submitted.publicKey,
+        authority: owner,
+      })
+      .rpc();
+    const [venueExecution] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("route_execution"), transactionId, raydiumProgram.toBuffer()],
+      routeExecutor
+    );
+    const selected = await view();
+    expect(selected.status).to.deep.equal({ routeSelected: {} });
+    expect(selected.routeExecutionAccount.toString()).to.equal(venueExecution.toString());
+
+    // Chained and split routes share the `route` record
+    await program.methods
+      .selectRoute(submitted.account.id, { route: { routeHash: Array(32).fill(0) } })
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        transactionAccount: submitted.publicKey,
+        authority: owner,
+      })
+      .rpc()
+      .then(
+        () => expect.fail("Expected invalid status transition error"),
+        (error) => expect(error.message).to.include("InvalidStatusTransition")
+      );
+    const [routeExecution] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("route_execution"), transactionId, Buffer.from("route")],
+      routeExecutor
+    );
+    expect(routeExecution.toString()).to.not.equal(venueExecution.toString());
   });
-});
 
+  it("Close a completed transaction to its depositor", async () => {
+    const owner = provider.wallet.publicKey;
+    const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("protection"), owner.toBuffer()],
+      program.programId
+    );
+    const inputToken = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .submitTransaction(
+        inputToken,
+        anchor.web3.Keypair.generate().publicKey,
+        new anchor.BN(1000000),
+        new anchor.BN(950000)
+      )
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        payer: owner,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const [submitted] = await program.account.transaction.all([
+      { memcmp: { offset: 8 + 32 + 32, bytes: inputToken.toBase58() } },
+    ]);
+    const close = () =>
+      program.methods
+        .closeTransaction(submitted.account.id)
+        .accounts({
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          depositor: owner,
+          owner: owner,
+        })
+        .rpc();
+
+    // Unfinished transactions are cancelled or expired instead
+    await close().then(
+      () => expect.fail("Expected transaction not completed error"),
+      (error) => expect(error.message).to.include("TransactionNotCompleted")
+    );
 
+    for (const status of [{ routeSelected: {} }, { executing: {} }]) {
+      await program.methods
+        .updateTransactionStatus(submitted.account.id, status)
+        .accounts({
+          config: configAccount,
+          protectionAccount: protectionAccount,
+          transactionAccount: submitted.publicKey,
+          authority: owner,
+        })
+        .rpc();
+    }
+    await program.methods
+      .completeTransaction(submitted.account.id, new anchor.BN(990000), Buffer.alloc(32, 1))
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        transactionAccount: submitted.publicKey,
+        depositor: owner,
+        authority: owner,
+      })
+      .rpc();
+    await close();
+
+    const closed = await provider.connection.getAccountInfo(submitted.publicKey);
+    expect(closed).to.be.null;
+  });
+});
@@ -1,147 +1,1518 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { ZephyraRouteExecutor } from "../target/types/zephyra_route_executor";
+import { ZephyraProtectionManager } from "../target/types/zephyra_protection_manager";
 import { expect } from "chai";
+import { createHash } from "crypto";
+import {
+  createAccount,
+  createApproveInstruction,
+  createMint,
+  createMintToInstruction,
+  createTransferInstruction,
+  AuthorityType,
+  getAccount,
+  mintTo,
+  setAuthority,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
 
 describe("zephyra-route-executor", () => {
   // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
 
   const program = anchor.workspace.ZephyraRouteExecutor as Program<ZephyraRouteExecutor>;
+  const protectionManager = anchor.workspace
+    .ZephyraProtectionManager as Program<ZephyraProtectionManager>;
   const provider = anchor.getProvider();
 
-  // Mock transaction account
-  const transactionId = Buffer.alloc(32, 1);
-  const inputToken = anchor.web3.Keypair.generate().publicKey;
-  const outputToken = anchor.web3.Keypair.generate().publicKey;
-  const inputAmount = new anchor.BN(1000000); // 1 SOL in lamports
+  // Introspection checks disabled unless a test opts in through the owner's settings
+  const noGuard = { enforceCaller: false, rejectPriorPoolAccess: false, oracle: null };
 
-  it("Execute Jupiter swap", async () => {
-    const routeData = Buffer.from("mock-jupiter-route-data");
-    const minOutput = new anchor.BN(950000); // 0.95 SOL minimum
+  const raydiumProgram = new anchor.web3.PublicKey("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+  const jupiterProgram = new anchor.web3.PublicKey("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+  const whirlpoolProgram = new anchor.web3.PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+  const meteoraProgram = new anchor.web3.PublicKey("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
 
-    // Mock transaction account
-    const transactionAccount = anchor.web3.Keypair.generate();
+  // Passthrough venues are registered with the one instruction the escrow
+  // authority signs for them and where it takes the swap's accounts
+  const jupiterRoute = {
+    discriminator: Buffer.from([229, 23, 203, 151, 122, 227, 173, 42]),
+    authorityIndex: 1,
+    sourceIndex: 2,
+    destinationIndex: 3,
+    poolIndex: 10,
+  };
+  const meteoraSwap = {
+    discriminator: Buffer.from([248, 198, 158, 145, 225, 117, 135, 200]),
+    authorityIndex: 10,
+    sourceIndex: 4,
+    destinationIndex: 5,
+    poolIndex: 0,
+  };
+  // The local validator has no DEX, so the SPL Token program stands in for a
+  // venue: a transfer out of the escrow is a 1:1 swap of the input mint, the
+  // escrow standing in for the pool
+  const tokenTransfer = {
+    discriminator: Buffer.from([3]),
+    authorityIndex: 2,
+    sourceIndex: 0,
+    destinationIndex: 1,
+    poolIndex: 0,
+  };
+  // Nor can a transfer trade one mint for another, so multi-hop routes mint
+  // each hop's output under the escrow authority, the mint standing in for
+  // the pool and the hop's source passed along untouched
+  const tokenMintTo = {
+    discriminator: Buffer.from([7]),
+    authorityIndex: 2,
+    sourceIndex: 3,
+    destinationIndex: 1,
+    poolIndex: 0,
+  };
 
-    const tx = await program.methods
-      .executeJupiterSwap(transactionId, routeData, minOutput)
+  // Venues are registry entries keyed by their program ID
+  const [venueRegistry] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("venue_registry")],
+    program.programId
+  );
+  const venuePda = (dexProgram: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("venue"), dexProgram.toBuffer()],
+      program.programId
+    )[0];
+  const [scoringConfig] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("scoring_config")],
+    program.programId
+  );
+  const scoringWeights = {
+    outputBps: 5000,
+    mevRiskBps: 2500,
+    priceImpactBps: 1500,
+    liquidityDepthBps: 1000,
+  };
+  const venueAccount = (dexProgram: anchor.web3.PublicKey) => ({
+    pubkey: venuePda(dexProgram),
+    isSigner: false,
+    isWritable: false,
+  });
+  const programAccount = (dexProgram: anchor.web3.PublicKey) => ({
+    pubkey: dexProgram,
+    isSigner: false,
+    isWritable: false,
+  });
+
+  // Swaps execute protection manager transactions, signed by their owner or a keeper
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const owner = anchor.web3.Keypair.generate();
+  const stranger = anchor.web3.Keypair.generate();
+  const [configAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    protectionManager.programId
+  );
+  const [protectionAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("protection"), owner.publicKey.toBuffer()],
+    protectionManager.programId
+  );
+
+  let inputMint: anchor.web3.PublicKey;
+  let outputMint: anchor.web3.PublicKey;
+  let usdcMint: anchor.web3.PublicKey;
+
+  // A submitted transaction along its selected route, its input escrowed
+  // under the executor's escrow PDA, and fresh output and refund accounts of
+  // the owner
+  type Escrowed = {
+    transactionAccount: anchor.web3.PublicKey;
+    transactionId: Buffer;
+    escrowAuthority: anchor.web3.PublicKey;
+    inputEscrow: anchor.web3.PublicKey;
+    outputTokenAccount: anchor.web3.PublicKey;
+    refundTokenAccount: anchor.web3.PublicKey;
+  };
+  const selectRoute = (
+    transactionAccount: anchor.web3.PublicKey,
+    transactionId: Buffer,
+    route: object
+  ) =>
+    protectionManager.methods
+      .selectRoute(Array.from(transactionId), route)
+      .accounts({
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        transactionAccount,
+        authority: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+  const submitted = new Set<string>();
+  const submitEscrowed = async (
+    input: anchor.web3.PublicKey,
+    output: anchor.web3.PublicKey,
+    amount: number,
+    minOutput: number,
+    route: object | null
+  ): Promise<Escrowed> => {
+    await protectionManager.methods
+      .submitTransaction(input, output, new anchor.BN(amount), new anchor.BN(minOutput))
       .accounts({
-        transactionAccount: transactionAccount.publicKey,
-        routeExecution: anchor.web3.Keypair.generate().publicKey,
-        payer: provider.wallet.publicKey,
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        payer: owner.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
+      .signers([owner])
       .rpc();
+    const transaction = (
+      await protectionManager.account.transaction.all([
+        { memcmp: { offset: 8 + 32, bytes: owner.publicKey.toBase58() } },
+      ])
+    ).find((transaction) => !submitted.has(transaction.publicKey.toString()));
+    submitted.add(transaction.publicKey.toString());
 
-    console.log("Execute Jupiter swap transaction signature", tx);
+    const transactionId = Buffer.from(transaction.account.id);
+    if (route) {
+      await selectRoute(transaction.publicKey, transactionId, route);
+    }
+    const [escrowAuthority] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), owner.publicKey.toBuffer(), transactionId],
+      program.programId
+    );
+    const inputEscrow = await createAccount(
+      provider.connection,
+      payer,
+      input,
+      escrowAuthority,
+      anchor.web3.Keypair.generate()
+    );
+    await mintTo(provider.connection, payer, input, inputEscrow, payer, amount);
+    const outputTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      output,
+      owner.publicKey,
+      anchor.web3.Keypair.generate()
+    );
+    const refundTokenAccount = await createAccount(
+      provider.connection,
+      payer,
+      input,
+      owner.publicKey,
+      anchor.web3.Keypair.generate()
+    );
 
-    // Verify the transaction was successful
-    expect(tx).to.be.a('string');
+    return {
+      transactionAccount: transaction.publicKey,
+      transactionId,
+      escrowAuthority,
+      inputEscrow,
+      outputTokenAccount,
+      refundTokenAccount,
+    };
+  };
+
+  // Swaps run under the execution guard in the owner's protection settings
+  const setGuard = (guard: object) =>
+    protectionManager.methods
+      .updateExecutionGuard(guard)
+      .accounts({
+        protectionAccount: protectionAccount,
+        payer: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+
+  const balance = async (tokenAccount: anchor.web3.PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+  const balances = async (escrowed: Escrowed) => ({
+    escrow: await balance(escrowed.inputEscrow),
+    destination: await balance(escrowed.outputTokenAccount),
   });
 
-  it("Execute Raydium swap", async () => {
-    const poolAddress = anchor.web3.Keypair.generate().publicKey;
-    const minOutput = new anchor.BN(950000);
+  // A rejected swap moves nothing out of the escrow
+  const expectRejected = async (
+    escrowed: Escrowed,
+    send: () => Promise<string>,
+    error: string
+  ) => {
+    const before = await balances(escrowed);
+    await send().then(
+      () => expect.fail(`Expected ${error}`),
+      (rejection) => expect(rejection.message).to.include(error)
+    );
+    expect(await balances(escrowed)).to.deep.equal(before);
+  };
 
-    const tx = await program.methods
-      .executeRaydiumSwap(transactionId, poolAddress, minOutput)
+  const routeExecutionPda = (escrowed: Escrowed, seed: Buffer) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("route_execution"), escrowed.transactionId, seed],
+      program.programId
+    )[0];
+
+  const routeAccounts = (escrowed: Escrowed, signer = owner.publicKey) => ({
+    config: configAccount,
+    transactionAccount: escrowed.transactionAccount,
+    protectionAccount: protectionAccount,
+    scoringConfig,
+    routeExecution: routeExecutionPda(escrowed, Buffer.from("route")),
+    escrowAuthority: escrowed.escrowAuthority,
+    inputEscrow: escrowed.inputEscrow,
+    outputTokenAccount: escrowed.outputTokenAccount,
+    refundTokenAccount: escrowed.refundTokenAccount,
+    // Only read when the owner's guard sets an oracle check
+    priceFeed: null,
+    inputMint: null,
+    outputMint: null,
+    payer: signer,
+    depositor: owner.publicKey,
+    instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+    protectionManager: protectionManager.programId,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  });
+
+  const swapAccounts = (
+    escrowed: Escrowed,
+    dexProgram: anchor.web3.PublicKey,
+    signer = owner.publicKey
+  ) => ({
+    ...routeAccounts(escrowed, signer),
+    dexProgram: dexProgram,
+    venue: venuePda(dexProgram),
+    routeExecution: routeExecutionPda(escrowed, dexProgram.toBuffer()),
+  });
+
+  // Transfer of `amount` out of the escrow into `destination`, signed by the
+  // escrow authority inside the executor
+  const transferLeg = (
+    escrowed: Escrowed,
+    amount: number,
+    destination = escrowed.outputTokenAccount
+  ) => ({
+    venueData: createTransferInstruction(
+      escrowed.inputEscrow,
+      destination,
+      escrowed.escrowAuthority,
+      amount
+    ).data,
+    venueAccounts: [
+      { pubkey: escrowed.inputEscrow, isSigner: false, isWritable: true },
+      { pubkey: destination, isSigner: false, isWritable: true },
+      { pubkey: escrowed.escrowAuthority, isSigner: false, isWritable: false },
+    ],
+  });
+
+  // Venue accounts of the adapters with on-chain pools, which the local
+  // validator does not hold
+  const venueAccounts = (count: number, pool = anchor.web3.Keypair.generate().publicKey) =>
+    [pool, ...Array.from({ length: count - 1 }, () => anchor.web3.Keypair.generate().publicKey)].map(
+      (pubkey) => ({ pubkey, isSigner: false, isWritable: true })
+    );
+
+  // Pools of the multi-venue route tests
+  const raydiumPool = anchor.web3.Keypair.generate().publicKey;
+  const whirlpool = anchor.web3.Keypair.generate().publicKey;
+
+  // Routes a transaction selects before it executes. A multi-venue route is
+  // bound to its hops and split weights by the hash of their borsh encoding.
+  type RouteHop = {
+    venue: anchor.web3.PublicKey;
+    pool: anchor.web3.PublicKey;
+    inputMint: anchor.web3.PublicKey;
+    outputMint: anchor.web3.PublicKey;
+  };
+  const venueRoute = (programId: anchor.web3.PublicKey) => ({ venue: { programId } });
+  const routeHash = (hops: RouteHop[], splitWeightsBps: number[] = []) => {
+    const hopCount = Buffer.alloc(4);
+    hopCount.writeUInt32LE(hops.length);
+    const weights = Buffer.alloc(4 + 2 * splitWeightsBps.length);
+    weights.writeUInt32LE(splitWeightsBps.length);
+    splitWeightsBps.forEach((weight, index) => weights.writeUInt16LE(weight, 4 + 2 * index));
+    return Array.from(
+      createHash("sha256")
+        .update(
+          Buffer.concat([
+            hopCount,
+            ...hops.flatMap((hop) =>
+              [hop.venue, hop.pool, hop.inputMint, hop.outputMint].map((key) => key.toBuffer())
+            ),
+            weights,
+          ])
+        )
+        .digest()
+    );
+  };
+  const multiVenueRoute = (hops: RouteHop[], splitWeightsBps: number[] = []) => ({
+    route: { routeHash: routeHash(hops, splitWeightsBps) },
+  });
+
+  // Transactions swapping the input mint into itself through the token
+  // program, and into the output mint along a route for the checks made
+  // before any CPI
+  let swap: Escrowed;
+  let pair: Escrowed;
+
+  before(async () => {
+    for (const wallet of [owner, stranger]) {
+      const airdrop = await provider.connection.requestAirdrop(
+        wallet.publicKey,
+        2 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(airdrop);
+    }
+    inputMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    outputMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    usdcMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+
+    // The provider wallet is the protocol admin and a keeper
+    if (!(await provider.connection.getAccountInfo(configAccount))) {
+      const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+        [protectionManager.programId.toBuffer()],
+        new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+      );
+      await protectionManager.methods
+        .initializeConfig(payer.publicKey, new anchor.BN(0), new anchor.BN(3600))
+        .accounts({
+          config: configAccount,
+          program: protectionManager.programId,
+          programData: programData,
+          admin: payer.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await protectionManager.methods
+      .setKeepers([payer.publicKey])
+      .accounts({
+        config: configAccount,
+        admin: payer.publicKey,
+      })
+      .rpc();
+    await protectionManager.methods
+      .initializeProtection(owner.publicKey)
       .accounts({
-        transactionAccount: anchor.web3.Keypair.generate().publicKey,
-        routeExecution: anchor.web3.Keypair.generate().publicKey,
-        payer: provider.wallet.publicKey,
+        protectionAccount: protectionAccount,
+        payer: payer.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
 
-    console.log("Execute Raydium swap transaction signature", tx);
+    // The provider wallet, the program's upgrade authority, administers the
+    // registry of the built-in venues. Nobody else can create it first.
+    const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    const impostor = anchor.web3.Keypair.generate();
+    const airdrop = await provider.connection.requestAirdrop(
+      impostor.publicKey,
+      anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(airdrop);
+    try {
+      await program.methods
+        .initializeVenueRegistry()
+        .accounts({
+          venueRegistry,
+          program: program.programId,
+          programData,
+          admin: impostor.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([impostor])
+        .rpc();
+      expect.fail("Expected unauthorized error");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
 
-    expect(tx).to.be.a('string');
+    await program.methods
+      .initializeVenueRegistry()
+      .accounts({
+        venueRegistry,
+        program: program.programId,
+        programData,
+        admin: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const venues = [
+      { programId: jupiterProgram, adapter: { passthrough: { layout: jupiterRoute } }, name: "Jupiter" },
+      { programId: raydiumProgram, adapter: { raydiumAmmV4: {} }, name: "Raydium" },
+      { programId: whirlpoolProgram, adapter: { whirlpool: {} }, name: "Orca" },
+      {
+        programId: TOKEN_PROGRAM_ID,
+        adapter: { passthrough: { layout: tokenTransfer } },
+        name: "SPL Token",
+      },
+    ];
+    for (const { programId, adapter, name } of venues) {
+      await program.methods
+        .registerVenue(programId, adapter, name)
+        .accounts({
+          venueRegistry,
+          venue: venuePda(programId),
+          admin: provider.wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await program.methods
+      .initializeScoringConfig(scoringWeights)
+      .accounts({
+        venueRegistry,
+        scoringConfig,
+        admin: provider.wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    swap = await submitEscrowed(
+      inputMint,
+      inputMint,
+      1_000_000,
+      1_000_000,
+      venueRoute(TOKEN_PROGRAM_ID)
+    );
+    pair = await submitEscrowed(
+      inputMint,
+      outputMint,
+      1_000_000,
+      950_000,
+      multiVenueRoute([
+        { venue: raydiumProgram, pool: raydiumPool, inputMint, outputMint: usdcMint },
+        { venue: whirlpoolProgram, pool: whirlpool, inputMint: usdcMint, outputMint },
+      ])
+    );
   });
 
-  it("Execute Orca swap", async () => {
-    const poolAddress = anchor.web3.Keypair.generate().publicKey;
-    const minOutput = new anchor.BN(950000);
+
+  it("Execute a passthrough swap signed by the owner", async () => {
+    const escrowed = await submitEscrowed(
+      inputMint,
+      inputMint,
+      1_000_001,
+      1_000_001,
+      venueRoute(TOKEN_PROGRAM_ID)
+    );
+    const leg = transferLeg(escrowed, 1_000_001);
+    expect(await balances(escrowed)).to.deep.equal({ escrow: 1_000_001, destination: 0 });
 
     const tx = await program.methods
-      .executeOrcaSwap(transactionId, poolAddress, minOutput)
+      .executeSwap(escrowed.transactionId, leg.venueData)
+      .accounts(swapAccounts(escrowed, TOKEN_PROGRAM_ID))
+      .remainingAccounts(leg.venueAccounts)
+      .signers([owner])
+      .rpc();
+
+    console.log("Execute passthrough swap transaction signature", tx);
+
+    // The whole input left the escrow for the owner's output account
+    expect(await balances(escrowed)).to.deep.equal({ escrow: 0, destination: 1_000_001 });
+    const execution = await program.account.routeExecution.fetch(
+      routeExecutionPda(escrowed, TOKEN_PROGRAM_ID.toBuffer())
+    );
+    expect(execution.inputAmount.toNumber()).to.equal(1_000_001);
+    expect(execution.outputAmount.toNumber()).to.equal(1_000_001);
+
+    // The swap completed the transaction with its measured output
+    const transaction = await protectionManager.account.transaction.fetch(
+      escrowed.transactionAccount
+    );
+    expect(transaction.status).to.deep.equal({ completed: {} });
+    expect(transaction.outputAmount.toNumber()).to.equal(1_000_001);
+  });
+
+  it("Execute a swap for the owner as a keeper", async () => {
+    const escrowed = await submitEscrowed(
+      inputMint,
+      inputMint,
+      1_000_002,
+      1_000_002,
+      venueRoute(TOKEN_PROGRAM_ID)
+    );
+    const leg = transferLeg(escrowed, 1_000_002);
+
+    await program.methods
+      .executeSwap(escrowed.transactionId, leg.venueData)
+      .accounts(swapAccounts(escrowed, TOKEN_PROGRAM_ID, payer.publicKey))
+      .remainingAccounts(leg.venueAccounts)
+      .rpc();
+
+    expect(await balances(escrowed)).to.deep.equal({ escrow: 0, destination: 1_000_002 });
+  });
+
+  it("Reject a swap signed by neither the owner nor a keeper", async () => {
+    const leg = transferLeg(swap, 1_000_000);
+
+    await expectRejected(
+      swap,
+      () =>
+        program.methods
+          .executeSwap(swap.transactionId, leg.venueData)
+          .accounts(swapAccounts(swap, TOKEN_PROGRAM_ID, stranger.publicKey))
+          .remainingAccounts(leg.venueAccounts)
+          .signers([stranger])
+          .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("Reject a passthrough instruction paying another account", async () => {
+    const strangerAccount = await createAccount(
+      provider.connection,
+      payer,
+      inputMint,
+      stranger.publicKey,
+      anchor.web3.Keypair.generate()
+    );
+    const leg = transferLeg(swap, 1_000_000, strangerAccount);
+
+    await expectRejected(
+      swap,
+      () =>
+        program.methods
+          .executeSwap(swap.transactionId, leg.venueData)
+          .accounts(swapAccounts(swap, TOKEN_PROGRAM_ID))
+          .remainingAccounts(leg.venueAccounts)
+          .signers([owner])
+          .rpc(),
+      "InvalidRouteData"
+    );
+    expect(await balance(strangerAccount)).to.equal(0);
+  });
+
+  it("Reject a passthrough instruction the venue is not registered for", async () => {
+    // Approving a delegate over the escrow, with every account in its registered place
+    const approve = createApproveInstruction(
+      swap.inputEscrow,
+      swap.outputTokenAccount,
+      swap.escrowAuthority,
+      1_000_000
+    );
+    const { venueAccounts } = transferLeg(swap, 1_000_000);
+
+    await expectRejected(
+      swap,
+      () =>
+        program.methods
+          .executeSwap(swap.transactionId, approve.data)
+          .accounts(swapAccounts(swap, TOKEN_PROGRAM_ID))
+          .remainingAccounts(venueAccounts)
+          .signers([owner])
+          .rpc(),
+      "InvalidRouteData"
+    );
+    expect((await getAccount(provider.connection, swap.inputEscrow)).delegate).to.be.null;
+  });
+
+  it("Reject a swap through a venue the transaction did not select", async () => {
+    // The transaction selected the token program's venue, not Raydium
+    await expectRejected(
+      swap,
+      () =>
+        program.methods
+          .executeSwap(swap.transactionId, Buffer.alloc(0))
+          .accounts(swapAccounts(swap, raydiumProgram))
+          .remainingAccounts(venueAccounts(14))
+          .signers([owner])
+          .rpc(),
+      "RouteNotSelected"
+    );
+  });
+
+  it("Reject a swap of a transaction that is no longer approved", async () => {
+    const escrowed = await submitEscrowed(
+      inputMint,
+      inputMint,
+      1_000_006,
+      1_000_006,
+      venueRoute(TOKEN_PROGRAM_ID)
+    );
+    await protectionManager.methods
+      .updateTransactionStatus(Array.from(escrowed.transactionId), { failed: {} })
       .accounts({
-        transactionAccount: anchor.web3.Keypair.generate().publicKey,
-        routeExecution: anchor.web3.Keypair.generate().publicKey,
-        payer: provider.wallet.publicKey,
+        config: configAccount,
+        protectionAccount: protectionAccount,
+        transactionAccount: escrowed.transactionAccount,
+        authority: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+    const leg = transferLeg(escrowed, 1_000_006);
+
+    await expectRejected(
+      escrowed,
+      () =>
+        program.methods
+          .executeSwap(escrowed.transactionId, leg.venueData)
+          .accounts(swapAccounts(escrowed, TOKEN_PROGRAM_ID))
+          .remainingAccounts(leg.venueAccounts)
+          .signers([owner])
+          .rpc(),
+      "TransactionNotApproved"
+    );
+  });
+
+  it("Register a venue without redeploying the executor", async () => {
+    const tx = await program.methods
+      .registerVenue(meteoraProgram, { passthrough: { layout: meteoraSwap } }, "Meteora")
+      .accounts({
+        venueRegistry,
+        venue: venuePda(meteoraProgram),
+        admin: provider.wallet.publicKey,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
 
-    console.log("Execute Orca swap transaction signature", tx);
+    console.log("Register venue transaction signature", tx);
 
-    expect(tx).to.be.a('string');
+    const venue = await program.account.venue.fetch(venuePda(meteoraProgram));
+    expect(venue.programId.toBase58()).to.equal(meteoraProgram.toBase58());
+    expect(venue.enabled).to.equal(true);
+    expect(venue.name).to.equal("Meteora");
+    expect(venue.adapter.passthrough.layout.authorityIndex).to.equal(10);
+  });
+
+  it("Reject venue registration by anyone but the admin", async () => {
+    const programId = anchor.web3.Keypair.generate().publicKey;
+
+    try {
+      await program.methods
+        .registerVenue(programId, { passthrough: { layout: jupiterRoute } }, "Outsider")
+        .accounts({
+          venueRegistry,
+          venue: venuePda(programId),
+          admin: stranger.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([stranger])
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected unauthorized error");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+  });
+
+  it("Reject a swap through a disabled venue", async () => {
+    const setEnabled = (enabled: boolean) =>
+      program.methods
+        .updateVenue({ passthrough: { layout: tokenTransfer } }, enabled)
+        .accounts({
+          venueRegistry,
+          venue: venuePda(TOKEN_PROGRAM_ID),
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+    const leg = transferLeg(swap, 1_000_000);
+    await setEnabled(false);
+
+    try {
+      await expectRejected(
+        swap,
+        () =>
+          program.methods
+            .executeSwap(swap.transactionId, leg.venueData)
+            .accounts(swapAccounts(swap, TOKEN_PROGRAM_ID))
+            .remainingAccounts(leg.venueAccounts)
+            .signers([owner])
+            .rpc(),
+        "VenueDisabled"
+      );
+    } finally {
+      await setEnabled(true);
+    }
+  });
+
+  it("Reject a DEX program that does not match the venue", async () => {
+    const hops = [
+      {
+        hop: { venue: raydiumProgram, pool: raydiumPool, inputMint, outputMint },
+        minOutput: new anchor.BN(950000),
+        venueAccountCount: 14,
+        venueData: Buffer.alloc(0),
+      },
+    ];
+
+    const routed = await submitEscrowed(
+      inputMint,
+      outputMint,
+      1_000_000,
+      950_000,
+      multiVenueRoute(hops.map(({ hop }) => hop))
+    );
+
+    // Raydium's registry entry paired with the Whirlpool program
+    await expectRejected(
+      routed,
+      () =>
+        program.methods
+          .executeRoute(routed.transactionId, hops)
+          .accounts(routeAccounts(routed))
+          .remainingAccounts([
+            venueAccount(raydiumProgram),
+            programAccount(whirlpoolProgram),
+            ...venueAccounts(14, raydiumPool),
+          ])
+          .signers([owner])
+          .rpc(),
+      "InvalidDexProgram"
+    );
   });
 
   it("Select best route", async () => {
     const routes = [
       {
-        dex: { jupiter: {} },
+        hops: [
+          {
+            venue: jupiterProgram,
+            pool: anchor.web3.PublicKey.default,
+            inputMint,
+            outputMint,
+          },
+        ],
+        splitWeightsBps: [],
         estimatedOutput: new anchor.BN(980000),
         priceImpactBps: 50,
         mevRiskScore: 25,
         liquidityDepth: new anchor.BN(1000000000),
       },
       {
-        dex: { raydium: {} },
+        hops: [
+          {
+            venue: raydiumProgram,
+            pool: anchor.web3.PublicKey.default,
+            inputMint,
+            outputMint,
+          },
+        ],
+        splitWeightsBps: [],
         estimatedOutput: new anchor.BN(970000),
         priceImpactBps: 75,
         mevRiskScore: 35,
         liquidityDepth: new anchor.BN(800000000),
       },
       {
-        dex: { orca: {} },
+        hops: [
+          {
+            venue: whirlpoolProgram,
+            pool: anchor.web3.PublicKey.default,
+            inputMint,
+            outputMint,
+          },
+        ],
+        splitWeightsBps: [],
         estimatedOutput: new anchor.BN(975000),
         priceImpactBps: 60,
         mevRiskScore: 30,
         liquidityDepth: new anchor.BN(900000000),
       },
+      {
+        // 60% through Raydium and 40% through Orca
+        hops: [
+          { venue: raydiumProgram, pool: raydiumPool, inputMint, outputMint },
+          { venue: whirlpoolProgram, pool: whirlpool, inputMint, outputMint },
+        ],
+        splitWeightsBps: [6000, 4000],
+        estimatedOutput: new anchor.BN(985000),
+        priceImpactBps: 40,
+        mevRiskScore: 30,
+        liquidityDepth: new anchor.BN(1700000000),
+      },
     ];
 
     const tx = await program.methods
       .selectBestRoute(routes)
       .accounts({
-        transactionAccount: anchor.web3.Keypair.generate().publicKey,
+        transactionAccount: pair.transactionAccount,
+        scoringConfig,
       })
+      .remainingAccounts([jupiterProgram, raydiumProgram, whirlpoolProgram].map(venueAccount))
       .rpc();
 
     console.log("Select best route transaction signature", tx);
 
     expect(tx).to.be.a('string');
+
+    // The selection carries the hash the transaction selects its route with
+    const selection = await program.methods
+      .selectBestRoute(routes)
+      .accounts({
+        transactionAccount: pair.transactionAccount,
+        scoringConfig,
+      })
+      .remainingAccounts([jupiterProgram, raydiumProgram, whirlpoolProgram].map(venueAccount))
+      .view();
+    expect(selection.routeHash).to.deep.equal(
+      routeHash(selection.selectedHops, selection.splitWeightsBps)
+    );
   });
 
-  it("Handle slippage exceeded error", async () => {
-    const routeData = Buffer.from("mock-jupiter-route-data");
-    const minOutput = new anchor.BN(2000000); // Very high minimum (2 SOL)
+  it("Reject a route with an MEV risk score over 100", async () => {
+    const route = {
+      hops: [
+        {
+          venue: jupiterProgram,
+          pool: anchor.web3.PublicKey.default,
+          inputMint,
+          outputMint,
+        },
+      ],
+      splitWeightsBps: [],
+      estimatedOutput: new anchor.BN(980000),
+      priceImpactBps: 50,
+      mevRiskScore: 150,
+      liquidityDepth: new anchor.BN(1000000000),
+    };
 
     try {
       await program.methods
-        .executeJupiterSwap(transactionId, routeData, minOutput)
+        .selectBestRoute([route])
         .accounts({
-          transactionAccount: anchor.web3.Keypair.generate().publicKey,
-          routeExecution: anchor.web3.Keypair.generate().publicKey,
-          payer: provider.wallet.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
+          transactionAccount: pair.transactionAccount,
+          scoringConfig,
         })
+        .remainingAccounts([venueAccount(jupiterProgram)])
         .rpc();
 
       // Should not reach here
-      expect.fail("Expected slippage exceeded error");
+      expect.fail("Expected invalid route data error");
     } catch (error) {
-      expect(error.message).to.include("SlippageExceeded");
+      expect(error.message).to.include("InvalidRouteData");
     }
   });
 
+  it("Update the scoring weights", async () => {
+    const tx = await program.methods
+      .updateScoringConfig({ ...scoringWeights, outputBps: 4000, liquidityDepthBps: 2000 })
+      .accounts({
+        venueRegistry,
+        scoringConfig,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc();
+
+    console.log("Update scoring config transaction signature", tx);
+
+    const config = await program.account.scoringConfig.fetch(scoringConfig);
+    expect(config.weights.outputBps).to.equal(4000);
+    expect(config.weights.liquidityDepthBps).to.equal(2000);
+  });
+
+  it("Reject scoring weights that do not sum to 10000", async () => {
+    try {
+      await program.methods
+        .updateScoringConfig({ ...scoringWeights, outputBps: 6000 })
+        .accounts({
+          venueRegistry,
+          scoringConfig,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected invalid scoring weights error");
+    } catch (error) {
+      expect(error.message).to.include("InvalidScoringWeights");
+    }
+  });
+
+  it("Bind a price feed to a mint pair", async () => {
+    const feedId = Array.from({ length: 32 }, (_, i) => i + 1);
+    const switchboardFeed = anchor.web3.Keypair.generate().publicKey;
+    const setPriceFeed = (
+      baseMint: anchor.web3.PublicKey,
+      quoteMint: anchor.web3.PublicKey,
+      source: object | null
+    ) =>
+      program.methods
+        .setPriceFeed(baseMint, quoteMint, source)
+        .accounts({
+          venueRegistry,
+          scoringConfig,
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    const tx = await setPriceFeed(inputMint, outputMint, { pyth: { feedId } });
+
+    console.log("Set price feed transaction signature", tx);
+
+    let config = await program.account.scoringConfig.fetch(scoringConfig);
+    expect(config.priceFeeds).to.have.length(1);
+    expect(config.priceFeeds[0].baseMint.toBase58()).to.equal(inputMint.toBase58());
+    expect(config.priceFeeds[0].source.pyth.feedId).to.deep.equal(feedId);
+
+    // Binding the pair the other way round replaces its feed
+    await setPriceFeed(outputMint, inputMint, { switchboard: { feed: switchboardFeed } });
+    config = await program.account.scoringConfig.fetch(scoringConfig);
+    expect(config.priceFeeds).to.have.length(1);
+    expect(config.priceFeeds[0].baseMint.toBase58()).to.equal(outputMint.toBase58());
+    expect(config.priceFeeds[0].source.switchboard.feed.toBase58()).to.equal(
+      switchboardFeed.toBase58()
+    );
+
+    await setPriceFeed(inputMint, outputMint, null);
+    config = await program.account.scoringConfig.fetch(scoringConfig);
+    expect(config.priceFeeds).to.be.empty;
+  });
+
+  it("Reject a price feed binding by anyone but the admin", async () => {
+    const feed = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .setPriceFeed(inputMint, outputMint, { switchboard: { feed } })
+      .accounts({
+        venueRegistry,
+        scoringConfig,
+        admin: stranger.publicKey,
+      })
+      .signers([stranger])
+      .rpc()
+      .then(
+        () => expect.fail("Expected unauthorized error"),
+        (error) => expect(error.message).to.include("Unauthorized")
+      );
+  });
+
+  it("Reject a price feed pricing a mint in itself", async () => {
+    const feed = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .setPriceFeed(inputMint, inputMint, { switchboard: { feed } })
+      .accounts({
+        venueRegistry,
+        scoringConfig,
+        admin: provider.wallet.publicKey,
+      })
+      .rpc()
+      .then(
+        () => expect.fail("Expected invalid price feed error"),
+        (error) => expect(error.message).to.include("InvalidPriceFeed")
+      );
+  });
+
+  it("Reject a swap below the transaction's minimum output", async () => {
+    // The owner asked for more than the 1:1 transfer delivers
+    const escrowed = await submitEscrowed(
+      inputMint,
+      inputMint,
+      1_000_003,
+      2_000_000,
+      venueRoute(TOKEN_PROGRAM_ID)
+    );
+    const leg = transferLeg(escrowed, 1_000_003);
+
+    await expectRejected(
+      escrowed,
+      () =>
+        program.methods
+          .executeSwap(escrowed.transactionId, leg.venueData)
+          .accounts(swapAccounts(escrowed, TOKEN_PROGRAM_ID))
+          .remainingAccounts(leg.venueAccounts)
+          .signers([owner])
+          .rpc(),
+      "SlippageExceeded"
+    );
+  });
+
+  it("Reject swap when the pool is touched earlier in the transaction", async () => {
+    const escrowed = await submitEscrowed(
+      inputMint,
+      outputMint,
+      1_000_000,
+      950_000,
+      venueRoute(raydiumProgram)
+    );
+    const poolAddress = anchor.web3.Keypair.generate().publicKey;
+
+    // Another program writes to the pool ahead of the protected swap
+    const frontRun = anchor.web3.SystemProgram.transfer({
+      fromPubkey: owner.publicKey,
+      toPubkey: poolAddress,
+      lamports: 1_000_000,
+    });
+
+    await setGuard({ ...noGuard, rejectPriorPoolAccess: true });
+    try {
+      await expectRejected(
+        escrowed,
+        () =>
+          program.methods
+            .executeSwap(escrowed.transactionId, Buffer.alloc(0))
+            .accounts(swapAccounts(escrowed, raydiumProgram))
+            .remainingAccounts(venueAccounts(14, poolAddress))
+            .preInstructions([frontRun])
+            .signers([owner])
+            .rpc(),
+        "PoolAccessedBeforeSwap"
+      );
+    } finally {
+      await setGuard(noGuard);
+    }
+  });
+
+  it("Reject a passthrough swap whose pool is touched earlier in the transaction", async () => {
+    const escrowed = await submitEscrowed(
+      inputMint,
+      inputMint,
+      1_000_005,
+      1_000_005,
+      venueRoute(TOKEN_PROGRAM_ID)
+    );
+    const leg = transferLeg(escrowed, 1_000_005);
+
+    // The registered layout puts the pool, stood in for by the escrow, first
+    const topUp = createMintToInstruction(inputMint, escrowed.inputEscrow, payer.publicKey, 1);
+
+    await setGuard({ ...noGuard, rejectPriorPoolAccess: true });
+    try {
+      await expectRejected(
+        escrowed,
+        () =>
+          program.methods
+            .executeSwap(escrowed.transactionId, leg.venueData)
+            .accounts(swapAccounts(escrowed, TOKEN_PROGRAM_ID))
+            .remainingAccounts(leg.venueAccounts)
+            .preInstructions([topUp])
+            .signers([owner])
+            .rpc(),
+        "PoolAccessedBeforeSwap"
+      );
+    } finally {
+      await setGuard(noGuard);
+    }
+  });
+
+  it("Require the price feed and mints when the guard sets an oracle check", async () => {
+    const oracleGuard = {
+      ...noGuard,
+      oracle: {
+        maxAgeSecs: 60,
+        maxConfidenceBps: 50,
+        maxDeviationBps: 100,
+      },
+    };
+    const leg = transferLeg(swap, 1_000_000);
+    await setGuard(oracleGuard);
+
+    // The swap itself succeeds, then the whole transaction reverts
+    try {
+      await expectRejected(
+        swap,
+        () =>
+          program.methods
+            .executeSwap(swap.transactionId, leg.venueData)
+            .accounts(swapAccounts(swap, TOKEN_PROGRAM_ID))
+            .remainingAccounts(leg.venueAccounts)
+            .signers([owner])
+            .rpc(),
+        "MissingOracleAccounts"
+      );
+    } finally {
+      await setGuard(noGuard);
+    }
+  });
+
+  it("Reject a swap whose mint pair has no bound feed", async () => {
+    const oracleGuard = {
+      ...noGuard,
+      oracle: { maxAgeSecs: 60, maxConfidenceBps: 50, maxDeviationBps: 100 },
+    };
+    const leg = transferLeg(swap, 1_000_000);
+    await setGuard(oracleGuard);
+
+    // The price account is never read without a feed bound to the mints
+    try {
+      await expectRejected(
+        swap,
+        () =>
+          program.methods
+            .executeSwap(swap.transactionId, leg.venueData)
+            .accounts({
+              ...swapAccounts(swap, TOKEN_PROGRAM_ID),
+              priceFeed: inputMint,
+              inputMint,
+              outputMint: inputMint,
+            })
+            .remainingAccounts(leg.venueAccounts)
+            .signers([owner])
+            .rpc(),
+        "UnknownPriceFeed"
+      );
+    } finally {
+      await setGuard(noGuard);
+    }
+  });
+
+  it("Reject an execution guard set by anyone but the owner", async () => {
+    try {
+      await protectionManager.methods
+        .updateExecutionGuard(noGuard)
+        .accounts({
+          protectionAccount: protectionAccount,
+          payer: stranger.publicKey,
+        })
+        .signers([stranger])
+        .rpc();
+
+      // Should not reach here
+      expect.fail("Expected unauthorized error");
+    } catch (error) {
+      expect(error.message).to.include("Unauthorized");
+    }
+  });
+
+  it("Reject an oracle deviation bound over 100%", async () => {
+    const oracle = { maxAgeSecs: 60, maxConfidenceBps: 50, maxDeviationBps: 10_001 };
+
+    await setGuard({ ...noGuard, oracle }).then(
+      () => expect.fail("Expected invalid execution guard error"),
+      (error) => expect(error.message).to.include("InvalidExecutionGuard")
+    );
+    const account = await protectionManager.account.protectionAccount.fetch(protectionAccount);
+    expect(account.settings.executionGuard.oracle).to.be.null;
+  });
+
+  it("Reject an intermediate account the escrow does not own", async () => {
+    // The first hop's output must land in an escrow-owned account
+    const intermediate = await createAccount(
+      provider.connection,
+      payer,
+      usdcMint,
+      stranger.publicKey,
+      anchor.web3.Keypair.generate()
+    );
+    const intermediateRefund = await createAccount(
+      provider.connection,
+      payer,
+      usdcMint,
+      owner.publicKey,
+      anchor.web3.Keypair.generate()
+    );
+    const hops = [
+      {
+        hop: { venue: raydiumProgram, pool: raydiumPool, inputMint, outputMint: usdcMint },
+        minOutput: new anchor.BN(140000),
+        venueAccountCount: 14,
+        venueData: Buffer.alloc(0),
+      },
+      {
+        hop: { venue: whirlpoolProgram, pool: whirlpool, inputMint: usdcMint, outputMint },
+        minOutput: new anchor.BN(950000),
+        venueAccountCount: 7,
+        venueData: Buffer.alloc(0),
+      },
+    ];
+
+    await expectRejected(
+      pair,
+      () =>
+        program.methods
+          .executeRoute(pair.transactionId, hops)
+          .accounts(routeAccounts(pair))
+          .remainingAccounts([
+            venueAccount(raydiumProgram),
+            programAccount(raydiumProgram),
+            { pubkey: intermediate, isSigner: false, isWritable: true },
+            { pubkey: intermediateRefund, isSigner: false, isWritable: true },
+            ...venueAccounts(14, raydiumPool),
+            venueAccount(whirlpoolProgram),
+            programAccount(whirlpoolProgram),
+            ...venueAccounts(7, whirlpool),
+          ])
+          .signers([owner])
+          .rpc(),
+      "InvalidRouteData"
+    );
+  });
+
+  it("Reject a route whose hops do not chain", async () => {
+    const hops = [
+      {
+        hop: {
+          venue: raydiumProgram,
+          pool: anchor.web3.Keypair.generate().publicKey,
+          inputMint,
+          outputMint: anchor.web3.Keypair.generate().publicKey,
+        },
+        minOutput: new anchor.BN(140000),
+        venueAccountCount: 14,
+        venueData: Buffer.alloc(0),
+      },
+      {
+        hop: {
+          venue: whirlpoolProgram,
+          pool: anchor.web3.Keypair.generate().publicKey,
+          inputMint: anchor.web3.Keypair.generate().publicKey,
+          outputMint,
+        },
+        minOutput: new anchor.BN(950000),
+        venueAccountCount: 7,
+        venueData: Buffer.alloc(0),
+      },
+    ];
+    const routed = await submitEscrowed(
+      inputMint,
+      outputMint,
+      1_000_000,
+      950_000,
+      multiVenueRoute(hops.map(({ hop }) => hop))
+    );
+
+    await expectRejected(
+      routed,
+      () =>
+        program.methods
+          .executeRoute(routed.transactionId, hops)
+          .accounts(routeAccounts(routed))
+          .signers([owner])
+          .rpc(),
+      "InvalidRouteData"
+    );
+  });
+
+  it("Reject a route other than the one the transaction selected", async () => {
+    // The transaction selected Raydium then Orca through USDC, not Raydium alone
+    const hops = [
+      {
+        hop: { venue: raydiumProgram, pool: raydiumPool, inputMint, outputMint },
+        minOutput: new anchor.BN(950000),
+        venueAccountCount: 14,
+        venueData: Buffer.alloc(0),
+      },
+    ];
+
+    await expectRejected(
+      pair,
+      () =>
+        program.methods
+          .executeRoute(pair.transactionId, hops)
+          .accounts(routeAccounts(pair))
+          .remainingAccounts([
+            venueAccount(raydiumProgram),
+            programAccount(raydiumProgram),
+            ...venueAccounts(14, raydiumPool),
+          ])
+          .signers([owner])
+          .rpc(),
+      "RouteNotSelected"
+    );
+  });
+
+  it("Execute a split route across two legs", async () => {
+    const escrowed = await submitEscrowed(inputMint, inputMint, 1_000_004, 1_000_004, null);
+    // 60% and 40% of the input, the rounding remainder going to the last leg
+    const legs = [600_002, 400_002].map((amount) => transferLeg(escrowed, amount));
+    const splits = [6000, 4000].map((weightBps, index) => ({
+      weightBps,
+      swap: {
+        hop: {
+          venue: TOKEN_PROGRAM_ID,
+          pool: escrowed.inputEscrow,
+          inputMint,
+          outputMint: inputMint,
+        },
+        minOutput: new anchor.BN(0),
+        venueAccountCount: 3,
+        venueData: legs[index].venueData,
+      },
+    }));
+    // The legs swap out of the escrow, so the route is selected once it exists
+    await selectRoute(
+      escrowed.transactionAccount,
+      escrowed.transactionId,
+      multiVenueRoute(
+        splits.map(({ swap }) => swap.hop),
+        splits.map(({ weightBps }) => weightBps)
+      )
+    );
+
+    const tx = await program.methods
+      .executeSplitRoute(escrowed.transactionId, splits)
+      .accounts(routeAccounts(escrowed))
+      .remainingAccounts(
+        legs.flatMap((leg) => [
+          venueAccount(TOKEN_PROGRAM_ID),
+          programAccount(TOKEN_PROGRAM_ID),
+          ...leg.venueAccounts,
+        ])
+      )
+      .signers([owner])
+      .rpc();
+
+    console.log("Execute split route transaction signature", tx);
+
+    expect(await balances(escrowed)).to.deep.equal({ escrow: 0, destination: 1_000_004 });
+    const execution = await program.account.routeExecution.fetch(
+      routeExecutionPda(escrowed, Buffer.from("route"))
+    );
+    expect(execution.hops.map((hop) => hop.inputAmount.toNumber())).to.deep.equal([
+      600_002, 400_002,
+    ]);
+    expect(execution.splitWeightsBps).to.deep.equal([6000, 4000]);
+  });
+
+  it("Execute a multi-hop route and return what the hops leave to the owner", async () => {
+    const midMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const finalMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    const escrowed = await submitEscrowed(
+      inputMint,
+      finalMint,
+      1_000_008,
+      900_000,
+      multiVenueRoute([
+        { venue: TOKEN_PROGRAM_ID, pool: midMint, inputMint, outputMint: midMint },
+        { venue: TOKEN_PROGRAM_ID, pool: finalMint, inputMint: midMint, outputMint: finalMint },
+      ])
+    );
+    for (const mint of [midMint, finalMint]) {
+      await setAuthority(
+        provider.connection,
+        payer,
+        mint,
+        payer,
+        AuthorityType.MintTokens,
+        escrowed.escrowAuthority
+      );
+    }
+    const intermediate = await createAccount(
+      provider.connection,
+      payer,
+      midMint,
+      escrowed.escrowAuthority,
+      anchor.web3.Keypair.generate()
+    );
+    const intermediateRefund = await createAccount(
+      provider.connection,
+      payer,
+      midMint,
+      owner.publicKey,
+      anchor.web3.Keypair.generate()
+    );
+
+    // Mint `amount` of the hop's output into `destination`, signed by the
+    // escrow authority inside the executor
+    const mintLeg = (
+      mint: anchor.web3.PublicKey,
+      source: anchor.web3.PublicKey,
+      destination: anchor.web3.PublicKey,
+      amount: number
+    ) => ({
+      venueData: createMintToInstruction(mint, destination, escrowed.escrowAuthority, amount)
+        .data,
+      venueAccounts: [
+        { pubkey: mint, isSigner: false, isWritable: true },
+        { pubkey: destination, isSigner: false, isWritable: true },
+        { pubkey: escrowed.escrowAuthority, isSigner: false, isWritable: false },
+        { pubkey: source, isSigner: false, isWritable: true },
+      ],
+    });
+    const legs = [
+      mintLeg(midMint, escrowed.inputEscrow, intermediate, 500_000),
+      mintLeg(finalMint, intermediate, escrowed.outputTokenAccount, 950_000),
+    ];
+    const hops = [
+      { pool: midMint, inputMint, outputMint: midMint, minOutput: 500_000 },
+      { pool: finalMint, inputMint: midMint, outputMint: finalMint, minOutput: 950_000 },
+    ].map(({ minOutput, ...hop }, index) => ({
+      hop: { venue: TOKEN_PROGRAM_ID, ...hop },
+      minOutput: new anchor.BN(minOutput),
+      venueAccountCount: 4,
+      venueData: legs[index].venueData,
+    }));
+
+    const setLayout = (layout: object) =>
+      program.methods
+        .updateVenue({ passthrough: { layout } }, true)
+        .accounts({
+          venueRegistry,
+          venue: venuePda(TOKEN_PROGRAM_ID),
+          admin: provider.wallet.publicKey,
+        })
+        .rpc();
+    await setLayout(tokenMintTo);
+
+    try {
+      const tx = await program.methods
+        .executeRoute(escrowed.transactionId, hops)
+        .accounts(routeAccounts(escrowed))
+        .remainingAccounts([
+          venueAccount(TOKEN_PROGRAM_ID),
+          programAccount(TOKEN_PROGRAM_ID),
+          { pubkey: intermediate, isSigner: false, isWritable: true },
+          { pubkey: intermediateRefund, isSigner: false, isWritable: true },
+          ...legs[0].venueAccounts,
+          venueAccount(TOKEN_PROGRAM_ID),
+          programAccount(TOKEN_PROGRAM_ID),
+          ...legs[1].venueAccounts,
+        ])
+        .signers([owner])
+        .rpc();
+
+      console.log("Execute multi-hop route transaction signature", tx);
+    } finally {
+      await setLayout(tokenTransfer);
+    }
+
+    const execution = await program.account.routeExecution.fetch(
+      routeExecutionPda(escrowed, Buffer.from("route"))
+    );
+    expect(execution.hops.map((hop) => hop.outputAmount.toNumber())).to.deep.equal([
+      500_000, 950_000,
+    ]);
+    expect(execution.outputAmount.toNumber()).to.equal(950_000);
+
+    // Neither hop spent its input, so the escrowed input and the
+    // intermediate output both went back to the owner
+    expect(await balances(escrowed)).to.deep.equal({ escrow: 0, destination: 950_000 });
+    expect(await balance(escrowed.refundTokenAccount)).to.equal(1_000_008);
+    expect(await balance(intermediate)).to.equal(0);
+    expect(await balance(intermediateRefund)).to.equal(500_000);
+  });
+
+  it("Reject split weights that do not cover the whole input", async () => {
+    const splits = [6000, 3000].map((weightBps) => ({
+      weightBps,
+      swap: {
+        hop: { venue: raydiumProgram, pool: raydiumPool, inputMint, outputMint },
+        minOutput: new anchor.BN(0),
+        venueAccountCount: 14,
+        venueData: Buffer.alloc(0),
+      },
+    }));
+    const routed = await submitEscrowed(
+      inputMint,
+      outputMint,
+      1_000_000,
+      950_000,
+      multiVenueRoute(
+        splits.map(({ swap }) => swap.hop),
+        splits.map(({ weightBps }) => weightBps)
+      )
+    );
+
+    await expectRejected(
+      routed,
+      () =>
+        program.methods
+          .executeSplitRoute(routed.transactionId, splits)
+          .accounts(routeAccounts(routed))
+          .signers([owner])
+          .rpc(),
+      "InvalidRouteData"
+    );
+  });
+
   it("Handle empty routes error", async () => {
     try {
       await program.methods
         .selectBestRoute([])
         .accounts({
-          transactionAccount: anchor.web3.Keypair.generate().publicKey,
+          transactionAccount: pair.transactionAccount,
+          scoringConfig,
         })
         .rpc();
 
@@ -151,6 +1522,44 @@ describe("zephyra-route-executor", () => {
       expect(error.message).to.include("NoRoutesProvided");
     }
   });
-});
 
+  it("Refund the escrow to its owner after a cancellation", async () => {
+    const escrowed = await submitEscrowed(
+      inputMint,
+      inputMint,
+      1_000_007,
+      1_000_007,
+      venueRoute(TOKEN_PROGRAM_ID)
+    );
+    await protectionManager.methods
+      .cancelTransaction(Array.from(escrowed.transactionId))
+      .accounts({
+        protectionAccount: protectionAccount,
+        transactionAccount: escrowed.transactionAccount,
+        depositor: owner.publicKey,
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+
+    const refund = (signer: anchor.web3.Keypair) =>
+      program.methods
+        .refundEscrow(escrowed.transactionId)
+        .accounts({
+          escrowAuthority: escrowed.escrowAuthority,
+          escrowTokenAccount: escrowed.inputEscrow,
+          destination: escrowed.refundTokenAccount,
+          owner: signer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    // The escrow PDA is derived from the owner, so nobody else can sign with it
+    await expectRejected(escrowed, () => refund(stranger), "ConstraintSeeds");
 
+    await refund(owner);
+    expect(await balance(escrowed.refundTokenAccount)).to.equal(1_000_007);
+    expect(await provider.connection.getAccountInfo(escrowed.inputEscrow)).to.be.null;
+  });
+});